@@ -8,6 +8,9 @@
 //!     "floor: 74\nbasement index: 1795"));
 //! ```
 
+use crate::{process_file_param, Params};
+use std::io::{self, BufRead};
+
 const INPUT: &str = include_str!("input");
 
 /// example answer 3, and ??
@@ -19,13 +22,14 @@ const INPUT: &str = include_str!("input");
 const EXAMPLE_INPUT: &str = "(()(()(";
 
 pub fn process_input(input: &str) -> String {
-    let parens: &str = input.trim();
-    let ups: i32 = parens.matches('(').count() as i32;
-    let downs: i32 = parens.len() as i32 - ups;
+    // whitespace is skipped, like process_reader does:
+    let parens = || input.chars().filter(|char| !char.is_whitespace());
+    let ups: i32 = parens().filter(|&char| char == '(').count() as i32;
+    let downs: i32 = parens().count() as i32 - ups;
     let floor = ups - downs;
     let mut current_floor = 0;
     let mut basement_index = 0;
-    for char in parens.chars() {
+    for char in parens() {
         basement_index += 1;
         current_floor += match char {
             '(' => 1,
@@ -39,6 +43,63 @@ pub fn process_input(input: &str) -> String {
     format!("floor: {}\nbasement index: {}", floor, basement_index)
 }
 
+/// Same as `process_input`, but reads the input in chunks, so it can be of any size.
+/// Whitespace is skipped, any byte other than parentheses is an error.
+/// ```
+/// use advent_of_code_202x::generated::year2015day01::{process_input, process_reader};
+/// use std::io::BufReader;
+/// let input = "()()\n)((\n";
+/// // a tiny buffer, to cross chunk boundaries:
+/// let reader = BufReader::with_capacity(2, input.as_bytes());
+/// assert_eq!(process_reader(reader).unwrap(), process_input(input));
+/// assert!(process_reader("(x)".as_bytes()).is_err());
+/// ```
+pub fn process_reader(mut reader: impl BufRead) -> io::Result<String> {
+    let (mut floor, mut index) = (0i64, 0u64);
+    let mut basement_index = None;
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        for &byte in chunk {
+            floor += match byte {
+                b'(' => 1,
+                b')' => -1,
+                _ if byte.is_ascii_whitespace() => continue,
+                _ => {
+                    let message =
+                        format!("unexpected {:?} after {} parentheses", byte as char, index);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+            };
+            index += 1;
+            if floor < 0 && basement_index.is_none() {
+                basement_index = Some(index);
+            }
+        }
+        let len = chunk.len();
+        reader.consume(len);
+    }
+    // like process_input, count all parentheses if the basement is never reached:
+    let basement_index = basement_index.unwrap_or(index);
+    Ok(format!(
+        "floor: {}\nbasement index: {}",
+        floor, basement_index
+    ))
+}
+
+/// With `file=path`, stream the input from a file of any size
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    process_file_param(params, process_reader).unwrap_or_else(|| {
+        if use_example {
+            run_example()
+        } else {
+            run()
+        }
+    })
+}
+
 pub fn run_example() -> String {
     process_input(EXAMPLE_INPUT)
 }
@@ -26,8 +26,8 @@ nearby tickets:
 38,6,12
 ";
 
+use crate::interval_set::IntervalSet;
 use std::collections::HashMap;
-use std::ops::Range;
 
 type Ticket = Vec<u32>;
 
@@ -35,50 +35,37 @@ fn split_int(in_string: &str) -> Ticket {
     in_string.split(',').map(|i| i.parse().unwrap()).collect()
 }
 
-type TwoRanges = (Range<u32>, Range<u32>);
-
-fn split_range_spec(in_string: &str) -> (&str, TwoRanges) {
-    let pairs: Vec<&str> = in_string.split(": ").collect();
-    let ranges: Vec<Vec<&str>> = pairs[1]
-        .split(" or ")
-        .map(|a| a.split('-').collect())
-        .collect();
-    let mut ranges: Vec<Range<u32>> = ranges
-        .iter()
-        .map(|a: &Vec<&str>| -> Range<u32> {
-            Range {
-                start: a[0].parse().unwrap(),
-                end: a[1].parse::<u32>().unwrap() + 1,
-            }
-        })
-        .collect();
-    (pairs[0], (ranges.remove(0), ranges.remove(0)))
+/// Parse `name: 1-3 or 5-7`, the ranges are inclusive
+fn split_range_spec(in_string: &str) -> (&str, IntervalSet<u32>) {
+    let (name, ranges) = in_string.split_once(": ").unwrap();
+    let ranges = ranges.split(" or ").map(|range| {
+        let (start, end) = range.split_once('-').unwrap();
+        start.parse().unwrap()..end.parse::<u32>().unwrap() + 1
+    });
+    (name, ranges.collect())
 }
 
 pub fn process_input(input: &str) -> String {
     let input: Vec<&str> = input.split("\n\n").map(|a| a.trim()).collect();
-    let field_specs: HashMap<&str, TwoRanges> =
+    let field_specs: HashMap<&str, IntervalSet<u32>> =
         input[0].split('\n').map(split_range_spec).collect();
-    let my_ticket = split_int(input[1].split('\n').last().unwrap().trim());
+    let my_ticket = split_int(input[1].split('\n').next_back().unwrap().trim());
     let nearby_tickets: Vec<Ticket> = input[2].trim().split('\n').skip(1).map(split_int).collect();
-    //println!("field_specs:\n{:?}\n", field_specs);
-    //println!("my_ticket:\n{:?}\n", my_ticket);
-    //println!("nearby_tickets:\n{:?}\n", nearby_tickets);
+    // all values valid for any field, so checking a value is one binary search:
+    let any_field = field_specs
+        .values()
+        .fold(IntervalSet::new(), |any, spec| any.union(spec));
     // Check all tickets for impossible fields and keep only valid tickets:
     let mut ticket_s_err_rate = 0;
     let mut valid_tickets: Vec<&Ticket> = Vec::new();
     for ticket in &nearby_tickets {
-        let mut valid = true;
-        for &field in ticket {
-            let valid_field = field_specs
-                .values()
-                .find(|(r1, r2)| r1.contains(&field) || r2.contains(&field));
-            if valid_field.is_none() {
-                valid = false;
-                ticket_s_err_rate += field;
-            }
-        }
-        if valid {
+        let invalid: Vec<u32> = ticket
+            .iter()
+            .copied()
+            .filter(|field| !any_field.contains(field))
+            .collect();
+        ticket_s_err_rate += invalid.iter().sum::<u32>();
+        if invalid.is_empty() {
             valid_tickets.push(ticket);
         }
     }
@@ -89,10 +76,7 @@ pub fn process_input(input: &str) -> String {
     // iterate over all tickets to only retain the possible options
     for ticket in valid_tickets {
         for (field, options) in ticket.iter().zip(field_options.iter_mut()) {
-            options.retain(|&o| {
-                let field_spec = &field_specs[o];
-                field_spec.0.contains(field) || field_spec.1.contains(field)
-            });
+            options.retain(|&o| field_specs[o].contains(field));
         }
     }
     //println!("field options after first round: {:?}", field_options);
@@ -1,10 +1,14 @@
 //! https://adventofcode.com/2020/day/18
 //! parse numeric expression with parenthesis, plus and times.
 //! the operators have the same precedence so evaluate left to right!
+//! part 2: plus has higher precedence than times. Both are just operator tables for the expr module.
 //!
 //! ```
-//! use advent_of_code_202x::generated::year2020day18::run;
+//! use advent_of_code_202x::generated::year2020day18::{process_input, run};
 //! assert!(run().contains("Sum part 1: 45283905029161\nSum part 2: 216975281211165"));
+//! // multi-digit numbers work, malformed lines are reported:
+//! assert!(process_input("12 * 10 + 3").contains("Sum part 1: 123\nSum part 2: 156"));
+//! assert_eq!(process_input("1 + 2\n(3 * 4"), "Invalid expression in line 2: unexpected end of expression");
 //! ```
 
 const INPUT: &str = include_str!("input");
@@ -25,114 +29,48 @@ const EXAMPLE_INPUT: &str = "
 (2 * (2 * 3)) + 4
 ";
 
-use std::iter::Peekable;
+use crate::expr::{Assoc, BinOp, ExprError, OpTable};
 
-fn parse_expr(token_stream: &mut impl Iterator<Item = char>) -> Vec<char> {
-    let mut polish: Vec<char> = Vec::new();
-    while let Some(token) = token_stream.next() {
-        match token {
-            '+' | '*' => polish.insert(0, token),
-            '(' => polish.extend(parse_expr(token_stream)),
-            ')' => return polish,
-            _ => polish.push(token),
-        }
-    }
-    polish
-}
-
-/// parse with reversed operator precedence from normal:
-fn parse_expr_op_prec(token_stream: &mut Peekable<impl Iterator<Item = char>>) -> Vec<char> {
-    parse_expr_lhs(parse_primary(token_stream), token_stream)
-}
-
-/// parse primary expr, either a number or a parenthesized expr
-fn parse_primary(token_stream: &mut Peekable<impl Iterator<Item = char>>) -> Vec<char> {
-    if let Some(token) = token_stream.next() {
-        //println!("primary {}", token);
-        match token {
-            '(' => {
-                let parsed = parse_expr_lhs(parse_primary(token_stream), token_stream);
-                if let Some(')') = token_stream.next() {
-                    return parsed;
-                } else {
-                    panic!("Parenthesis was not balanced!")
-                }
-            }
-            '+' | '*' | ')' => panic!(
-                "Operator or closing paren at beginning of primary expr should never happen!"
-            ),
-            _ => return vec![token],
-        }
-    }
-    panic!("No primary expression to parse!");
+/// part 1: plus and times have the same precedence, evaluate left to right
+fn equal_precedence() -> OpTable {
+    OpTable::new()
+        .with('+', BinOp::Add, 1, Assoc::Left)
+        .with('*', BinOp::Mul, 1, Assoc::Left)
 }
 
-/// parse an expr with an operator, lhs already parsed, continue while multiplicative
-fn parse_expr_lhs(
-    mut lhs: Vec<char>, //min_precedence,
-    token_stream: &mut Peekable<impl Iterator<Item = char>>,
-) -> Vec<char> {
-    let mut rhs: Vec<char>;
-    while let Some(&token) = token_stream.peek() {
-        if token == ')' {
-            return lhs;
-        }
-        token_stream.next(); // consume peeked token
-                             //println!("operator {} (lhs: {:?})", token, lhs);
-        match token {
-            '+' => rhs = parse_primary(token_stream),
-            '*' => rhs = parse_expr_lhs(parse_primary(token_stream), token_stream),
-            '(' | ')' => panic!("Opening parenthesis inside expr should never happen!"),
-            _ => panic!("Number inside expr should never happen!"),
-        }
-        lhs.insert(0, token);
-        lhs.extend(rhs);
-        //println!("lhs now: {:?}", lhs);
-    }
-    lhs
+/// part 2: plus binds tighter than times (reversed from normal)
+fn plus_before_times() -> OpTable {
+    OpTable::new()
+        .with('+', BinOp::Add, 2, Assoc::Left)
+        .with('*', BinOp::Mul, 1, Assoc::Left)
 }
 
-#[allow(clippy::ptr_arg)]
-fn eval_expr(p_expr: &Vec<char>) -> u64 {
-    let mut operand_stack: Vec<u64> = Vec::new();
-    for &op in p_expr.iter().rev() {
-        match op {
-            '+' => {
-                let op1 = operand_stack.pop().unwrap();
-                let op2 = operand_stack.pop().unwrap();
-                operand_stack.push(op1 + op2)
-            }
-            '*' => {
-                let op1 = operand_stack.pop().unwrap();
-                let op2 = operand_stack.pop().unwrap();
-                operand_stack.push(op1 * op2)
-            }
-            _ => operand_stack.push(op.to_digit(10).unwrap().into()),
-        }
-    }
-    operand_stack.pop().unwrap()
+/// Evaluate each line with the given operator table and sum the results,
+/// the error reports the (1-based) line number
+fn sum_evaluated(input: &str, table: &OpTable) -> Result<u64, String> {
+    input
+        .lines()
+        .enumerate()
+        .try_fold(0u64, |sum, (index, line)| {
+            table
+                .eval(line)
+                .and_then(|value| sum.checked_add(value).ok_or(ExprError::Overflow))
+                .map_err(|err| format!("line {}: {}", index + 1, err))
+        })
 }
 
 pub fn process_input(input: &str) -> String {
-    let input = input.trim().split('\n');
-    //println!("input:\n{:?}\n", input);
-    let token_streams = input.map(|a| a.chars().filter(|&c| c != ' '));
-    // TODO: there should be a nicer way that doesn't create any intermediate Vec
-    // but just passes through the initial chars in a different order...
-    let mut parsed_exprs = Vec::new();
-    let mut parsed_exprs2 = Vec::new();
-    for ts in token_streams.into_iter() {
-        parsed_exprs.push(parse_expr(&mut ts.clone()));
-        parsed_exprs2.push(parse_expr_op_prec(&mut ts.into_iter().peekable()));
-        //        parsed_exprs2.push(parse_primary(&mut ts.into_iter().peekable()));
+    let input = input.trim();
+    match (
+        sum_evaluated(input, &equal_precedence()),
+        sum_evaluated(input, &plus_before_times()),
+    ) {
+        (Ok(evaluated_sum_1), Ok(evaluated_sum_2)) => format!(
+            "Sum part 1: {}\nSum part 2: {}",
+            evaluated_sum_1, evaluated_sum_2,
+        ),
+        (Err(err), _) | (_, Err(err)) => format!("Invalid expression in {}", err),
     }
-    let evaluated_sum_1: u64 = parsed_exprs.iter().map(eval_expr).sum();
-    //println!("Parsed: {:?}", parsed_exprs2);
-    let evaluated_sum_2: u64 = parsed_exprs2.iter().map(eval_expr).sum();
-    format!(
-        "Sum part 1: {}\nSum part 2: {}",
-        evaluated_sum_1, evaluated_sum_2,
-    )
 }
 
 pub fn run_example() -> String {
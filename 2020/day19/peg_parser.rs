@@ -1,11 +1,8 @@
 //! https://adventofcode.com/2020/day/19
 //! parse ab strings based on a grammar that only uses the sequence and choice operators.
 //! This is probably meant to be done with just recursive descent parsing,
-//! but I would like to use it to implement a PEG parser with memoization,
-//! i.e. a packrat parser.
-//! (Maybe later also implement left-recursion like in this paper:
-//! https://web.cs.ucla.edu/~todd/research/pepm08.pdf
-//! this is also roughly what's implemented in python's new peg parser.)
+//! but part 1 translates the rules for the packrat parser of the peg module,
+//! which also reads the rules themselves.
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2020day19::run;
@@ -84,130 +81,89 @@ babaaabbbaaabaababbaabababaaab
 aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba
 ";
 
-use std::collections::{HashMap, HashSet};
-
-type PosSet = HashSet<usize>;
-
-#[derive(Debug)]
-struct ParseRun<'a> {
-    rules: &'a HashMap<&'a str, &'a str>,
-    msg: &'a str,
-    memo: HashMap<(&'a str, usize), PosSet>,
-}
-
-impl<'a> ParseRun<'a> {
-    fn new(rules: &'a HashMap<&'a str, &'a str>, msg: &'a str) -> ParseRun<'a> {
-        ParseRun {
-            rules,
-            msg,
-            memo: HashMap::new(),
-        }
-    }
-
-    /// return true if the rule matches the whole msg:
-    fn parse(&mut self, rule: &'a str) -> bool {
-        let target_len = self.msg.len();
-        return self
-            .apply_rule(rule, 0)
-            .iter()
-            .any(|&pos| target_len == pos);
-    }
-
-    /// return a set of possible new positions after applying the rule
-    /// returns an empty set if no match is possible
-    fn apply_rule(&mut self, rule: &'a str, at_pos: usize) -> PosSet {
-        //println!("apply_rule {} at pos {} (self.pos {})", rule, at_pos, self.pos);
-        if let Some(answer) = self.memo.get(&(rule, at_pos)) {
-            //println!("found memoized {} (setting self.pos {} to {})", answer, self.pos, position);
-            return answer.clone();
-        }
-        let answer = self.eval_body(self.rules[rule], at_pos);
-        let result = answer.clone();
-        self.memo.insert((rule, at_pos), answer);
-        result
-    }
-
-    /// All rule bodies have the form: a [b] [ | c d ]
-    /// so possibly a choice, with both options being a sequence,
-    /// any entry could be a rule name or a literal char "a"
-    /// return a set of all possible next scanning positions (empty if no match)
-    fn eval_body(&mut self, body: &'a str, at_pos: usize) -> PosSet {
-        //println!("eval_body {}", body);
-        let mut matched = PosSet::new();
-        for option in body.split(" | ") {
-            //println!("Checking option: {}", option);
-            let mut current_positions = PosSet::new();
-            current_positions.insert(at_pos);
-            for seq_elem in option.split(' ') {
-                //println!("Checking sequence element: {}", seq_elem);
-                if seq_elem.len() == 3 && seq_elem.starts_with('"') && seq_elem.ends_with('"') {
-                    let to_match = seq_elem.chars().nth(1);
-                    // advance all positions that match:
-                    current_positions = current_positions
-                        .iter()
-                        .filter(|&&pos| to_match == self.msg.chars().nth(pos))
-                        .map(|&pos| pos + 1)
-                        .collect();
-                    if current_positions.is_empty() {
-                        break; // sequence failed
-                    }
-                } else {
-                    // it is another rule:
-                    current_positions = current_positions
-                        .iter()
-                        .map(|&pos| self.apply_rule(seq_elem, pos))
-                        .fold(PosSet::new(), |hs, next_hs| {
-                            hs.union(&next_hs).cloned().collect()
-                        });
-                    if current_positions.is_empty() {
-                        break; // sequence failed
-                    }
-                }
-            }
-            if !current_positions.is_empty() {
-                assert!(!current_positions.contains(&at_pos));
-                matched = matched.union(&current_positions).cloned().collect();
-                // a true PEG parser would return here on the first option that already succeeded,
-                // but to allow ambiguous rules, we will continue here
-            }
-        }
-        matched
+use crate::grammar::Grammar;
+use crate::peg::{CaptureError, Peg};
+use itertools::Itertools;
+use std::error::Error;
+
+/// The puzzle rules, as a PEG of their own
+const RULES_SYNTAX: &str = r#"
+rules <- rule:rule ("\n" rule:rule)*
+rule <- name:number ": " (literal:('"' [a-z] '"') / sequence:sequence (" | " sequence:sequence)*)
+sequence <- part:number (" " part:number)*
+number <- [0-9]+
+"#;
+
+/// Translate the puzzle rules to a PEG, rule `n` becomes `rn`.
+/// PEG choices commit to the first alternative that matches, which is only the same as
+/// the puzzle's choices because all rules of part 1 match strings of a fixed length.
+fn translate_rules(rules: &str) -> Result<String, Box<dyn Error>> {
+    let parsed = Peg::new(RULES_SYNTAX)?.parse("rules", rules)?;
+    let mut translated = String::new();
+    for rule in parsed.all("rule") {
+        let name: usize = rule.value("name")?;
+        let body = match rule.text_of("literal") {
+            Some(literal) => literal.to_owned(),
+            None => rule
+                .all("sequence")
+                .map(|sequence| {
+                    let parts: Vec<usize> = sequence.values("part")?;
+                    Ok(parts.iter().map(|part| format!("r{}", part)).join(" "))
+                })
+                .collect::<Result<Vec<_>, CaptureError>>()?
+                .join(" / "),
+        };
+        translated += &format!("r{} <- {}\n", name, body);
     }
+    Ok(translated)
 }
 
 // now the choice of PEG parsing is biting me,
 // as these rules, especially the 8 rule seem to be ambiguous,
 // and thus need backtracking...
+// the Earley recognizer of the grammar module handles any modification,
+// even left-recursive ones like `8: 42 | 8 42`
 const PART2_MODIFICATION: &str = "
 8: 42 | 42 8
 11: 42 31 | 42 11 31
 ";
 
-fn rule_splitter(r: &str) -> (&str, &str) {
-    // very verbose, next time try itertools::next_tuple
-    match r.split(':').collect::<Vec<&str>>()[..] {
-        [name, rule, ..] => (name, rule.trim()),
-        _ => panic!("No rule found!"),
-    }
+/// Return the compiled grammar and the messages
+fn parse_input(input: &str) -> (Grammar, Vec<&str>) {
+    let (rules, messages) = input.trim().split_once("\n\n").unwrap();
+    (Grammar::parse(rules).unwrap(), messages.lines().collect())
+}
+
+/// Count the messages matching rule 0 after replacing rules with the modification,
+/// which may be left-recursive
+/// ```
+/// use advent_of_code_202x::generated::year2020day19::count_matches_modified;
+/// let input = "0: 8 11\n8: 42\n11: 42 31\n42: \"a\"\n31: \"b\"\n\naab\naaabb\nab\nba";
+/// assert_eq!(count_matches_modified(input, ""), 1);
+/// assert_eq!(count_matches_modified(input, "8: 42 | 8 42\n11: 42 31 | 42 11 31"), 2);
+/// ```
+pub fn count_matches_modified(input: &str, modification: &str) -> usize {
+    let (mut grammar, messages) = parse_input(input);
+    grammar.add_rules(modification).unwrap();
+    messages
+        .iter()
+        .filter(|&&msg| grammar.recognize("0", msg))
+        .count()
 }
 
 pub fn process_input(input: &str) -> String {
-    let input: Vec<_> = input.trim().split("\n\n").collect();
-    let mut rules: HashMap<&str, &str> = input[0].split('\n').map(rule_splitter).collect();
-    let messages: Vec<_> = input[1].split('\n').collect();
-    //println!("rules:\n{:?}\nmessages:\n{:?}", rules, messages);
-    // create a parser class that knows the rules and manages memoizing:
+    let (mut grammar, messages) = parse_input(input);
     // try applying rule 0 for each message and count successes:
+    let (rules, _) = input.trim().split_once("\n\n").unwrap();
+    let peg = Peg::new(&translate_rules(rules).unwrap()).unwrap();
     let matched_messages_count = messages
         .iter()
-        .filter(|&&msg| ParseRun::new(&rules, msg).parse("0"))
+        .filter(|&&msg| peg.matches("r0", msg))
         .count();
-    for (rule, body) in PART2_MODIFICATION.trim().split('\n').map(rule_splitter) {
-        rules.insert(rule, body);
-    }
+    grammar.add_rules(PART2_MODIFICATION).unwrap();
     let matched_messages: Vec<&&str> = messages
         .iter()
-        .filter(|&&msg| ParseRun::new(&rules, msg).parse("0"))
+        .filter(|&&msg| grammar.recognize("0", msg))
         .collect();
     format!(
         "Number of matches: {}\nPart 2: {} matches: {:?}",
@@ -1,6 +1,10 @@
 //! https://adventofcode.com/2020/day/20
 //! Match image tiles based on their borders
 //!
+//! The tiles are assembled into any rectangular layout by backtracking, trying the tiles (in all
+//! 8 orientations) whose edges fit the left and upper neighbours, so ambiguous edges are fine.
+//! The image can be written as PBM or PNG, e.g. `cargo run 20 2020 out=sea.png borders=true`
+//!
 //! ```
 //! use advent_of_code_202x::generated::year2020day20::run;
 //! assert!(run().contains(
@@ -131,365 +135,412 @@ Tile 3079:
 ..#.###...
 ";
 
-// PART 1 solved without representing the tiles, just focusing on edges:
-
-// for each tile get all four edges as strings,
-// turn them into a type that compares backwards and forwards,
-// then enter them one by one into a hashmap (mapping to the tile number)
-// but if an entry is already inside, remove it instead of adding it
-// (this should eliminate all entries that have a matching partner)
-// the remaining entries should point to the edges
-// the corners are the ones that have two entries for the tile!
-// (so take the values and remove all unique ones)
-
-// for PART 2 re-implemented with a full Tile struct that uses an ndarray::Array2
-// and keep a full mapping of all flip-ignoring "unique" edges to their tile ids
-// so we can then reconstruct a full image tile by tile
+// the tiles are assembled into a full image, the corners of the assembly give PART 1,
+// then for PART 2 the borders of the tiles are removed,
+// and the sea monsters in the image are found in any orientation
 
-use ndarray::{prelude::*, Zip};
-use std::{
-    collections::HashMap,
-    hash::{Hash, Hasher},
-};
+use crate::bitmap::{write_pbm, write_png};
+use crate::pattern::{covered_positions, find_matches, Orientation, Template};
+use crate::Params;
+use ndarray::prelude::*;
+use std::{collections::HashMap, fmt, fs::File, io::BufWriter};
 
+/// the sea monster, spaces match anything
 const SEA_MONSTER: &str = "
-                  # X
-#    ##    ##    ###X
- #  #  #  #  #  #   X
-";
-
-/// A Edge tuple struct that simply changes the equality and hash of a String to consider
-/// a reversed string identical
-#[derive(Debug)]
-struct Edge(Array1<u8>);
-
-impl PartialEq for Edge {
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0 || self.0 == other.0.slice(s![..;-1])
-    }
-}
-impl Eq for Edge {}
-impl Hash for Edge {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        let reversed = self.0.slice(s![..;-1]);
-        let straight = self.0.slice(s![..]);
-        if reversed.iter().lt(straight.iter()) {
-            reversed.hash(state);
-        } else {
-            straight.hash(state);
-        }
-    }
-}
-
+                  #
+#    ##    ##    ###
+ #  #  #  #  #  #";
+
+/// pixel values in the image
+const WATER: u8 = 0;
+const WAVE: u8 = 1;
+const MONSTER: u8 = 2;
+/// colors for water, waves and monsters in a PNG
+const PALETTE: [[u8; 3]; 3] = [[16, 40, 96], [120, 180, 230], [40, 200, 80]];
+
+/// A tile with its id and pixels (0 or 1), of any shape
 #[derive(Debug, Clone)]
-struct Tile {
-    pixels: Array2<u8>,
-    id: String,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum EdgeDir {
-    Top,
-    Bottom,
-    Left,
-    Right,
-}
-
-impl EdgeDir {
-    fn next_ccw(&self) -> Self {
-        match self {
-            EdgeDir::Top => EdgeDir::Left,
-            EdgeDir::Left => EdgeDir::Bottom,
-            EdgeDir::Bottom => EdgeDir::Right,
-            EdgeDir::Right => EdgeDir::Top,
-        }
-    }
+pub struct Tile {
+    pub id: String,
+    pub pixels: Array2<u8>,
 }
 
 impl Tile {
     fn from_str(tile_string: &str, tile_id: &str) -> Self {
-        Tile::from_str_shape(tile_string, tile_id, (10, 10))
-    }
-
-    fn from_str_shape(tile_string: &str, tile_id: &str, shape: (usize, usize)) -> Self {
+        let lines: Vec<&str> = tile_string.lines().collect();
+        let shape = (lines.len(), lines.first().map_or(0, |line| line.len()));
         Tile {
             pixels: Array::from_shape_vec(
-                shape, // hard-coded size!
-                tile_string
-                    .chars()
-                    .filter_map(|c| {
-                        if c == '#' {
-                            Some(1)
-                        } else if " .".contains(c) {
-                            Some(0)
-                        } else {
-                            None
-                        }
-                    })
+                shape,
+                lines
+                    .iter()
+                    .flat_map(|line| line.chars())
+                    .map(|c| if c == '#' { WAVE } else { WATER })
                     .collect(),
             )
             .unwrap(),
             id: tile_id.to_owned(),
         }
     }
+}
 
-    fn get_edge(&self, edge_dir: &EdgeDir) -> Edge {
-        match *edge_dir {
-            EdgeDir::Top => Edge(self.pixels.row(0).to_owned()),
-            EdgeDir::Bottom => Edge(self.pixels.row(self.pixels.nrows() - 1).to_owned()),
-            EdgeDir::Left => Edge(self.pixels.column(0).to_owned()),
-            EdgeDir::Right => Edge(self.pixels.column(self.pixels.ncols() - 1).to_owned()),
-        }
+/// Return the pixels in another orientation, the same way `Orientation::apply` moves offsets
+fn orient(pixels: ArrayView2<u8>, orientation: Orientation) -> Array2<u8> {
+    let mut view = pixels;
+    if orientation.mirrored {
+        view.invert_axis(Axis(1));
     }
-
-    fn get_edges(&self) -> Vec<(Edge, EdgeDir)> {
-        [EdgeDir::Top, EdgeDir::Bottom, EdgeDir::Left, EdgeDir::Right]
-            .iter()
-            .map(|dir| (self.get_edge(dir), *dir))
-            .collect()
+    for _ in 0..orientation.turns {
+        // a clockwise quarter turn: transpose, then mirror left to right
+        view = view.reversed_axes();
+        view.invert_axis(Axis(1));
     }
+    view.to_owned()
+}
 
-    /// rotate array counterclockwise, i.e. the final column becomes the first row
-    fn rotate_ccw(&mut self) {
-        let mut pix_vec = Vec::new();
-        for col_index in (0..self.pixels.ncols()).rev() {
-            pix_vec.extend(self.pixels.column(col_index));
-        }
-        let old_shape = self.pixels.shape();
-        self.pixels = Array2::from_shape_vec((old_shape[1], old_shape[0]), pix_vec).unwrap();
-    }
+fn tile_splitter(r: &str) -> Tile {
+    let (name, tile_data) = r.split_once(":\n").expect("No tile found!");
+    Tile::from_str(tile_data, name.trim_start_matches("Tile "))
+}
+
+/// Parse the tiles, separated by empty lines
+pub fn parse_tiles(input: &str) -> Vec<Tile> {
+    input.trim().split("\n\n").map(tile_splitter).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssemblyError {
+    NoTiles,
+    /// the tile does not have the shape of the first tile
+    ShapeMismatch(String),
+    /// the edges do not fit together in any rectangle
+    NoArrangement,
+    /// tiles need at least 3 rows and columns, to be left with pixels without their borders
+    TooSmall(usize, usize),
+}
 
-    /// rotate until from_dir is to_dir
-    fn rotate_from_to(&mut self, from_dir: &EdgeDir, to_dir: &EdgeDir) {
-        let mut cur_dir = *from_dir;
-        while &cur_dir != to_dir {
-            self.rotate_ccw();
-            cur_dir = cur_dir.next_ccw();
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssemblyError::NoTiles => write!(f, "no tiles"),
+            AssemblyError::ShapeMismatch(id) => write!(f, "tile {} has a different shape", id),
+            AssemblyError::NoArrangement => write!(f, "no arrangement of the tiles fits together"),
+            AssemblyError::TooSmall(rows, cols) => {
+                write!(f, "tiles of {}x{} pixels are too small", rows, cols)
+            }
         }
     }
+}
+
+impl std::error::Error for AssemblyError {}
 
-    /// flip array, so that the indicated edge is reversed (and all other rows/cols)
-    fn flip_along(&mut self, edge_dir: &EdgeDir) {
-        let lane_iter = match edge_dir {
-            &EdgeDir::Top | &EdgeDir::Bottom => self.pixels.rows_mut(),
-            &EdgeDir::Left | &EdgeDir::Right => self.pixels.columns_mut(),
+/// A tile in one orientation, with its edges
+#[derive(Debug)]
+struct Variant {
+    tile: usize,
+    orientation: Orientation,
+    pixels: Array2<u8>,
+    bottom: Vec<u8>,
+    right: Vec<u8>,
+}
+
+/// The tiles in a rectangle of rows and columns of tiles, row by row, each in its orientation
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    pub rows: usize,
+    pub cols: usize,
+    pub placements: Vec<(String, Orientation)>,
+    tiles: Vec<Array2<u8>>,
+}
+
+/// Backtracking search, placing the tiles row by row
+struct Assembler<'a> {
+    variants: &'a [Variant],
+    /// variants by their left and top edges
+    by_left: HashMap<Vec<u8>, Vec<usize>>,
+    by_top: HashMap<Vec<u8>, Vec<usize>>,
+    cols: usize,
+    used: Vec<bool>,
+    placed: Vec<usize>,
+}
+
+impl Assembler<'_> {
+    /// Place tiles from the position on, return true when all are placed
+    fn place(&mut self, position: usize) -> bool {
+        if position == self.used.len() {
+            return true;
+        }
+        let (row, col) = (position / self.cols, position % self.cols);
+        let left = (col > 0).then(|| &self.variants[self.placed[position - 1]]);
+        let above = (row > 0).then(|| &self.variants[self.placed[position - self.cols]]);
+        let candidates: Vec<usize> = match (left, above) {
+            (Some(left), _) => self.by_left.get(&left.right).cloned().unwrap_or_default(),
+            (None, Some(above)) => self.by_top.get(&above.bottom).cloned().unwrap_or_default(),
+            (None, None) => (0..self.variants.len()).collect(),
         };
-        for mut lane in lane_iter {
-            let lane_len = lane.len();
-            for index in 0..(lane_len / 2) {
-                lane.swap(index, lane_len - 1 - index);
+        for candidate in candidates {
+            let variant = &self.variants[candidate];
+            let fits_above =
+                above.is_none_or(|above| above.bottom.iter().eq(variant.pixels.row(0).iter()));
+            if self.used[variant.tile] || !fits_above {
+                continue;
+            }
+            self.used[variant.tile] = true;
+            self.placed.push(candidate);
+            if self.place(position + 1) {
+                return true;
             }
+            self.placed.pop();
+            self.used[variant.tile] = false;
         }
+        false
     }
 }
 
-fn tile_splitter(r: &str) -> (&str, Tile) {
-    // very verbose, next time try itertools::next_tuple
-    match r.split(":\n").collect::<Vec<&str>>()[..] {
-        [name, tile_data, ..] => (&name[5..], Tile::from_str(tile_data, &name[5..])),
-        _ => panic!("No tile found!"),
+/// Assemble the tiles into a rectangle where all neighbouring edges are equal.
+/// Layouts closest to a square are tried first. Tiles that are not square keep their shape,
+/// so they are only turned by half turns.
+/// Tiles need at least 3 rows and columns, to keep some pixels when their borders are stripped.
+/// ```
+/// use advent_of_code_202x::generated::year2020day20::{assemble, parse_tiles, AssemblyError};
+/// // three tiles fit in a row, the image is found in one of its 8 orientations:
+/// let tiles = parse_tiles("Tile 1:\n##.\n#.#\n...\n\nTile 2:\n#..\n#.#\n#.#\n\nTile 3:\n..#\n.##\n...");
+/// let assembly = assemble(&tiles).unwrap();
+/// assert_eq!((assembly.rows, assembly.cols), (1, 3));
+/// assert_eq!(assembly.image(true).rows().into_iter().map(|row| row.iter().map(|p| p.to_string()).collect::<String>()).collect::<Vec<_>>(), ["010001101", "100011101", "110000001"]);
+/// let tiles = parse_tiles("Tile 1:\n##.\n#.#\n...\n\nTile 2:\n#..\n#..\n#.#");
+/// assert_eq!(assemble(&tiles).unwrap_err(), AssemblyError::NoArrangement);
+/// let tiles = parse_tiles("Tile 1:\n##.\n#.#\n...\n\nTile 2:\n#.\n#.");
+/// assert_eq!(assemble(&tiles).unwrap_err(), AssemblyError::ShapeMismatch("2".to_owned()));
+/// let tiles = parse_tiles("Tile 1:\n#.#\n\nTile 2:\n#..");
+/// assert_eq!(assemble(&tiles).unwrap_err().to_string(), "tiles of 1x3 pixels are too small");
+/// ```
+///
+/// Random images cut into tiles, with few different edges:
+/// ```
+/// use advent_of_code_202x::generated::year2020day20::{assemble, Tile};
+/// use ndarray::{s, Array2};
+/// use advent_of_code_202x::testing::XorShift;
+/// let mut rng = XorShift::default();
+/// for _ in 0..50 {
+///     let (rows, cols, size) = (1 + rng.below(3) as usize, 1 + rng.below(4) as usize, 3 + rng.below(2) as usize);
+///     // neighbouring tiles share their border pixels:
+///     let image = Array2::from_shape_fn((rows * (size - 1) + 1, cols * (size - 1) + 1), |_| (rng.below(4) == 0) as u8);
+///     let mut tiles: Vec<Tile> = (0..rows * cols).map(|i| {
+///         let (row, col) = (i / cols * (size - 1), i % cols * (size - 1));
+///         let mut pixels = image.slice(s![row..row + size, col..col + size]).to_owned();
+///         if rng.below(2) == 0 {
+///             pixels = pixels.reversed_axes();
+///         }
+///         if rng.below(2) == 0 {
+///             pixels.invert_axis(ndarray::Axis(0));
+///         }
+///         Tile { id: i.to_string(), pixels: pixels.as_standard_layout().to_owned() }
+///     }).collect();
+///     tiles.reverse();
+///     let assembly = assemble(&tiles).unwrap();
+///     assert_eq!(assembly.rows * assembly.cols, rows * cols);
+///     let image = assembly.image(true);
+///     for r in 0..assembly.rows {
+///         for c in 0..assembly.cols {
+///             let tile = |r: usize, c: usize| image.slice(s![r * size..(r + 1) * size, c * size..(c + 1) * size]).to_owned();
+///             if c + 1 < assembly.cols {
+///                 assert_eq!(tile(r, c).column(size - 1), tile(r, c + 1).column(0));
+///             }
+///             if r + 1 < assembly.rows {
+///                 assert_eq!(tile(r, c).row(size - 1), tile(r + 1, c).row(0));
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub fn assemble(tiles: &[Tile]) -> Result<Assembly, AssemblyError> {
+    let shape = tiles.first().ok_or(AssemblyError::NoTiles)?.pixels.dim();
+    if let Some(tile) = tiles.iter().find(|tile| tile.pixels.dim() != shape) {
+        return Err(AssemblyError::ShapeMismatch(tile.id.clone()));
     }
-}
-
-/// reconstruct image by starting from a corner and filling a matrix:
-fn reconstruct_image(
-    mut tiles: HashMap<&str, Tile>,
-    edge_matches: &HashMap<Edge, Vec<(&str, EdgeDir)>>,
-    corner: &str,
-) -> Array2<u8> {
-    let num_tiles = tiles.len();
-    let row_length: usize = (num_tiles as f64).sqrt() as usize;
-    assert!(num_tiles == row_length * row_length);
-    let mut tile_id = corner;
-    let mut tile = tiles.remove(tile_id).unwrap();
-    //println!("Tile before:\n{:?}", tile);
-    for rotation in 0..4 {
-        if edge_matches[&tile.get_edge(&EdgeDir::Left)].len() == 1
-            && edge_matches[&tile.get_edge(&EdgeDir::Top)].len() == 1
-        {
-            break;
-        } else {
-            if rotation == 3 {
-                panic!("Top-Left Tile - still no fit after 3 rotations!")
+    if shape.0 < 3 || shape.1 < 3 {
+        return Err(AssemblyError::TooSmall(shape.0, shape.1));
+    }
+    let mut variants = Vec::new();
+    for (index, tile) in tiles.iter().enumerate() {
+        let mut seen = Vec::new();
+        for orientation in Orientation::ALL {
+            let pixels = orient(tile.pixels.view(), orientation);
+            // skip turns that change the shape, and symmetric tiles that look the same:
+            if pixels.dim() != shape || seen.contains(&pixels) {
+                continue;
             }
-            tile.rotate_ccw();
-            //println!("Tile rotated:\n{:?}", tile);
+            seen.push(pixels.clone());
+            variants.push(Variant {
+                tile: index,
+                orientation,
+                bottom: pixels.row(shape.0 - 1).to_vec(),
+                right: pixels.column(shape.1 - 1).to_vec(),
+                pixels,
+            });
         }
     }
-    // create a vec of tile references, and a row counter
-    let mut tile_grid: Vec<Tile> = Vec::new();
-    let mut edge_to_match: Edge;
-    //let mut rows = 1; // no need for row counter, hard-coded for simplicity
-    for tile_index in 1..num_tiles {
-        // look for match to the right and rotate/flip until matched edges (flip if edge.0 unequal)
-        // when no more match to the right, go back to first index (in row) and
-        // look for match at bottom once, then to the right again
-        // (easier: with fixed row_length, just look for the bottom match when at the beginning of row)
-        let targt_edge_dir = if (tile_index % row_length) != 0 {
-            edge_to_match = tile.get_edge(&EdgeDir::Right);
-            EdgeDir::Left
-        } else {
-            let row_start_tile = &tile_grid[tile_index - row_length];
-            //println!("Row-start at tile_index {}, matching bottom of index {}\nROWSTARTTILE {:?}",
-            //    tile_index, tile_index - row_length, row_start_tile);
-            edge_to_match = row_start_tile.get_edge(&EdgeDir::Bottom);
-            tile_id = &row_start_tile.id;
-            EdgeDir::Top
-        };
-        //println!("Pre-Match: tile_id {} at tile_index {} for targt_edge_dir {:?}, edge {:?},\n{:?}",
-        //    tile_id, tile_index, targt_edge_dir, edge_to_match, &tile);
-        let &(next_tile_id, next_tile_edge_dir) = match edge_matches[&edge_to_match]
-            .iter()
-            .find(|(tid, _)| tid != &tile_id)
-        {
-            Some(ematch) => ematch,
-            None => panic!(
-                "No edge match found for tile_id {}, tile_index {} targt_edge_dir {:?}\n{:?}",
-                tile_id, tile_index, targt_edge_dir, edge_matches[&edge_to_match]
-            ),
+    let mut by_left: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
+    let mut by_top: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
+    for (index, variant) in variants.iter().enumerate() {
+        by_left
+            .entry(variant.pixels.column(0).to_vec())
+            .or_default()
+            .push(index);
+        by_top
+            .entry(variant.pixels.row(0).to_vec())
+            .or_default()
+            .push(index);
+    }
+    let num_tiles = tiles.len();
+    let mut layouts: Vec<(usize, usize)> = (1..=num_tiles)
+        .filter(|&rows| num_tiles.is_multiple_of(rows))
+        .map(|rows| (rows, num_tiles / rows))
+        .collect();
+    layouts.sort_by_key(|&(rows, cols)| (rows.abs_diff(cols), rows));
+    for (rows, cols) in layouts {
+        let mut assembler = Assembler {
+            variants: &variants,
+            by_left: by_left.clone(),
+            by_top: by_top.clone(),
+            cols,
+            used: vec![false; num_tiles],
+            placed: Vec::with_capacity(num_tiles),
         };
-        //println!("Match found: next_tile_id {} at tile_index {} for targt_edge_dir {:?}",
-        //    next_tile_id, tile_index, targt_edge_dir);
-        let mut next_tile = tiles.remove(next_tile_id).unwrap();
-        // rotate to get correct orientation:
-        next_tile.rotate_from_to(&next_tile_edge_dir, &targt_edge_dir);
-        // flip if necessary:
-        if edge_to_match.0 != next_tile.get_edge(&targt_edge_dir).0 {
-            next_tile.flip_along(&targt_edge_dir);
-        }
-        assert!(edge_to_match.0 == next_tile.get_edge(&targt_edge_dir).0);
-        // safety check that the top edge matches (borders could be symmetric and then no flip would be triggered)
-        if tile_index > row_length && (tile_index % row_length) != 0 {
-            let above_tile = &tile_grid[tile_index - row_length];
-            if above_tile.get_edge(&EdgeDir::Bottom).0 != next_tile.get_edge(&EdgeDir::Top).0 {
-                panic!(
-                    "Tile does not match tile above \n{:?}\n\n{:?}",
-                    above_tile, next_tile
-                );
-            }
+        if assembler.place(0) {
+            let placed: Vec<&Variant> = assembler.placed.iter().map(|&v| &variants[v]).collect();
+            return Ok(Assembly {
+                rows,
+                cols,
+                placements: placed
+                    .iter()
+                    .map(|v| (tiles[v.tile].id.clone(), v.orientation))
+                    .collect(),
+                tiles: placed.iter().map(|v| v.pixels.clone()).collect(),
+            });
         }
-        // record tile for new image grid:
-        tile_grid.push(tile);
-        // switch to next_tile
-        tile_id = next_tile_id;
-        tile = next_tile;
     }
-    tile_grid.push(tile); // final tile
-                          // calculate dimensions of full image
-                          // (row-length is the difference of length to remembered row-start index,
-                          // every tile will change from 10x10 to 8x8 after removing borders)
-    let image_dim = row_length * 8;
-    // fill a new Array2 with the center 8X8 from all tiles in order
-    let mut image: Array2<u8> = Array2::<u8>::zeros((image_dim, image_dim));
-    //println!("TILE GRID:");
-    for (index, tile) in tile_grid.iter().enumerate() {
-        //if index % row_length == 0 { println!(); }
-        //print!("{} ", tile.id);
-        let row_index = 8 * (index / row_length);
-        let col_index = 8 * (index % row_length);
-        let img_slice = s![row_index..row_index + 8, col_index..col_index + 8];
-        azip!((ipix in &mut image.slice_mut(img_slice), &tpix in tile.pixels.slice(s![1..9, 1..9])) *ipix = tpix);
-    }
-    image
+    Err(AssemblyError::NoArrangement)
 }
 
-fn count_value(array: ArrayView2<u8>, value: u8) -> usize {
-    array.iter().filter(|&&num| num == value).count()
-}
+impl Assembly {
+    /// Return the ids of the tiles in the corners
+    pub fn corners(&self) -> [&str; 4] {
+        let last_row = (self.rows - 1) * self.cols;
+        [0, self.cols - 1, last_row, last_row + self.cols - 1]
+            .map(|i| self.placements[i].0.as_str())
+    }
 
-/// 2d cross-correlation of kernel on image with no padding, i.e. only multiply the kernel
-/// with the matching part of the image where it fully fits. Therefore returns
-/// an array with dimensions of image minus dimensions of kernel + 1
-/// (Often also called convolve2d, but convolve first rotates the kernel 180)
-fn crosscorrelate2d_unpadded(image: ArrayView2<u8>, kernel: ArrayView2<u8>) -> Array2<u8> {
-    let kernel_rows = kernel.shape()[0];
-    let kernel_cols = kernel.shape()[1];
-    let num_rows = image.shape()[0] - kernel_rows + 1;
-    let num_cols = image.shape()[1] - kernel_cols + 1;
-    let mut result = Array2::<u8>::zeros((num_rows, num_cols));
-    for row in 0..num_rows {
-        for col in 0..num_cols {
-            result[[row, col]] =
-                Zip::from(image.slice(s![row..row + kernel_rows, col..col + kernel_cols]))
-                    .and(&kernel)
-                    .fold(0, |acc, &i, &k| acc + i * k);
+    /// Put the tiles together, with their borders or without
+    pub fn image(&self, borders: bool) -> Array2<u8> {
+        let (tile_rows, tile_cols) = self.tiles[0].dim();
+        let cut = if borders { 0 } else { 1 };
+        let (height, width) = (tile_rows - 2 * cut, tile_cols - 2 * cut);
+        let mut image = Array2::zeros((self.rows * height, self.cols * width));
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let (row, col) = (index / self.cols * height, index % self.cols * width);
+            image
+                .slice_mut(s![row..row + height, col..col + width])
+                .assign(&tile.slice(s![cut..tile_rows - cut, cut..tile_cols - cut]));
         }
+        image
     }
-    result
-}
 
-/// count occurrences of monster in image, but first the correct orientation (rotate/flip) has to be found
-/// (rotate/flip the monster rather than the image for efficiency)
-/// so do a convolve for every possible orientation until found
-fn count_seamonster_hashes(image: ArrayView2<u8>, mut monster: Tile) -> usize {
-    let monster_size = count_value(monster.pixels.view(), 1) as u8;
-    let mut monster_count: usize;
-    for trial in 0..8 {
-        // 8 possible orientations
-        // check if a convolution gets any result:
-        let correlate = crosscorrelate2d_unpadded(image.view(), monster.pixels.view());
-        monster_count = count_value(correlate.view(), monster_size);
-        //println!("monster_count {} (target size {}) for monster {:?} correlate {:?}",
-        //        monster_count, monster_size, &monster, &correlate);
-        if monster_count > 0 {
-            return monster_count * (monster_size as usize);
-        }
-        monster.rotate_ccw();
-        if trial == 3 {
-            monster.flip_along(&EdgeDir::Right);
+    /// Return the image with the pixels of sea monsters set to MONSTER, searched without borders
+    pub fn highlight_monsters(&self, borders: bool) -> Array2<u8> {
+        let monster = Template::from_rows(SEA_MONSTER.trim_start_matches('\n'), ' ').map(|_| WAVE);
+        let inner = self.image(false);
+        let matches = find_matches(inner.view(), &monster, &Orientation::ALL);
+        let mut image = self.image(borders);
+        let (tile_rows, tile_cols) = self.tiles[0].dim();
+        for (row, col) in covered_positions(&monster, &matches) {
+            // skip over the borders of the tiles before:
+            image[if borders {
+                (
+                    row / (tile_rows - 2) * tile_rows + row % (tile_rows - 2) + 1,
+                    col / (tile_cols - 2) * tile_cols + col % (tile_cols - 2) + 1,
+                )
+            } else {
+                (row, col)
+            }] = MONSTER;
         }
+        image
     }
-    panic!("No monsters found in any orientation!");
+}
+
+fn count_value(array: ArrayView2<u8>, value: u8) -> usize {
+    array.iter().filter(|&&num| num == value).count()
 }
 
 pub fn process_input(input: &str) -> String {
-    let input = input.trim().split("\n\n");
-    let tiles: HashMap<&str, Tile> = input.map(tile_splitter).collect();
-    //println!("Input: {:?}", &tiles["3371"]);
-    let mut edge_matches: HashMap<Edge, Vec<(&str, EdgeDir)>> = HashMap::new();
-    for (&tile_name, tile) in &tiles {
-        for (edge, edge_dir) in tile.get_edges() {
-            edge_matches
-                .entry(edge)
-                .or_insert_with(Vec::new)
-                .push((tile_name, edge_dir));
+    let tiles = parse_tiles(input);
+    // reconstruct the image, then multiply the corner ids and count the waves that are not part of monsters:
+    let (product, roughness) = match assemble(&tiles) {
+        Ok(assembly) => {
+            let corners = assembly.corners().map(|id| id.parse::<u64>().unwrap());
+            let image = assembly.highlight_monsters(false);
+            (
+                corners.iter().product::<u64>().to_string(),
+                count_value(image.view(), WAVE).to_string(),
+            )
         }
-    }
-    //println!("Edge_map {:?}", edge_matches);
-    let mut outer_edge_freq = HashMap::new();
-    for unique_edge_vec in edge_matches.values().filter(|&m_vec| m_vec.len() == 1) {
-        let tile_id = unique_edge_vec[0].0;
-        *outer_edge_freq.entry(tile_id).or_insert(0) += 1;
-    }
-    //println!("Outer Edge Frequencies: {:?}", outer_edge_freq);
-    let corners: Vec<_> = outer_edge_freq
-        .iter()
-        .filter_map(|(&k, &v)| if v == 2 { Some(k) } else { None })
-        .collect();
-    //println!("Corners: {:?}", corners);
-    let product_of_corners = corners
-        .iter()
-        .map(|&tid| tid.parse::<u64>().unwrap())
-        .product::<u64>();
-    // println!(
-    //     "{} Tiles and {} Outer Tiles",
-    //     tiles.len(),
-    //     outer_edge_freq.len()
-    // );
-    // reconstruct image by starting from a corner and filling a matrix:
-    let image = reconstruct_image(tiles, &edge_matches, corners[0]);
-    let num_hashes = count_value(image.view(), 1);
-    //println!("IMAGE (sum {})\n{:?}", num_hashes, image);
-    // filter out monsters and count again:
-    let monster = Tile::from_str_shape(SEA_MONSTER, "monster", (3, 20));
-    //println!("the monster:\n{:?}", monster);
-    let num_hashes_not_seamonster = num_hashes - count_seamonster_hashes(image.view(), monster);
+        Err(error) => (error.to_string(), error.to_string()),
+    };
     format!(
         "Product of corners: {}\nNOT PART OF SEA MONSTERS sum: {}",
-        product_of_corners, num_hashes_not_seamonster,
+        product, roughness,
     )
 }
 
+/// Write the assembled image to out=file.pbm or out=file.png (default sea.pbm),
+/// borders=true keeps the borders of the tiles, highlight=false does not mark the monsters.
+/// PBM only has black and white, so highlighted monsters are the only black pixels there.
+/// ```
+/// use advent_of_code_202x::generated::year2020day20::run_with_params;
+/// let file = std::env::temp_dir().join("sea_monsters_example.pbm");
+/// let params = [("out", file.to_str().unwrap())].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), format!("wrote 24x24 image to {}", file.display()));
+/// let pbm = std::fs::read_to_string(&file).unwrap();
+/// assert!(pbm.starts_with("P1\n24 24\n"));
+/// // two monsters with 15 pixels each:
+/// assert_eq!(pbm.lines().skip(2).map(|line| line.matches('1').count()).sum::<usize>(), 30);
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let tiles = parse_tiles(if use_example { EXAMPLE_INPUT } else { INPUT });
+    let flag = |key: &str, default: bool| params.get(key).map_or(default, |v| v == "true");
+    let path = params.get("out").map_or("sea.pbm", String::as_str);
+    let assembly = match assemble(&tiles) {
+        Ok(assembly) => assembly,
+        Err(error) => return error.to_string(),
+    };
+    let borders = flag("borders", false);
+    let image = if flag("highlight", true) {
+        assembly.highlight_monsters(borders)
+    } else {
+        assembly.image(borders)
+    };
+    let result = File::create(path).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        if path.ends_with(".png") {
+            write_png(image.view(), &PALETTE, &mut out)
+        } else if flag("highlight", true) {
+            write_pbm(image.mapv(|p| p == MONSTER).view(), &mut out)
+        } else {
+            write_pbm(image.mapv(|p| p != WATER).view(), &mut out)
+        }
+    });
+    match result {
+        Ok(()) => format!(
+            "wrote {}x{} image to {}",
+            image.ncols(),
+            image.nrows(),
+            path
+        ),
+        Err(error) => format!("could not write {}: {}", path, error),
+    }
+}
+
 pub fn run_example() -> String {
     process_input(EXAMPLE_INPUT)
 }
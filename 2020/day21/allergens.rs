@@ -26,95 +26,208 @@ sqjhc fvjkl (contains soy)
 sqjhc mxmxvkd sbzzf (contains fish)
 ";
 
-use std::collections::{HashMap, HashSet};
+use crate::Params;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
 
-fn food_splitter(line: &str) -> (Vec<&str>, Vec<&str>) {
-    let mut ingrs_allergens = line.split(" (contains ");
-    let ingrs = ingrs_allergens.next().unwrap().split(' ').collect();
-    let allergens = ingrs_allergens
-        .next()
-        .unwrap()
-        .trim_end_matches(')')
-        .split(", ")
-        .collect();
-    (ingrs, allergens)
+/// One line of the input: all ingredients, and some of the allergens in them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Food<'a> {
+    pub ingredients: Vec<&'a str>,
+    pub allergens: Vec<&'a str>,
 }
 
-pub fn process_input(input: &str) -> String {
-    let input = input.trim().split('\n');
-    let foods: Vec<_> = input.map(food_splitter).collect();
-    //println!("Input: {:?}", &foods);
-    let mut ingr_counts = HashMap::new();
-    let mut allergen_options = HashMap::new();
-    for (ingrs, allergens) in foods {
-        for &ingr in ingrs.iter() {
-            *ingr_counts.entry(ingr).or_insert(0) += 1;
+impl<'a> Food<'a> {
+    /// Parse `ingredient ingredient (contains allergen, allergen)`, the allergens are optional
+    pub fn from_line(line: &'a str) -> Self {
+        let (ingredients, allergens) = match line.split_once(" (contains ") {
+            Some((ingredients, allergens)) => (
+                ingredients,
+                allergens.trim_end_matches(')').split(", ").collect(),
+            ),
+            None => (line, Vec::new()),
+        };
+        Food {
+            ingredients: ingredients.split_whitespace().collect(),
+            allergens,
         }
-        let ingr_set: HashSet<_> = ingrs.into_iter().collect();
-        for &allergen in allergens.iter() {
-            if !allergen_options.contains_key(allergen) {
-                allergen_options.insert(allergen, ingr_set.clone());
-            } else {
-                allergen_options
-                    .entry(allergen)
-                    .and_modify(|e| *e = e.intersection(&ingr_set).cloned().collect());
+    }
+}
+
+/// What became of an allergen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome<'a> {
+    /// the ingredient was the only candidate left in the round (counted from 1)
+    Resolved { ingredient: &'a str, round: usize },
+    /// the candidates left when no more allergens could be resolved,
+    /// none if the foods contradict each other, or the single candidate if other allergens
+    /// were left with the same one in the same round
+    Unresolved(Vec<&'a str>),
+}
+
+/// How one allergen was narrowed down to its ingredient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation<'a> {
+    pub allergen: &'a str,
+    /// the numbers of the foods listing the allergen (first line is 1),
+    /// the candidates are the ingredients all of them have in common
+    pub foods: Vec<usize>,
+    pub candidates: Vec<&'a str>,
+    pub outcome: Outcome<'a>,
+}
+
+impl fmt::Display for Explanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let foods: Vec<String> = self.foods.iter().map(usize::to_string).collect();
+        write!(
+            f,
+            "{} in foods {}: candidates {}, ",
+            self.allergen,
+            foods.join(" "),
+            self.candidates.join(" ")
+        )?;
+        match &self.outcome {
+            Outcome::Resolved { ingredient, round } => {
+                write!(f, "{} in round {}", ingredient, round)
             }
+            Outcome::Unresolved(left) if left.is_empty() => write!(f, "contradiction"),
+            Outcome::Unresolved(left) => write!(f, "unresolved: {}", left.join(" ")),
         }
     }
-    //println!(
-    //    "Ingr counts:\n{:?}\nAllergen options:\n{:?}",
-    //    &ingr_counts, &allergen_options
-    //);
-    let mut possible_allergens: HashSet<&&str> = HashSet::new();
-    allergen_options
-        .values()
-        .for_each(|hs| possible_allergens.extend(hs));
-    //println!("Possible allergens:\n{:?}", possible_allergens);
-    let safe_ingr_count: i32 = ingr_counts
+}
+
+/// Explanations for all allergens, sorted by allergen.
+/// Each round fixes all allergens with a single candidate left,
+/// and removes their ingredients from the candidates of the others.
+/// Allergens left with the same single ingredient in a round stay unresolved.
+/// ```
+/// use advent_of_code_202x::generated::year2020day21::{explain, Food, Outcome};
+/// let foods: Vec<Food> = ["a b c (contains x, y)", "a b (contains x)", "c d (contains y, z)", "d e"]
+///     .into_iter()
+///     .map(Food::from_line)
+///     .collect();
+/// let explanations = explain(&foods);
+/// assert_eq!(explanations[0].foods, vec![1, 2]);
+/// assert_eq!(explanations[0].outcome, Outcome::Unresolved(vec!["a", "b"]));
+/// assert_eq!(explanations[1].outcome, Outcome::Resolved { ingredient: "c", round: 1 });
+/// assert_eq!(explanations[2].to_string(), "z in foods 3: candidates c d, d in round 2");
+/// // both allergens can only be in a, but an ingredient has at most one allergen:
+/// let foods: Vec<Food> = ["a (contains x)", "a (contains y)"].into_iter().map(Food::from_line).collect();
+/// let explanations = explain(&foods);
+/// assert_eq!(explanations[0].to_string(), "x in foods 1: candidates a, unresolved: a");
+/// assert_eq!(explanations[1].outcome, Outcome::Unresolved(vec!["a"]));
+/// ```
+pub fn explain<'a>(foods: &[Food<'a>]) -> Vec<Explanation<'a>> {
+    let mut constraints: BTreeMap<&str, (Vec<usize>, BTreeSet<&str>)> = BTreeMap::new();
+    for (index, food) in foods.iter().enumerate() {
+        let ingredients: BTreeSet<&str> = food.ingredients.iter().copied().collect();
+        for &allergen in &food.allergens {
+            let (numbers, candidates) = constraints
+                .entry(allergen)
+                .or_insert_with(|| (Vec::new(), ingredients.clone()));
+            numbers.push(index + 1);
+            candidates.retain(|ingredient| ingredients.contains(ingredient));
+        }
+    }
+    let mut left: BTreeMap<&str, BTreeSet<&str>> = constraints
         .iter()
-        .filter_map(|(ing, &count)| {
-            if possible_allergens.contains(ing) {
-                None
-            } else {
-                Some(count)
-            }
-        })
-        .sum();
-    let mut allergen_translations: HashMap<&str, &str> = HashMap::new();
-    loop {
-        let allergens_found: Vec<_> = allergen_options
+        .map(|(&allergen, (_, candidates))| (allergen, candidates.clone()))
+        .collect();
+    let mut outcomes = BTreeMap::new();
+    for round in 1.. {
+        let found: Vec<(&str, &str)> = left
             .iter()
-            .filter_map(|(&k, v)| if v.len() == 1 { Some(k) } else { None })
+            .filter(|(_, candidates)| candidates.len() == 1)
+            .map(|(&allergen, candidates)| (allergen, *candidates.first().unwrap()))
             .collect();
-        if allergens_found.is_empty() {
+        if found.is_empty() {
             break;
         }
-        for aller in allergens_found {
-            let trans = *allergen_options
-                .remove(aller)
-                .unwrap()
+        for &(allergen, ingredient) in &found {
+            left.remove(allergen);
+            for candidates in left.values_mut() {
+                candidates.remove(ingredient);
+            }
+            let shared = found
                 .iter()
-                .next()
-                .unwrap();
-            allergen_options.iter_mut().for_each(|(_k, v)| {
-                v.remove(trans);
-            });
-            allergen_translations.insert(aller, trans);
+                .any(|&(other, same)| other != allergen && same == ingredient);
+            let outcome = if shared {
+                Outcome::Unresolved(vec![ingredient])
+            } else {
+                Outcome::Resolved { ingredient, round }
+            };
+            outcomes.insert(allergen, outcome);
         }
     }
-    //println!("Allergen translations:\n{:?}", &allergen_translations);
-    // sort alphabetically
-    let mut allergens: Vec<_> = allergen_translations.keys().collect();
-    allergens.sort();
-    let translations: Vec<_> = allergens
+    constraints
+        .into_iter()
+        .map(|(allergen, (foods, candidates))| Explanation {
+            allergen,
+            foods,
+            candidates: candidates.into_iter().collect(),
+            outcome: outcomes
+                .remove(allergen)
+                .unwrap_or_else(|| Outcome::Unresolved(left[allergen].iter().copied().collect())),
+        })
+        .collect()
+}
+
+/// Count the ingredients that are no candidate for any allergen, each time they appear
+fn count_safe(foods: &[Food], explanations: &[Explanation]) -> usize {
+    let unsafe_ingredients: HashSet<&str> = explanations
         .iter()
-        .map(|&&a| allergen_translations[a])
+        .flat_map(|explanation| explanation.candidates.iter().copied())
         .collect();
-    format!(
+    foods
+        .iter()
+        .flat_map(|food| &food.ingredients)
+        .filter(|ingredient| !unsafe_ingredients.contains(*ingredient))
+        .count()
+}
+
+fn report(input: &str, with_explanation: bool) -> String {
+    let foods: Vec<Food> = input.trim().lines().map(Food::from_line).collect();
+    let explanations = explain(&foods);
+    let mut dangerous = Vec::new();
+    let mut lines = Vec::new();
+    for explanation in &explanations {
+        match &explanation.outcome {
+            Outcome::Resolved { ingredient, .. } => dangerous.push(*ingredient),
+            Outcome::Unresolved(_) if !with_explanation => lines.push(explanation.to_string()),
+            Outcome::Unresolved(_) => {}
+        }
+        if with_explanation {
+            lines.push(explanation.to_string());
+        }
+    }
+    lines.push(format!(
         "Safe ingredient count: {}\nCanonical dangerous: {}",
-        safe_ingr_count,
-        translations.join(","),
-    )
+        count_safe(&foods, &explanations),
+        dangerous.join(","),
+    ));
+    lines.join("\n")
+}
+
+pub fn process_input(input: &str) -> String {
+    report(input, false)
+}
+
+/// With `explain=true`, show how each allergen was resolved first,
+/// e.g. `cargo run 21 2020 x explain=true`
+/// ```
+/// use advent_of_code_202x::generated::year2020day21::run_with_params;
+/// let params = [("explain", "true")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert!(run_with_params(&params, true).starts_with(
+///     "dairy in foods 1 2: candidates mxmxvkd, mxmxvkd in round 1\n\
+///     fish in foods 1 4: candidates mxmxvkd sqjhc, sqjhc in round 2\n\
+///     soy in foods 3: candidates fvjkl sqjhc, fvjkl in round 3\n"
+/// ));
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let input = if use_example { EXAMPLE_INPUT } else { INPUT };
+    let with_explanation = params.get("explain").is_some_and(|e| e == "true");
+    report(input, with_explanation)
 }
 
 pub fn run_example() -> String {
@@ -38,90 +38,544 @@ Player 2:
 ";
 
 use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use crate::number_theory::{mul_mod, pow_mod};
+use crate::Params;
 
 type Deck = VecDeque<usize>;
 
-/// play game rounds until one player has all the cards and return the player index, and that deck
-fn play_combat(mut players: Vec<Deck>) -> (usize, Deck) {
-    while !players.iter().any(|deck| deck.is_empty()) {
-        let trick: Vec<_> = players
-            .iter_mut()
-            .map(|deck| deck.pop_front().unwrap())
-            .collect();
-        let (winner_index, &max_num) = trick
-            .iter()
-            .enumerate()
-            .max_by_key(|(_, &val)| val)
-            .unwrap();
-        players[winner_index].push_back(max_num);
-        players[winner_index].push_back(trick[1 - winner_index]);
+/// modulus and bases for two independent polynomial hashes of a deck (2^61 - 1 is prime)
+const HASH_MODULUS: u64 = (1 << 61) - 1;
+const HASH_BASES: [u64; 2] = [1_000_003, 998_244_353];
+
+/// A deck that keeps two polynomial hashes of its cards up to date:
+/// top card * BASE^(len - 1) + ... + bottom card, so taking the top card and
+/// putting a card at the bottom are O(1) updates, with the power of the top card cached
+#[derive(Debug, Clone)]
+struct HashedDeck {
+    cards: Deck,
+    hashes: [u64; 2],
+    /// BASE^(len - 1) for each hash, 1 for an empty deck
+    top_powers: [u64; 2],
+    /// the inverses of the bases, to lower the top powers
+    inverse_bases: [u64; 2],
+}
+
+impl HashedDeck {
+    fn new(cards: impl IntoIterator<Item = usize>) -> Self {
+        let mut deck = HashedDeck {
+            cards: Deck::new(),
+            hashes: [0; 2],
+            top_powers: [1; 2],
+            inverse_bases: HASH_BASES.map(|base| pow_mod(base, HASH_MODULUS - 2, HASH_MODULUS)),
+        };
+        cards.into_iter().for_each(|card| deck.push_back(card));
+        deck
     }
-    players
-        .into_iter()
-        .enumerate()
-        .find(|(_, deck)| !deck.is_empty())
-        .unwrap()
-}
-
-/// play recursive game rounds until one player has all the cards and return the player index, and that deck
-/// NOTE: this is still pretty slow. Probably because I wrote it to possible allow more than two players
-/// (could try representing deck1 and deck2 directly rather than as a Vec<Deck>)
-fn play_recursive_combat(mut players: Vec<Deck>) -> (usize, Deck) {
-    let mut game_history = HashSet::new();
-    loop {
-        if !game_history.insert(players.clone()) {
-            // repeat configuration: player 1 wins:
-            return (0, players.remove(0));
+
+    fn pop_front(&mut self) -> Option<usize> {
+        let card = self.cards.pop_front()?;
+        for i in 0..2 {
+            let top = mul_mod(card as u64, self.top_powers[i], HASH_MODULUS);
+            self.hashes[i] = (self.hashes[i] + HASH_MODULUS - top) % HASH_MODULUS;
+            if !self.cards.is_empty() {
+                self.top_powers[i] =
+                    mul_mod(self.top_powers[i], self.inverse_bases[i], HASH_MODULUS);
+            }
         }
-        let trick: Vec<_> = players
-            .iter_mut()
-            .map(|deck| deck.pop_front().unwrap())
-            .collect();
-        let winner_index;
-        // recursive game?
-        if players
-            .iter()
-            .zip(trick.iter())
-            .all(|(deck, &num)| deck.len() >= num)
-        {
-            // TODO
-            let recurse_players = players
+        Some(card)
+    }
+
+    fn push_back(&mut self, card: usize) {
+        for (i, &base) in HASH_BASES.iter().enumerate() {
+            if !self.cards.is_empty() {
+                self.top_powers[i] = mul_mod(self.top_powers[i], base, HASH_MODULUS);
+            }
+            let shifted = mul_mod(self.hashes[i], base, HASH_MODULUS);
+            self.hashes[i] = (shifted + card as u64) % HASH_MODULUS;
+        }
+        self.cards.push_back(card);
+    }
+}
+
+/// The decks at the start of a round, as two combined hashes of player, length and cards
+/// of each deck: the game history keeps only these 122 bits per round, and two different
+/// states of a game would need to collide in both hashes to end it early.
+fn game_state(players: &[(usize, HashedDeck)]) -> [u64; 2] {
+    [0, 1].map(|i| {
+        // the length tells where one deck ends and the next one starts:
+        players.iter().fold(0, |state, (player, deck)| {
+            [*player as u64, deck.cards.len() as u64, deck.hashes[i]]
                 .iter()
-                .zip(trick.iter())
-                .map(|(deck, &num)| deck.iter().take(num).copied().collect())
-                .collect();
-            winner_index = play_recursive_combat(recurse_players).0;
-        } else {
-            // normal round: higher card wins:
-            winner_index = trick
+                .fold(state, |state, &part| {
+                    (mul_mod(state, HASH_BASES[i], HASH_MODULUS) + part) % HASH_MODULUS
+                })
+        })
+    })
+}
+
+/// Decide who wins a round: gets the trick as (player, card) in player order,
+/// returns the position of the winning card in the trick
+pub type RoundWinner = fn(&[(usize, usize)]) -> usize;
+
+/// the normal rule: the highest card wins
+pub fn highest_card(trick: &[(usize, usize)]) -> usize {
+    (0..trick.len()).max_by_key(|&i| trick[i].1).unwrap()
+}
+
+/// a variant: the lowest card wins
+pub fn lowest_card(trick: &[(usize, usize)]) -> usize {
+    (0..trick.len()).min_by_key(|&i| trick[i].1).unwrap()
+}
+
+/// One step of a game, for the replay log. Players are numbered from 1, like in the puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    GameStart {
+        game: usize,
+        depth: usize,
+    },
+    Round {
+        game: usize,
+        depth: usize,
+        round: usize,
+        decks: Vec<(usize, Vec<usize>)>,
+        trick: Vec<(usize, usize)>,
+    },
+    SubGame {
+        game: usize,
+        depth: usize,
+        sub_game: usize,
+    },
+    RoundWon {
+        game: usize,
+        depth: usize,
+        round: usize,
+        winner: usize,
+    },
+    /// the decks repeated a previous round, so the first player wins the game
+    Repeat {
+        game: usize,
+        depth: usize,
+        round: usize,
+    },
+    GameWon {
+        game: usize,
+        depth: usize,
+        winner: usize,
+    },
+}
+
+fn cards_to_string(cards: &[usize]) -> String {
+    cards
+        .iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// prints like the example in the puzzle text, indented by the depth of the sub-game
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::GameStart { game, depth } => {
+                write!(f, "{:indent$}=== Game {} ===", "", game, indent = 2 * depth)
+            }
+            Event::Round {
+                game,
+                depth,
+                round,
+                decks,
+                trick,
+            } => {
+                let indent = 2 * depth;
+                writeln!(f, "{:indent$}-- Round {} (Game {}) --", "", round, game)?;
+                for (player, cards) in decks {
+                    writeln!(
+                        f,
+                        "{:indent$}Player {}'s deck: {}",
+                        "",
+                        player,
+                        cards_to_string(cards)
+                    )?;
+                }
+                let plays: Vec<_> = trick
+                    .iter()
+                    .map(|(player, card)| format!("Player {} plays: {}", player, card))
+                    .collect();
+                write!(
+                    f,
+                    "{:indent$}{}",
+                    "",
+                    plays.join(&format!("\n{:indent$}", ""))
+                )
+            }
+            Event::SubGame { depth, .. } => write!(
+                f,
+                "{:indent$}Playing a sub-game to determine the winner...",
+                "",
+                indent = 2 * depth
+            ),
+            Event::RoundWon {
+                game,
+                depth,
+                round,
+                winner,
+            } => write!(
+                f,
+                "{:indent$}Player {} wins round {} of game {}!",
+                "",
+                winner,
+                round,
+                game,
+                indent = 2 * depth
+            ),
+            Event::Repeat { game, depth, round } => write!(
+                f,
+                "{:indent$}Round {} of game {} repeats a previous round, player 1 wins!",
+                "",
+                round,
+                game,
+                indent = 2 * depth
+            ),
+            Event::GameWon {
+                game,
+                depth,
+                winner,
+            } => write!(
+                f,
+                "{:indent$}The winner of game {} is player {}!",
+                "",
+                game,
+                winner,
+                indent = 2 * depth
+            ),
+        }
+    }
+}
+
+impl Event {
+    /// one JSON object for the event
+    pub fn to_json(&self) -> String {
+        let pairs_json = |pairs: &[(usize, usize)], first: &str, second: &str| {
+            let objects: Vec<_> = pairs
                 .iter()
-                .enumerate()
-                .max_by_key(|(_, &val)| val)
-                .unwrap()
-                .0;
+                .map(|(a, b)| format!("{{\"{}\":{},\"{}\":{}}}", first, a, second, b))
+                .collect();
+            format!("[{}]", objects.join(","))
+        };
+        match self {
+            Event::GameStart { game, depth } => {
+                format!(
+                    "{{\"event\":\"game_start\",\"game\":{},\"depth\":{}}}",
+                    game, depth
+                )
+            }
+            Event::Round {
+                game,
+                depth,
+                round,
+                decks,
+                trick,
+            } => {
+                let decks: Vec<_> = decks
+                    .iter()
+                    .map(|(player, cards)| {
+                        format!("{{\"player\":{},\"cards\":{:?}}}", player, cards)
+                    })
+                    .collect();
+                format!(
+                    "{{\"event\":\"round\",\"game\":{},\"depth\":{},\"round\":{},\"decks\":[{}],\"trick\":{}}}",
+                    game,
+                    depth,
+                    round,
+                    decks.join(","),
+                    pairs_json(trick, "player", "card")
+                )
+            }
+            Event::SubGame {
+                game,
+                depth,
+                sub_game,
+            } => format!(
+                "{{\"event\":\"sub_game\",\"game\":{},\"depth\":{},\"sub_game\":{}}}",
+                game, depth, sub_game
+            ),
+            Event::RoundWon {
+                game,
+                depth,
+                round,
+                winner,
+            } => format!(
+                "{{\"event\":\"round_won\",\"game\":{},\"depth\":{},\"round\":{},\"winner\":{}}}",
+                game, depth, round, winner
+            ),
+            Event::Repeat { game, depth, round } => format!(
+                "{{\"event\":\"repeat\",\"game\":{},\"depth\":{},\"round\":{}}}",
+                game, depth, round
+            ),
+            Event::GameWon {
+                game,
+                depth,
+                winner,
+            } => format!(
+                "{{\"event\":\"game_won\",\"game\":{},\"depth\":{},\"winner\":{}}}",
+                game, depth, winner
+            ),
         }
-        // winner takes the cards: (NOTE: for simplicity this relies on it being a two-player game!)
-        players[winner_index].push_back(trick[winner_index]);
-        players[winner_index].push_back(trick[1 - winner_index]);
-        // check whether one players lost all their cards, then the other wins:
-        if players.iter().any(|deck| deck.is_empty()) {
-            return players
-                .into_iter()
-                .enumerate()
-                .find(|(_, deck)| !deck.is_empty())
+    }
+}
+
+/// Write the whole log as a JSON array, one event per line
+pub fn log_to_json(log: &[Event]) -> String {
+    let events: Vec<_> = log.iter().map(|event| event.to_json()).collect();
+    format!("[\n{}\n]", events.join(",\n"))
+}
+
+/// Game engine for any number of players. In every round all players with cards left play their top card,
+/// the round winner (decided by `round_winner`) puts their own card and then the other cards,
+/// highest first, at the bottom of their deck. Players without cards drop out, until one player is left.
+/// In recursive games, if every player has at least as many cards left as the value of their card,
+/// a sub-game with that many cards decides the round, and a repeated round means that the first player
+/// of the game wins (decks are hashed incrementally to look that up quickly).
+/// ```
+/// use advent_of_code_202x::generated::year2020day22::{lowest_card, Combat};
+/// let decks = vec![vec![9, 2, 6, 3, 1], vec![5, 8, 4, 7, 10]];
+/// assert_eq!(Combat::new(false).play(&decks), (1, vec![3, 2, 10, 6, 8, 5, 9, 4, 7, 1]));
+/// assert_eq!(Combat::new(true).play(&decks), (1, vec![7, 5, 6, 2, 4, 1, 10, 8, 9, 3]));
+/// // the lowest card wins instead:
+/// assert_eq!(Combat::new(false).with_round_winner(lowest_card).play(&decks).0, 0);
+/// // three players, the first one drops out first, then the second:
+/// let decks = vec![vec![5, 1, 7], vec![2, 8, 4], vec![3, 6, 9]];
+/// assert_eq!(Combat::new(false).play(&decks), (2, vec![9, 8, 5, 7, 6, 3, 4, 2, 1]));
+/// // this would loop forever without the repeat rule:
+/// let decks = vec![vec![43, 19], vec![2, 29, 14]];
+/// assert_eq!(Combat::new(true).play(&decks), (0, vec![43, 19]));
+/// ```
+///
+/// Compare recursive games with a naive version that remembers all decks, for random decks:
+/// ```
+/// use advent_of_code_202x::generated::year2020day22::Combat;
+/// use advent_of_code_202x::testing::XorShift;
+/// use std::collections::{HashSet, VecDeque};
+/// fn naive(mut a: VecDeque<usize>, mut b: VecDeque<usize>) -> (usize, Vec<usize>) {
+///     let mut seen = HashSet::new();
+///     loop {
+///         if b.is_empty() || !seen.insert((a.clone(), b.clone())) {
+///             return (0, a.into());
+///         }
+///         if a.is_empty() {
+///             return (1, b.into());
+///         }
+///         let (x, y) = (a.pop_front().unwrap(), b.pop_front().unwrap());
+///         let a_wins = if a.len() >= x && b.len() >= y {
+///             naive(a.iter().take(x).copied().collect(), b.iter().take(y).copied().collect()).0 == 0
+///         } else {
+///             x > y
+///         };
+///         if a_wins {
+///             a.extend([x, y]);
+///         } else {
+///             b.extend([y, x]);
+///         }
+///     }
+/// }
+/// // the same cards split differently, at different points of one game:
+/// let decks = vec![vec![4, 7], vec![3, 2, 5, 6, 1]];
+/// assert_eq!(Combat::new(true).play(&decks), (0, vec![3, 7, 2]));
+/// let mut rng = XorShift::default();
+/// for _ in 0..3000 {
+///     let len = 2 + rng.index(9);
+///     let mut cards: Vec<usize> = (1..=len).collect();
+///     for i in (1..len).rev() {
+///         cards.swap(i, rng.index(i + 1));
+///     }
+///     let (a, b) = cards.split_at(1 + rng.index(len - 1));
+///     let expected = naive(a.iter().copied().collect(), b.iter().copied().collect());
+///     assert_eq!(Combat::new(true).play(&[a.to_vec(), b.to_vec()]), expected, "{:?} {:?}", a, b);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Combat {
+    recursive: bool,
+    round_winner: RoundWinner,
+    log: Option<Vec<Event>>,
+    games: usize,
+}
+
+impl Combat {
+    pub fn new(recursive: bool) -> Self {
+        Combat {
+            recursive,
+            round_winner: highest_card,
+            log: None,
+            games: 0,
+        }
+    }
+
+    pub fn with_round_winner(mut self, round_winner: RoundWinner) -> Self {
+        self.round_winner = round_winner;
+        self
+    }
+
+    /// record a replay log while playing
+    /// ```
+    /// use advent_of_code_202x::generated::year2020day22::{log_to_json, Combat};
+    /// let mut combat = Combat::new(true).with_log();
+    /// combat.play(&[vec![9, 2, 6, 3, 1], vec![5, 8, 4, 7, 10]]);
+    /// let log = combat.log().unwrap();
+    /// assert_eq!(log[1].to_string(), "-- Round 1 (Game 1) --\nPlayer 1's deck: 9, 2, 6, 3, 1\n\
+    ///     Player 2's deck: 5, 8, 4, 7, 10\nPlayer 1 plays: 9\nPlayer 2 plays: 5");
+    /// assert_eq!(log[2].to_string(), "Player 1 wins round 1 of game 1!");
+    /// assert_eq!(log.iter().filter(|event| event.to_string().contains("=== Game")).count(), 5);
+    /// assert_eq!(log.last().unwrap().to_string(), "The winner of game 1 is player 2!");
+    /// assert!(log_to_json(log).contains(r#"{"event":"sub_game","game":1,"depth":0,"sub_game":2}"#));
+    /// ```
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(Vec::new());
+        self
+    }
+
+    pub fn log(&self) -> Option<&[Event]> {
+        self.log.as_deref()
+    }
+
+    fn record(&mut self, event: impl FnOnce() -> Event) {
+        if let Some(log) = self.log.as_mut() {
+            log.push(event());
+        }
+    }
+
+    /// play a game and return the winning player's index and deck
+    pub fn play(&mut self, decks: &[Vec<usize>]) -> (usize, Vec<usize>) {
+        let players = decks
+            .iter()
+            .enumerate()
+            .map(|(player, cards)| (player, HashedDeck::new(cards.iter().copied())))
+            .collect();
+        let (winner, deck) = self.play_game(players, 0);
+        (winner, deck.cards.into())
+    }
+
+    /// play game rounds until one player has all the cards and return that player and the deck
+    fn play_game(
+        &mut self,
+        mut players: Vec<(usize, HashedDeck)>,
+        depth: usize,
+    ) -> (usize, HashedDeck) {
+        self.games += 1;
+        let game = self.games;
+        self.record(|| Event::GameStart { game, depth });
+        let mut game_history = HashSet::new();
+        let mut round = 0;
+        while players.len() > 1 {
+            round += 1;
+            if self.recursive && !game_history.insert(game_state(&players)) {
+                self.record(|| Event::Repeat { game, depth, round });
+                let (winner, deck) = players.swap_remove(0);
+                self.record(|| Event::GameWon {
+                    game,
+                    depth,
+                    winner: winner + 1,
+                });
+                return (winner, deck);
+            }
+            // the decks before the round, for the log:
+            let decks: Option<Vec<_>> = self.log.is_some().then(|| {
+                players
+                    .iter()
+                    .map(|(player, deck)| (player + 1, deck.cards.iter().copied().collect()))
+                    .collect()
+            });
+            let trick: Vec<(usize, usize)> = players
+                .iter_mut()
+                .map(|(player, deck)| (*player, deck.pop_front().unwrap()))
+                .collect();
+            self.record(|| Event::Round {
+                game,
+                depth,
+                round,
+                decks: decks.unwrap(),
+                trick: trick
+                    .iter()
+                    .map(|&(player, card)| (player + 1, card))
+                    .collect(),
+            });
+            // recursive game?
+            let winner_position = if self.recursive
+                && players
+                    .iter()
+                    .zip(trick.iter())
+                    .all(|((_, deck), &(_, card))| deck.cards.len() >= card)
+            {
+                let sub_game = self.games + 1;
+                self.record(|| Event::SubGame {
+                    game,
+                    depth,
+                    sub_game,
+                });
+                let recurse_players = players
+                    .iter()
+                    .zip(trick.iter())
+                    .map(|((player, deck), &(_, card))| {
+                        (
+                            *player,
+                            HashedDeck::new(deck.cards.iter().take(card).copied()),
+                        )
+                    })
+                    .collect();
+                let (sub_winner, _) = self.play_game(recurse_players, depth + 1);
+                trick
+                    .iter()
+                    .position(|&(player, _)| player == sub_winner)
+                    .unwrap()
+            } else {
+                (self.round_winner)(&trick)
+            };
+            // winner takes the cards: their own card first, then the others by value
+            let (winner, winner_card) = trick[winner_position];
+            let mut others: Vec<usize> = trick
+                .iter()
+                .filter(|&&(player, _)| player != winner)
+                .map(|&(_, card)| card)
+                .collect();
+            others.sort_unstable_by(|a, b| b.cmp(a));
+            let (_, winner_deck) = players
+                .iter_mut()
+                .find(|(player, _)| *player == winner)
                 .unwrap();
+            winner_deck.push_back(winner_card);
+            others
+                .into_iter()
+                .for_each(|card| winner_deck.push_back(card));
+            self.record(|| Event::RoundWon {
+                game,
+                depth,
+                round,
+                winner: winner + 1,
+            });
+            // players without cards are out:
+            players.retain(|(_, deck)| !deck.cards.is_empty());
         }
+        let (winner, deck) = players.pop().unwrap();
+        self.record(|| Event::GameWon {
+            game,
+            depth,
+            winner: winner + 1,
+        });
+        (winner, deck)
     }
 }
 
 /// calculate the score: bottom card * 1, next * 2, and so on
-fn calc_score(deck: Deck) -> usize {
+fn calc_score(deck: &[usize]) -> usize {
     deck.iter()
         .zip((1..=deck.len()).rev())
         .fold(0, |acc, (&a, b)| acc + a * b)
 }
 
-fn cards_splitter(lines: &str) -> Deck {
+fn cards_splitter(lines: &str) -> Vec<usize> {
     lines
         .split('\n')
         .skip(1)
@@ -131,18 +585,18 @@ fn cards_splitter(lines: &str) -> Deck {
 
 pub fn process_input(input: &str) -> String {
     let input = input.trim().split("\n\n");
-    let players: Vec<Deck> = input.map(cards_splitter).collect();
+    let players: Vec<Vec<usize>> = input.map(cards_splitter).collect();
     //println!("Input: {:?}", &players);
-    let (winner_1, winning_deck_1) = play_combat(players.clone());
+    let (winner_1, winning_deck_1) = Combat::new(false).play(&players);
     //println!("Winning deck part 1: {:?}", &winning_deck_1);
-    let (recursive_winner, winning_deck_2) = play_recursive_combat(players);
+    let (recursive_winner, winning_deck_2) = Combat::new(true).play(&players);
     //println!("Winning deck part 2: {:?}", &winning_deck_2);
     format!(
         "Part 1 Winner {} scores: {:?}\nPart 2 Winner {} scores: {:?}",
         winner_1 + 1,
-        calc_score(winning_deck_1),
+        calc_score(&winning_deck_1),
         recursive_winner + 1,
-        calc_score(winning_deck_2)
+        calc_score(&winning_deck_2)
     )
 }
 
@@ -153,3 +607,45 @@ pub fn run_example() -> String {
 pub fn run() -> String {
     process_input(INPUT)
 }
+
+/// Play one game with parameters `recursive=true|false` (default true), `rule=highest|lowest`
+/// and `log=text|json` to print the replay log, e.g. `cargo run 22 2020 x log=text`
+/// ```
+/// use advent_of_code_202x::generated::year2020day22::run_with_params;
+/// let params = [("recursive", "false"), ("log", "json")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// let output = run_with_params(&params, true);
+/// assert!(output.starts_with("[\n{\"event\":\"game_start\",\"game\":1,\"depth\":0},\n"));
+/// assert!(output.ends_with("]\nWinner 2 scores: 306"));
+/// let params = [("rule".to_string(), "random".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "unknown rule=random, use highest or lowest");
+/// let params = [("recursive".to_string(), "yes".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "invalid recursive=yes, use true or false");
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let input = if use_example { EXAMPLE_INPUT } else { INPUT };
+    let players: Vec<Vec<usize>> = input.trim().split("\n\n").map(cards_splitter).collect();
+    let recursive = match params.get("recursive").map(|r| (r, r.parse())) {
+        None => true,
+        Some((_, Ok(recursive))) => recursive,
+        Some((recursive, Err(_))) => {
+            return format!("invalid recursive={}, use true or false", recursive)
+        }
+    };
+    let round_winner = match params.get("rule").map(String::as_str) {
+        None | Some("highest") => highest_card,
+        Some("lowest") => lowest_card,
+        Some(rule) => return format!("unknown rule={}, use highest or lowest", rule),
+    };
+    let mut combat = Combat::new(recursive).with_round_winner(round_winner);
+    if params.contains_key("log") {
+        combat = combat.with_log();
+    }
+    let (winner, deck) = combat.play(&players);
+    let log = match (params.get("log").map(String::as_str), combat.log()) {
+        (Some("json"), Some(log)) => log_to_json(log) + "\n",
+        (_, Some(log)) => log.iter().map(|event| event.to_string() + "\n").collect(),
+        _ => String::new(),
+    };
+    format!("{}Winner {} scores: {}", log, winner + 1, calc_score(&deck))
+}
@@ -1,8 +1,8 @@
 //! https://adventofcode.com/2020/day/23
 //! Cups game with a crab
 //!
-//! part 1 implemented directly by holding the cup numbers in a vec
-//! part 2 needed a more efficient representation: neighbour links
+//! part 1 was first implemented directly by holding the cup numbers in a vec,
+//! part 2 needed a more efficient representation: neighbour links, now used for both parts
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2020day23::run;
@@ -11,6 +11,8 @@
 //! ));
 //! ```
 
+use itertools::Itertools;
+
 const INPUT: &str = "614752839";
 
 /// example answer after 100 moves: (1) 67384529
@@ -22,115 +24,205 @@ const INPUT: &str = "614752839";
 /// ```
 const EXAMPLE_INPUT: &str = "389125467";
 
+use crate::Params;
+use std::fmt;
+
 type Cups = Vec<usize>;
 
-/// play 100 moves of the cups game and return the new cups order
-#[allow(clippy::needless_collect)]
-fn play_game(mut cups: Cups) -> Cups {
-    let max_cup = *cups.iter().max().unwrap();
-    let min_cup = *cups.iter().min().unwrap();
-    for _ in 0..100 {
-        // need to collect here, as the items are later used to mutate cups again:
-        let to_be_moved: Cups = cups.drain(1..4).collect();
-        let mut target = cups[0] - 1;
-        let target_index: usize;
-        loop {
-            if target < min_cup {
-                target = max_cup;
-            }
-            if let Some(found_index) = cups.iter().position(|&c| c == target) {
-                target_index = found_index;
-                break;
+/// Integer type used for the links between cups, smaller ones make the circle more cache friendly
+pub trait Link: Copy {
+    /// the highest label a link can hold
+    const MAX_LABEL: usize;
+    fn from_label(label: usize) -> Self;
+    fn label(self) -> usize;
+}
+
+impl Link for u32 {
+    const MAX_LABEL: usize = u32::MAX as usize;
+    fn from_label(label: usize) -> Self {
+        label as u32
+    }
+    fn label(self) -> usize {
+        self as usize
+    }
+}
+
+impl Link for usize {
+    // one less, to have room for the unused label 0:
+    const MAX_LABEL: usize = usize::MAX - 1;
+    fn from_label(label: usize) -> Self {
+        label
+    }
+    fn label(self) -> usize {
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircleError {
+    /// the total number of cups does not fit into the links
+    TooManyCups(usize),
+    /// picking up this many cups leaves no destination
+    TooFewCups { total: usize, pick_up: usize },
+    /// a starting cup that is not in 1..=total, or given twice
+    InvalidCup(usize),
+}
+
+impl fmt::Display for CircleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CircleError::TooManyCups(total) => {
+                write!(f, "{} cups do not fit into the links of the circle", total)
             }
-            target -= 1;
+            CircleError::TooFewCups { total, pick_up } => write!(
+                f,
+                "{} cups are too few to pick up {}, need at least 2 more",
+                total, pick_up
+            ),
+            CircleError::InvalidCup(cup) => write!(f, "invalid cup {}", cup),
         }
-        to_be_moved
-            .into_iter()
-            .rev()
-            .for_each(|ele| cups.insert(target_index + 1, ele));
-        cups.rotate_left(1);
     }
-    cups
 }
 
-/// struct tracks the clockwise neighbour of every number on a circle
-/// minimum number is always 1 and the circle is filled up with nums up to max
-#[derive(Debug)]
-struct Circle {
-    cw_neighbour: Vec<usize>, // here used as a list of links to the clockwise neighbour
-    current: usize,
-    max: usize,
+impl std::error::Error for CircleError {}
+
+/// struct tracks the clockwise neighbour of every cup label on a circle
+/// labels are 1 up to the total number of cups
+/// ```
+/// use advent_of_code_202x::generated::year2020day23::Circle;
+/// let mut circle: Circle = Circle::new(&[3, 8, 9, 1, 2, 5, 4, 6, 7], 9, 3).unwrap();
+/// circle.play(10);
+/// assert_eq!(circle.iter_from(1).skip(1).collect::<Vec<_>>(), [9, 2, 6, 5, 8, 3, 7, 4]);
+/// // the cups not given are added in increasing order, any number of cups can be picked up:
+/// let mut circle: Circle = Circle::new(&[3, 1], 6, 2).unwrap();
+/// assert_eq!(circle.iter_from(3).collect::<Vec<_>>(), [3, 1, 2, 4, 5, 6]);
+/// circle.play(1); // pick up 1 and 2, and put them after 6
+/// assert_eq!(circle.iter_from(3).collect::<Vec<_>>(), [3, 4, 5, 6, 1, 2]);
+/// assert_eq!(circle.current(), 4);
+/// ```
+///
+/// ```
+/// use advent_of_code_202x::generated::year2020day23::{Circle, CircleError};
+/// assert_eq!(Circle::<u32>::new(&[1, 2], 1 << 32, 3).unwrap_err(), CircleError::TooManyCups(1 << 32));
+/// assert_eq!(Circle::<u32>::new(&[1, 2], 4, 3).unwrap_err(), CircleError::TooFewCups { total: 4, pick_up: 3 });
+/// assert_eq!(Circle::<u32>::new(&[1, 0], 9, 3).unwrap_err(), CircleError::InvalidCup(0));
+/// assert_eq!(Circle::<u32>::new(&[1, 1], 9, 3).unwrap_err(), CircleError::InvalidCup(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Circle<L: Link = u32> {
+    cw_neighbour: Vec<L>, // list of links to the clockwise neighbour, indexed by label
+    current: L,
+    picked_up: Vec<usize>,
 }
 
-impl Circle {
-    fn new(starting_cups: Cups, max: usize) -> Self {
-        let start = *starting_cups.first().unwrap();
-        // use a vec with max + 1 entries, to use the num directly as index,
-        // 0th entry is meaningless. initialize with default sequence:
-        let mut neighbours: Vec<usize> = (1..max + 2).collect();
-        neighbours[max] = start; // close circle
-        neighbours[0] = 0; // take meaningless element out of circle
-        for a_b in starting_cups.windows(2) {
-            neighbours[a_b[0]] = a_b[1];
+impl<L: Link> Circle<L> {
+    /// Create a circle starting with the given cups, filled up with the missing labels up to `total`.
+    /// The cups need to be distinct labels in 1..=total, and there need to be enough cups
+    /// to pick up `pick_up` cups and still have a destination.
+    pub fn new(starting_cups: &[usize], total: usize, pick_up: usize) -> Result<Self, CircleError> {
+        if total > L::MAX_LABEL {
+            return Err(CircleError::TooManyCups(total));
         }
-        neighbours[*starting_cups.last().unwrap()] = *starting_cups.iter().max().unwrap() + 1;
-        Circle {
-            cw_neighbour: neighbours,
-            current: start,
-            max,
+        if pick_up.checked_add(2).is_none_or(|needed| total < needed) {
+            return Err(CircleError::TooFewCups { total, pick_up });
+        }
+        let mut seen = vec![false; total + 1];
+        for &cup in starting_cups {
+            if !(1..=total).contains(&cup) || seen[cup] {
+                return Err(CircleError::InvalidCup(cup));
+            }
+            seen[cup] = true;
         }
+        let order: Vec<usize> = starting_cups
+            .iter()
+            .copied()
+            .chain((1..=total).filter(|&cup| !seen[cup]))
+            .collect();
+        // use a vec with total + 1 entries, to use the label directly as index,
+        // 0th entry is meaningless:
+        let mut neighbours = vec![L::from_label(0); total + 1];
+        for a_b in order.windows(2) {
+            neighbours[a_b[0]] = L::from_label(a_b[1]);
+        }
+        neighbours[order[total - 1]] = L::from_label(order[0]); // close circle
+        Ok(Circle {
+            cw_neighbour: neighbours,
+            current: L::from_label(order[0]),
+            picked_up: vec![0; pick_up],
+        })
+    }
+
+    pub fn current(&self) -> usize {
+        self.current.label()
+    }
+
+    pub fn next(&self, label: usize) -> usize {
+        self.cw_neighbour[label].label()
+    }
+
+    /// Iterate once around the circle clockwise, starting with `label`
+    pub fn iter_from(&self, label: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(Some(label), move |&cup| Some(self.next(cup)))
+            .take(self.cw_neighbour.len() - 1)
     }
 
-    /// play 10_000_000 moves of the cups game and return the new cups order
-    fn play_game(&mut self) {
-        for _ in 0..10_000_000 {
-            let a = self.cw_neighbour[self.current];
-            let b = self.cw_neighbour[a];
-            let c = self.cw_neighbour[b];
-            let mut target = self.current - 1;
-            while [0, a, b, c].contains(&target) {
+    /// play the given number of moves of the cups game
+    pub fn play(&mut self, moves: usize) {
+        let max = self.cw_neighbour.len() - 1;
+        for _ in 0..moves {
+            let current = self.current.label();
+            let mut last_picked = current;
+            for picked in self.picked_up.iter_mut() {
+                last_picked = self.cw_neighbour[last_picked].label();
+                *picked = last_picked;
+            }
+            let mut target = current - 1;
+            while target == 0 || self.picked_up.contains(&target) {
                 if target == 0 {
-                    target = self.max;
+                    target = max;
                 } else {
                     target -= 1;
                 }
             }
-            // cut a,b,c out from circle
-            self.cw_neighbour[self.current] = self.cw_neighbour[c];
-            // insert a,b,c clockwise from target:
-            self.cw_neighbour[c] = self.cw_neighbour[target];
-            self.cw_neighbour[target] = a;
-            self.current = self.cw_neighbour[self.current];
+            if let Some(&first_picked) = self.picked_up.first() {
+                // cut the picked up cups out from circle
+                self.cw_neighbour[current] = self.cw_neighbour[last_picked];
+                // insert them clockwise from target:
+                self.cw_neighbour[last_picked] = self.cw_neighbour[target];
+                self.cw_neighbour[target] = L::from_label(first_picked);
+            }
+            self.current = self.cw_neighbour[current];
         }
     }
 }
 
-fn cups_splitter(line: &str) -> Cups {
-    line.chars()
-        .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect()
+/// Return the cups given as digits or comma separated numbers, or None if they are neither
+fn cups_splitter(line: &str) -> Option<Cups> {
+    if line.contains(',') {
+        line.split(',').map(|cup| cup.trim().parse().ok()).collect()
+    } else {
+        line.chars()
+            .map(|c| c.to_digit(10).map(|cup| cup as usize))
+            .collect()
+    }
+}
+
+/// Return the two cups clockwise from cup 1
+fn neighbours_of_1<L: Link>(circle: &Circle<L>) -> (usize, usize) {
+    let a = circle.next(1);
+    (a, circle.next(a))
 }
 
 pub fn process_input(input: &str) -> String {
-    let starting_cups: Cups = cups_splitter(input);
+    let starting_cups: Cups = cups_splitter(input).unwrap();
     //println!("Input: {:?}", &starting_cups);
-    let part1_cups = play_game(starting_cups.clone());
+    let mut circle: Circle = Circle::new(&starting_cups, starting_cups.len(), 3).unwrap();
+    circle.play(100);
+    let part1_cups: Vec<usize> = circle.iter_from(circle.current()).collect();
     // part 2: extend cups to 1 million, do 10 million moves
-    // -> need a specialized structure, a linked-list in an array
-    let mut circle = Circle::new(starting_cups, 1_000_000);
-    //println!(
-    //    "Circle first 12: {:?} last 5: {:?}",
-    //    &circle.cw_neighbour[..12],
-    //    &circle.cw_neighbour[circle.max - 4..]
-    //);
-    circle.play_game();
-    // println!(
-    //     "Circle first 12: {:?} last 5: {:?}",
-    //     &circle.cw_neighbour[..12],
-    //     &circle.cw_neighbour[circle.max - 4..]
-    // );
-    let a = circle.cw_neighbour[1];
-    let b = circle.cw_neighbour[a];
+    let mut circle: Circle = Circle::new(&starting_cups, 1_000_000, 3).unwrap();
+    circle.play(10_000_000);
+    let (a, b) = neighbours_of_1(&circle);
     format!(
         "Part 1 final cups: {}\nProduct of 1-neighbours: {} * {} = {}",
         &part1_cups
@@ -151,3 +243,64 @@ pub fn run_example() -> String {
 pub fn run() -> String {
     process_input(INPUT)
 }
+
+/// Play with parameters `cups=389125467` (or comma separated labels), `total=1000000`,
+/// `moves=10000000` and `pickup=3`, e.g. `cargo run 23 2020 total=20 moves=1000 pickup=5`
+/// ```
+/// use advent_of_code_202x::generated::year2020day23::run_with_params;
+/// let params = [("total", "9"), ("moves", "100")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(
+///     run_with_params(&params, true),
+///     "Cups clockwise from 1: 6,7,3,8,4,5,2,9\nProduct of 1-neighbours: 6 * 7 = 42"
+/// );
+/// let params = [("moves", "-1")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "invalid moves=-1, expected a non-negative integer");
+/// let params = [("total", "9"), ("pickup", "8")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "9 cups are too few to pick up 8, need at least 2 more");
+/// let params = [("cups", "1,x")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "invalid cups=1,x, expected digits or comma separated numbers");
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let cups = params
+        .get("cups")
+        .map_or(if use_example { EXAMPLE_INPUT } else { INPUT }, |cups| cups);
+    let Some(starting_cups) = cups_splitter(cups) else {
+        return format!(
+            "invalid cups={}, expected digits or comma separated numbers",
+            cups
+        );
+    };
+    let get = |key, default| match params.get(key).map(|value| (value, value.parse())) {
+        Some((_, Ok(value))) => Ok(value),
+        Some((value, Err(_))) => Err(format!(
+            "invalid {}={}, expected a non-negative integer",
+            key, value
+        )),
+        None => Ok(default),
+    };
+    let (total, moves, pick_up) = match (
+        get("total", 1_000_000),
+        get("moves", 10_000_000),
+        get("pickup", 3),
+    ) {
+        (Ok(total), Ok(moves), Ok(pick_up)) => (total.max(starting_cups.len()), moves, pick_up),
+        (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => return error,
+    };
+    let mut circle: Circle = match Circle::new(&starting_cups, total, pick_up) {
+        Ok(circle) => circle,
+        Err(error) => return error.to_string(),
+    };
+    circle.play(moves);
+    let (a, b) = neighbours_of_1(&circle);
+    format!(
+        "Cups clockwise from 1: {}\nProduct of 1-neighbours: {} * {} = {}",
+        circle.iter_from(1).skip(1).take(20).join(","),
+        a,
+        b,
+        a as u64 * b as u64
+    )
+}
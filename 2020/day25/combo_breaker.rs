@@ -24,61 +24,50 @@ const EXAMPLE_INPUT: &str = "
 17807724
 ";
 
-const BASE: i32 = 7;
-const MODULO: i32 = 20201227;
+use crate::modint::ModInt;
+use crate::number_theory::discrete_log;
 
-/// find the discrete log of num with base (modulo modulo)
-/// (extended euclidean algorithm)
-fn find_discrete_log(num: i32, base: i32, modulo: i32) -> i32 {
-    //if num % base == 0 {
-    //    return num / base;
-    //}
-    // do it by "trial multiplication" first (brute force search):
-    let mut value = 1;
-    for ll in 1..modulo {
-        value *= base;
-        value %= modulo;
-        if value == num {
-            return ll;
-        }
-    }
-    value // should be unreachable
-}
+const BASE: u64 = 7;
+const MODULO: u64 = 20201227;
 
-fn transform_subject_number(subject: i32, loop_size: i32, modulo: i32) -> i32 {
-    let mut value: i64 = 1;
-    let subject = i64::from(subject);
-    let modulo = i64::from(modulo);
-    for _ll in 1..=loop_size {
-        value *= subject;
-        value %= modulo;
-    }
-    value as i32
+/// values are transformed modulo 20201227
+type Key = ModInt<u32, MODULO>;
+
+/// Return the encryption key for the two public keys: one key transformed with the loop size of the other.
+/// The loop size is a discrete log, found with baby-step giant-step in the small prime-order subgroups
+/// (20201226 = 2 * 3 * 29 * 116099), instead of trying all loop sizes up to the modulo.
+/// ```
+/// use advent_of_code_202x::generated::year2020day25::find_encryption_key;
+/// assert_eq!(find_encryption_key(5764801, 17807724), Some(14897079));
+/// // works for any public keys below the modulo, 20201226 = -1 has loop size 10100613:
+/// assert_eq!(find_encryption_key(20201226, 20201226), Some(20201226));
+/// assert_eq!(find_encryption_key(4, 20201226), Some(1));
+/// assert_eq!(find_encryption_key(20201227, 17807724), None);
+/// ```
+pub fn find_encryption_key(card_public_key: u64, door_public_key: u64) -> Option<u64> {
+    // find discrete logarithm with base 7 in modular arithmetic to get the loop-size:
+    let door_loop_size = discrete_log(BASE, door_public_key, MODULO)?;
+    // check that it's true:
+    debug_assert_eq!(
+        Key::new(BASE).pow(door_loop_size),
+        Key::new(door_public_key)
+    );
+    // calculate the encryption key, the card key is only valid if it has a loop size too:
+    discrete_log(BASE, card_public_key, MODULO)?;
+    let encryption_key = Key::new(card_public_key).pow(door_loop_size);
+    Some(encryption_key.value() as u64)
 }
 
 pub fn process_input(input: &str) -> String {
-    let input: Vec<_> = input.trim().split('\n').collect();
-    let public_keys: Vec<_> = input.iter().map(|pk| pk.parse::<i32>().unwrap()).collect();
-    //println!("Input keys: {:?}", &public_keys);
-    // find discrete logarithm with base 7 in modular arithmetic to get the loop-sizes:
-    let loop_sizes: Vec<_> = public_keys
-        .iter()
-        .map(|&pk| find_discrete_log(pk, BASE, MODULO))
+    let public_keys: Vec<u64> = input
+        .trim()
+        .split('\n')
+        .map(|pk| pk.parse().unwrap())
         .collect();
-    //println!("Loop sizes: {:?}", &loop_sizes);
-    // check that it's true:
-    // for loop_s in &loop_sizes {
-    //     println!(
-    //         "transform subject number {} -> {}",
-    //         BASE,
-    //         transform_subject_number(7, *loop_s, MODULO)
-    //     );
-    // }
-    // calculate the encryption key
-    format!(
-        "Encryption key: {}",
-        transform_subject_number(public_keys[0], loop_sizes[1], MODULO)
-    )
+    match find_encryption_key(public_keys[0], public_keys[1]) {
+        Some(key) => format!("Encryption key: {}", key),
+        None => "No loop size for public keys".to_owned(),
+    }
 }
 
 pub fn run_example() -> String {
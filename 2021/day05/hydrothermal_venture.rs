@@ -31,94 +31,46 @@ const EXAMPLE_INPUT: &str = "
 5,5 -> 8,2
 ";
 
-use num::{PrimInt, Unsigned};
-use std::collections::HashMap;
-use std::iter;
+use crate::line::{count_overlaps, Point, Segment};
 
-type Point = (usize, usize);
-type LineSpec = (Point, Point);
-/// hashmap that records how often a point is used by lines
-type PosMap = HashMap<Point, usize>;
-
-fn abs_diff<U>(slf: U, other: U) -> U
-where
-    U: PrimInt + Unsigned,
-{
-    if slf < other {
-        other - slf
-    } else {
-        slf - other
-    }
+fn str_to_i64(a_str: &str) -> i64 {
+    a_str.parse::<i64>().unwrap()
 }
 
-fn calc_points_use_count(line_specs: &[LineSpec], diag: bool) -> PosMap {
-    let mut points_used = PosMap::new();
-    for ((a, b), (x, y)) in line_specs {
-        if a != x && b != y {
-            assert_eq!(abs_diff(*x, *a), abs_diff(*y, *b)); // as noted in the task
-            if !diag {
-                continue;
-            }
-        }
-        let a_range: Box<dyn Iterator<Item = usize>> = if a != x {
-            if x > a {
-                Box::new(*a..=*x)
-            } else {
-                Box::new((*x..=*a).rev())
-            }
-        } else {
-            // repeat a as needed:
-            Box::new(iter::repeat(*a))
-        };
-        let b_range: Box<dyn Iterator<Item = usize>> = if b != y {
-            if y > b {
-                Box::new(*b..=*y)
-            } else {
-                Box::new((*y..=*b).rev())
-            }
-        } else {
-            // repeat b as needed:
-            Box::new(iter::repeat(*b))
-        };
-        for (index_a, index_b) in a_range.zip(b_range) {
-            *points_used.entry((index_a, index_b)).or_insert(0) += 1;
+fn line_splitter(line: &str) -> Segment {
+    // very verbose, next time try itertools::next_tuple
+    let point = |x: &str, y: &str| -> Point { (str_to_i64(x), str_to_i64(y)) };
+    match line.trim().split([',', ' ']).collect::<Vec<&str>>()[..] {
+        [startx, starty, "->", endx, endy] => {
+            Segment::new(point(startx, starty), point(endx, endy))
         }
+        _ => panic!("No line-spec found!"),
     }
-    points_used
-}
-
-fn get_doubly_used_points(points_used: &PosMap) -> usize {
-    points_used.values().filter(|count| count > &&1).count()
 }
 
-fn str_to_usize(a_str: &str) -> usize {
-    a_str.parse::<usize>().unwrap()
-}
-
-fn line_splitter(line: &str) -> LineSpec {
-    // very verbose, next time try itertools::next_tuple
-    match line
-        .trim()
-        .split(&[',', ' ', '-', '>'][..])
-        .collect::<Vec<&str>>()[..]
-    {
-        [startx, starty, _, _, _, endx, endy, ..] => (
-            (str_to_usize(startx), str_to_usize(starty)),
-            (str_to_usize(endx), str_to_usize(endy)),
-        ),
-        _ => panic!("No line-spec found!"),
-    }
+/// Return the number of points used by more than one line, for any slopes
+/// ```
+/// use advent_of_code_202x::generated::year2021day05::count_doubly_used_points;
+/// // negative coordinates and a line with slope 2:
+/// assert_eq!(count_doubly_used_points("-3,-3 -> 3,3\n-2,-4 -> 1,2\n5,-4 -> -5,-4"), 2);
+/// ```
+pub fn count_doubly_used_points(input: &str) -> usize {
+    let segments: Vec<_> = input.trim().split('\n').map(line_splitter).collect();
+    count_overlaps(&segments)
 }
 
 pub fn process_input(input: &str) -> String {
-    let line_specs: Vec<_> = input.trim().split('\n').map(line_splitter).collect();
-    //println!("lines:\n{:?}", line_specs);
-    let points_used_1 = calc_points_use_count(&line_specs, false);
-    let points_used_2 = calc_points_use_count(&line_specs, true);
+    let segments: Vec<_> = input.trim().split('\n').map(line_splitter).collect();
+    //println!("lines:\n{:?}", segments);
+    let straight: Vec<_> = segments
+        .iter()
+        .filter(|segment| segment.is_horizontal() || segment.is_vertical())
+        .copied()
+        .collect();
     format!(
         "Number of doubly used points, straight-only: {}\nNumber of doubly used points: {}",
-        get_doubly_used_points(&points_used_1),
-        get_doubly_used_points(&points_used_2)
+        count_overlaps(&straight),
+        count_overlaps(&segments)
     )
 }
 
@@ -18,40 +18,78 @@ const INPUT: &str = include_str!("input");
 /// ```
 const EXAMPLE_INPUT: &str = "3,4,3,1,2";
 
-/// vec that records how many fish with a certain timer are present
-type FishRegister = [usize; 9];
+use crate::linear_recurrence::{Arithmetic, CountError, LinearRecurrence};
+use crate::Params;
 
-fn count_after_sim_steps(timers: &[usize], sim_steps: usize) -> usize {
-    let mut simulation: FishRegister = [0; 9];
-    for timer in timers {
-        simulation[*timer] += 1;
+/// the states are the timers of the fish: each timer counts down,
+/// and a fish with timer 0 resets to 6 and spawns a new fish with timer 8
+fn lanternfish_rules() -> LinearRecurrence<usize> {
+    let mut rules = LinearRecurrence::new();
+    for timer in 1..=8 {
+        rules.add_transition(timer, timer - 1, 1);
     }
-    for _step in 0..sim_steps {
-        // rotate all counts one index down (which will move the 0-count to index 8),
-        // and add the 0-count (now at index 8) once to 6:
-        simulation.rotate_left(1);
-        simulation[6] += simulation[8];
-        // more efficient but much less clear would be to skip the rotating,
-        // and use (_step + 7) % 9 to identify the count that needs to be increased:
-        //simulation[(_step + 7) % 9] += simulation[_step % 9]
-    }
-    simulation.iter().sum()
+    rules.add_transition(0, 6, 1);
+    rules.add_transition(0, 8, 1);
+    rules
+}
+
+/// Count the fish after any number of steps, with matrix powers instead of simulating step by step
+/// ```
+/// use advent_of_code_202x::generated::year2021day06::count_after_sim_steps;
+/// use advent_of_code_202x::linear_recurrence::{Arithmetic, CountError};
+/// assert_eq!(count_after_sim_steps(&[3, 4, 3, 1, 2], 18, Arithmetic::Checked), Ok(26));
+/// assert_eq!(count_after_sim_steps(&[3], 1000, Arithmetic::Checked), Ok(73027160854862288386253621336887521653));
+/// assert_eq!(count_after_sim_steps(&[3], 3000, Arithmetic::Checked), Err(CountError::Overflow));
+/// assert_eq!(count_after_sim_steps(&[3, 9], 1, Arithmetic::Checked), Err(CountError::UnknownState(9)));
+/// let prime = 1_000_000_007;
+/// assert_eq!(count_after_sim_steps(&[3, 4, 3, 1, 2], 256, Arithmetic::Modulo(prime)), Ok(26984457539 % prime as u128));
+/// ```
+pub fn count_after_sim_steps(
+    timers: &[usize],
+    sim_steps: u64,
+    arithmetic: Arithmetic,
+) -> Result<u128, CountError<usize>> {
+    let counts = lanternfish_rules().count_after(
+        timers.iter().map(|&timer| (timer, 1)),
+        sim_steps,
+        arithmetic,
+    )?;
+    let total = counts
+        .values()
+        .try_fold(0, |sum, &count| arithmetic.add(sum, count))?;
+    Ok(total)
+}
+
+/// Describe the number of fish after the days, or why it could not be counted
+fn describe_count(timers: &[usize], sim_steps: u64, arithmetic: Arithmetic) -> String {
+    let count = match count_after_sim_steps(timers, sim_steps, arithmetic) {
+        Ok(num_fish) => match arithmetic {
+            Arithmetic::Checked => num_fish.to_string(),
+            Arithmetic::Modulo(modulus) => format!("{} (mod {})", num_fish, modulus),
+        },
+        Err(CountError::Overflow) => format!("{}, try mod=<prime>", CountError::<usize>::Overflow),
+        Err(CountError::UnknownState(timer)) => {
+            format!("invalid timer {} in the input, expected 0 to 8", timer)
+        }
+    };
+    format!("number of fish after {} days: {}", sim_steps, count)
 }
 
 fn str_to_usize(a_str: &str) -> usize {
     a_str.parse::<usize>().unwrap()
 }
 
+fn parse_timers(input: &str) -> Vec<usize> {
+    input.trim().split(',').map(str_to_usize).collect()
+}
+
 pub fn process_input(input: &str) -> String {
-    let timers: Vec<_> = input.trim().split(',').map(str_to_usize).collect();
+    let timers = parse_timers(input);
     //println!("timers:\n{:?}", timers);
-    let sim_steps_1: usize = 80;
-    let num_fish_1 = count_after_sim_steps(&timers, sim_steps_1);
-    let sim_steps_2: usize = 256;
-    let num_fish_2 = count_after_sim_steps(&timers, sim_steps_2);
     format!(
-        "number of fish after {} days: {}\nnumber of fish after {} days: {}",
-        sim_steps_1, num_fish_1, sim_steps_2, num_fish_2
+        "{}\n{}",
+        describe_count(&timers, 80, Arithmetic::Checked),
+        describe_count(&timers, 256, Arithmetic::Checked)
     )
 }
 
@@ -62,3 +100,36 @@ pub fn run_example() -> String {
 pub fn run() -> String {
     process_input(INPUT)
 }
+
+/// Count the fish after `days=N` days, optionally modulo `mod=M`,
+/// e.g. `cargo run 6 2021 days=1000000000000 mod=1000000007`
+/// ```
+/// use advent_of_code_202x::generated::year2021day06::run_with_params;
+/// let params = [("days", "1000000000000"), ("mod", "1000000007")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "number of fish after 1000000000000 days: 995077479 (mod 1000000007)");
+/// let params = [("days".to_string(), "5000".to_string())].into_iter().collect();
+/// assert!(run_with_params(&params, true).contains("does not fit into u128"));
+/// let params = [("mod".to_string(), "0".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "invalid mod=0, expected a positive integer");
+/// let params = [("days".to_string(), "-1".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "invalid days=-1, expected a non-negative integer");
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let timers = parse_timers(if use_example { EXAMPLE_INPUT } else { INPUT });
+    let sim_steps = match params.get("days").map(|days| (days, days.parse::<u64>())) {
+        Some((_, Ok(days))) => days,
+        Some((days, Err(_))) => {
+            return format!("invalid days={}, expected a non-negative integer", days)
+        }
+        None => 256,
+    };
+    let arithmetic = match params.get("mod").map(|m| (m, m.parse::<u64>())) {
+        Some((_, Ok(modulus))) if modulus > 0 => Arithmetic::Modulo(modulus),
+        Some((modulus, _)) => {
+            return format!("invalid mod={}, expected a positive integer", modulus)
+        }
+        None => Arithmetic::Checked,
+    };
+    describe_count(&timers, sim_steps, arithmetic)
+}
@@ -15,75 +15,26 @@ const INPUT: &str = include_str!("input");
 /// ```
 const EXAMPLE_INPUT: &str = "16,1,2,0,4,2,7,1,2,14";
 
-use num::{PrimInt, Unsigned};
+use crate::convex_cost::{minimize_total_cost, Cost};
+use crate::Params;
 
-fn abs_diff<U>(slf: U, other: U) -> U
-where
-    U: PrimInt + Unsigned,
-{
-    if slf < other {
-        other - slf
-    } else {
-        slf - other
-    }
-}
-
-/// expects an already sorted array slice!
-fn find_median(positions: &[usize]) -> usize {
-    let len = positions.len();
-    let mid = len / 2;
-    if len % 2 == 0 {
-        (positions[mid - 1] + positions[mid]) / 2
-    } else {
-        positions[mid]
-    }
-}
-
-fn find_average(positions: &[usize]) -> f32 {
-    positions.iter().sum::<usize>() as f32 / positions.len() as f32
-}
-
-fn calculate_fuel_use(positions: &[usize], target_pos: usize) -> usize {
-    positions.iter().map(|pos| abs_diff(*pos, target_pos)).sum()
-}
-
-fn calculate_fuel_use_triangular(positions: &[usize], target_pos: usize) -> usize {
-    positions
-        .iter()
-        .map(|pos| {
-            let diff = abs_diff(*pos, target_pos);
-            diff * (diff + 1) / 2
-        })
-        .sum()
+fn str_to_i64(a_str: &str) -> i64 {
+    a_str.parse::<i64>().unwrap()
 }
 
-/// didn't bother with proving that, but the best case should be at the average,
-/// or at least very close by
-fn find_best_fuel_use_triangular(positions: &[usize]) -> (usize, usize) {
-    let mut target_pos = find_average(positions).round() as usize;
-    let mut best_case = calculate_fuel_use_triangular(positions, target_pos);
-    for new_target in [target_pos - 1, target_pos + 1] {
-        let new_sum = calculate_fuel_use_triangular(positions, new_target);
-        if new_sum < best_case {
-            best_case = new_sum;
-            target_pos = new_target;
-        }
-    }
-    (target_pos, best_case)
-}
-
-fn str_to_usize(a_str: &str) -> usize {
-    a_str.parse::<usize>().unwrap()
+fn parse_positions(input: &str) -> Vec<i64> {
+    input.trim().split(',').map(str_to_i64).collect()
 }
 
 pub fn process_input(input: &str) -> String {
-    let mut positions: Vec<_> = input.trim().split(',').map(str_to_usize).collect();
-    positions.sort_unstable();
+    let positions = parse_positions(input);
     //println!("positions:\n{:?}", positions);
-    let median_position = find_median(&positions);
-    let fuel_use = calculate_fuel_use(&positions, median_position);
-    // different distance measure in part 2: triangular number
-    let (best_position, fuel_use_t) = find_best_fuel_use_triangular(&positions);
+    // linear distance: the best position is the median
+    let (median_position, fuel_use) =
+        minimize_total_cost(&positions, |d| Cost::Linear.cost(d)).unwrap();
+    // different distance measure in part 2: triangular number, the best position is close to the average
+    let (best_position, fuel_use_t) =
+        minimize_total_cost(&positions, |d| Cost::Triangular.cost(d)).unwrap();
     format!(
         "fuel for pos {}: {}\ntriangular fuel for pos {}: {}",
         median_position, fuel_use, best_position, fuel_use_t
@@ -97,3 +48,26 @@ pub fn run_example() -> String {
 pub fn run() -> String {
     process_input(INPUT)
 }
+
+/// Choose the cost model with `cost=linear|triangular|quadratic|capped:N`, e.g. `cargo run 7 2021 cost=capped:100`
+/// ```
+/// use advent_of_code_202x::generated::year2021day07::run_with_params;
+/// let params = [("cost".to_string(), "quadratic".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "quadratic fuel for pos 5: 291");
+/// let params = [("cost".to_string(), "capped:2".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "capped:2 fuel for pos 2: 67");
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let positions = parse_positions(if use_example { EXAMPLE_INPUT } else { INPUT });
+    let cost_name = params.get("cost").map_or("linear", String::as_str);
+    let cost: Cost = match cost_name.parse() {
+        Ok(cost) => cost,
+        Err(error) => return error.to_string(),
+    };
+    match minimize_total_cost(&positions, |d| cost.cost(d)) {
+        Some((position, fuel_use)) => {
+            format!("{} fuel for pos {}: {}", cost_name, position, fuel_use)
+        }
+        None => "fuel use does not fit into u128".to_owned(),
+    }
+}
@@ -1,6 +1,12 @@
 //! https://adventofcode.com/2021/day/8
 //! identify seven-segment display numbers based on randomly shuffled segment labels
 //!
+//! solved for any segment display: the symbols of the display are bitmasks of segments,
+//! and backtracking assigns symbols to the distinct observed patterns of shuffled wires.
+//! A wiring exists for the symbols assigned so far iff splitting the wires by the patterns
+//! they are in, and the segments by the symbols they are in, gives classes of equal sizes,
+//! so the wire permutations themselves never need to be enumerated.
+//!
 //! ```
 //! use advent_of_code_202x::generated::year2021day08::run;
 //! assert!(run().contains("num of unique len outputs: 375\nsum of outputs: 1019355"));
@@ -27,147 +33,379 @@ egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
 ";
 
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
-/// pattern of segments is a set of segment labels (char) unordered. Set operations are helpful:
-type SegmentPattern = BTreeSet<char>;
-/// an entry has the 10 unique segment patterns observed, and 4 specific output values seen:
-type DisplayEntry = ([SegmentPattern; 10], [SegmentPattern; 4]);
-/// need to find a mapping from pattern to digit:
-type DigitMapping = HashMap<SegmentPattern, usize>;
+/// at most this many segments (or wires), so sets of them fit into a u32 bitmask
+pub const MAX_SEGMENTS: usize = 32;
 
-fn count_unique_len_outputs(entries: &[DisplayEntry]) -> usize {
-    entries
-        .iter()
-        .map(|entry| {
-            entry
-                .1
-                .iter()
-                .filter(|patt| [2, 3, 4, 7].contains(&patt.len()))
-                .count()
-        })
-        .sum()
+/// the seven-segment digits, with segments a (top) to g (middle) like in the puzzle
+const SEVEN_SEGMENT_DIGITS: [(char, &str); 10] = [
+    ('0', "abcefg"),
+    ('1', "cf"),
+    ('2', "acdeg"),
+    ('3', "acdfg"),
+    ('4', "bcdf"),
+    ('5', "abdfg"),
+    ('6', "abdefg"),
+    ('7', "acf"),
+    ('8', "abcdefg"),
+    ('9', "abcdfg"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// a segment or wire label outside of a.. for the number of segments
+    UnknownLabel(char),
+    /// two symbols of a display with the same segments
+    DuplicateSegments(char, char),
+    /// no wire permutation explains all observed patterns
+    Inconsistent,
+    /// two wire permutations explain all patterns, but decode this one to different symbols
+    Ambiguous(String, char, char),
+    /// the decoded output is not a number
+    NotANumber(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnknownLabel(label) => write!(f, "unknown segment label {:?}", label),
+            DecodeError::DuplicateSegments(a, b) => {
+                write!(f, "symbols {} and {} have the same segments", a, b)
+            }
+            DecodeError::Inconsistent => write!(f, "no wiring matches all patterns"),
+            DecodeError::Ambiguous(pattern, a, b) => {
+                write!(f, "ambiguous: {} could be {} or {}", pattern, a, b)
+            }
+            DecodeError::NotANumber(output) => write!(f, "output {} is not a number", output),
+        }
+    }
 }
 
-fn extract_unique_digit(
-    digit: usize,
-    it: &mut dyn Iterator<Item = &SegmentPattern>,
-    mapping: &mut DigitMapping,
-    digits: &mut [Option<SegmentPattern>; 10],
-) {
-    let dig_patt = it.next().unwrap();
-    mapping.insert(dig_patt.clone(), digit);
-    digits[digit] = Some(dig_patt.clone());
-    assert_eq!(None, it.next());
+impl std::error::Error for DecodeError {}
+
+/// A segment display: which segments light up for each symbol, segments are labelled a, b, c, ...
+/// ```
+/// use advent_of_code_202x::generated::year2021day08::{DecodeError, SegmentDisplay};
+/// let display = SegmentDisplay::seven_segment();
+/// let patterns = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab cdfeb fcadb cdfeb cdbaf";
+/// let patterns: Vec<&str> = patterns.split(' ').collect();
+/// let decoded = display.decode(&patterns).unwrap();
+/// assert_eq!(decoded[10..].iter().collect::<String>(), "5353");
+/// // 1 and 7 alone do not tell which of the two wires is c and f:
+/// assert_eq!(display.decode(&["ab", "abd", "ba"]), Ok(vec!['1', '7', '1']));
+/// assert_eq!(display.decode(&["ab", "abd", "bd"]), Err(DecodeError::Inconsistent));
+/// assert_eq!(display.decode(&["ab", "abd", "abc"]), Err(DecodeError::Inconsistent));
+/// assert!(matches!(display.decode(&["abcde"]), Err(DecodeError::Ambiguous(..))));
+/// assert_eq!(display.decode(&["abz"]), Err(DecodeError::UnknownLabel('z')));
+/// assert_eq!(SegmentDisplay::new(2, &[('1', "ab"), ('7', "ba")]).unwrap_err(), DecodeError::DuplicateSegments('1', '7'));
+/// // all wirings decode a pattern with all segments lit the same, they are not tried one by one:
+/// let display = SegmentDisplay::new(16, &[('8', "abcdefghijklmnop"), ('1', "ab"), ('7', "abc")]).unwrap();
+/// assert_eq!(display.decode(&["ponmlkjihgfedcba", "xy"]), Err(DecodeError::UnknownLabel('x')));
+/// assert_eq!(display.decode(&["ponmlkjihgfedcba", "op", "opn", "po"]), Ok(vec!['8', '1', '7', '1']));
+/// ```
+///
+/// A 14-segment display, with the middle split in g (left) and h (right), and diagonals and verticals
+/// i (upper left), j (upper middle), k (upper right), l (lower left), m (lower middle) and n (lower right):
+/// ```
+/// use advent_of_code_202x::generated::year2021day08::SegmentDisplay;
+/// let display = SegmentDisplay::new(14, &[
+///     ('A', "abcefgh"), ('E', "adefg"), ('H', "bcefgh"), ('I', "adjm"),
+///     ('K', "efgkn"), ('M', "bcefik"), ('N', "bcefin"), ('T', "ajm"),
+///     ('V', "eflk"), ('X', "ikln"), ('Y', "ikm"), ('Z', "adkl"),
+/// ]).unwrap();
+/// // scrambled with a -> n, b -> m, ..., n -> a:
+/// let scramble = |symbol: &str| symbol.chars().map(|c| (b'a' + b'n' - c as u8) as char).collect::<String>();
+/// let words = ["ajm", "bcefgh", "adefg", "bcefik", "abcefgh", "ikln", "adkl", "bcefin", "adjm", "efgkn"];
+/// let patterns: Vec<String> = words.iter().map(|word| scramble(word)).collect();
+/// let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+/// assert_eq!(display.decode(&patterns).unwrap().iter().collect::<String>(), "THEMAXZNIK");
+/// ```
+///
+/// Compare with trying all permutations, for random displays:
+/// ```
+/// use advent_of_code_202x::generated::year2021day08::{DecodeError, SegmentDisplay};
+/// use itertools::Itertools;
+/// use advent_of_code_202x::testing::XorShift;
+/// let mut rng = XorShift::default();
+/// let to_labels = |mask: usize| (0..8).filter(|i| mask & 1 << i != 0).map(|i| (b'a' + i as u8) as char).collect::<String>();
+/// for _ in 0..300 {
+///     let segments = 3 + rng.index(4);
+///     let masks: Vec<usize> = (0..2 + rng.index(5)).map(|_| 1 + rng.index((1 << segments) - 1)).unique().collect();
+///     let symbols: Vec<(char, String)> = masks.iter().enumerate().map(|(i, &m)| ((b'0' + i as u8) as char, to_labels(m))).collect();
+///     let definition: Vec<(char, &str)> = symbols.iter().map(|(c, s)| (*c, s.as_str())).collect();
+///     let display = SegmentDisplay::new(segments, &definition).unwrap();
+///     // observe some scrambled symbols, or sometimes random patterns:
+///     let mut hidden: Vec<usize> = (0..segments).collect();
+///     for i in (1..segments).rev() {
+///         hidden.swap(i, rng.index(i + 1));
+///     }
+///     let scramble = |mask: usize| (0..segments).filter(|&i| mask & 1 << i != 0).map(|i| 1 << hidden[i]).sum::<usize>();
+///     let observed: Vec<usize> = (0..1 + rng.index(4))
+///         .map(|_| if rng.index(5) == 0 { 1 + rng.index((1 << segments) - 1) } else { scramble(masks[rng.index(masks.len())]) })
+///         .collect();
+///     let patterns: Vec<String> = observed.iter().map(|&m| to_labels(m)).collect();
+///     let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+///     // all decodings with any wiring:
+///     let decodings: Vec<Vec<char>> = (0..segments).permutations(segments).filter_map(|wiring| {
+///         observed.iter().map(|&pattern| {
+///             let mask = (0..segments).filter(|&w| pattern & 1 << w != 0).map(|w| 1 << wiring[w]).sum::<usize>();
+///             masks.iter().position(|&m| m == mask).map(|i| symbols[i].0)
+///         }).collect::<Option<Vec<char>>>()
+///     }).unique().collect();
+///     match (display.decode(&patterns), decodings.len()) {
+///         (Ok(decoded), 1) => assert_eq!(decoded, decodings[0]),
+///         (Err(DecodeError::Inconsistent), 0) => {}
+///         (Err(DecodeError::Ambiguous(..)), n) if n > 1 => {}
+///         (result, _) => panic!("{:?} for {:?} with {:?}", result, patterns, decodings),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SegmentDisplay {
+    segments: usize,
+    symbols: BTreeMap<u32, char>,
 }
 
-fn create_digit_mapping(patterns: &[SegmentPattern; 10]) -> DigitMapping {
-    let mut mapping = DigitMapping::new();
-    let mut digits: [Option<SegmentPattern>; 10] = Default::default();
-    // four of the digits use a unique number of segments:
-    for (digit, num_segments) in [(1, 2), (7, 3), (4, 4), (8, 7)] {
-        let mut dig_iter = patterns.iter().filter(|pat| pat.len() == num_segments);
-        extract_unique_digit(digit, &mut dig_iter, &mut mapping, &mut digits);
+/// convert segment or wire labels into a bitmask
+fn labels_to_mask(labels: &str, segments: usize) -> Result<u32, DecodeError> {
+    labels.chars().try_fold(0, |mask, label| {
+        let index = (label as usize).wrapping_sub('a' as usize);
+        if index < segments {
+            Ok(mask | 1 << index)
+        } else {
+            Err(DecodeError::UnknownLabel(label))
+        }
+    })
+}
+
+impl SegmentDisplay {
+    /// Create a display from the number of segments, and symbols with their lit segments
+    pub fn new(segments: usize, symbols: &[(char, &str)]) -> Result<Self, DecodeError> {
+        assert!(segments <= MAX_SEGMENTS, "too many segments: {}", segments);
+        let mut masks = BTreeMap::new();
+        for &(symbol, labels) in symbols {
+            if let Some(other) = masks.insert(labels_to_mask(labels, segments)?, symbol) {
+                return Err(DecodeError::DuplicateSegments(other, symbol));
+            }
+        }
+        Ok(SegmentDisplay {
+            segments,
+            symbols: masks,
+        })
     }
-    // digits 6, 9 and 0 have 6 segments:
-    // 6 is missing a segment that 1 has, even more: 1 is not a subset
-    let mut dig_iter = patterns
-        .iter()
-        .filter(|pat| pat.len() == 6 && !digits[1].as_ref().unwrap().is_subset(pat))
-        .collect::<Vec<_>>() // necessary to avoid double borrowing digits!
-        .into_iter();
-    extract_unique_digit(6, &mut dig_iter, &mut mapping, &mut digits);
-    // 9 has all segments that 4 has
-    let mut dig_iter = patterns
-        .iter()
-        .filter(|pat| pat.len() == 6 && digits[4].as_ref().unwrap().is_subset(pat))
-        .collect::<Vec<_>>() // necessary to avoid double borrowing digits!
-        .into_iter();
-    extract_unique_digit(9, &mut dig_iter, &mut mapping, &mut digits);
-    // 0 is the other one
-    let mut dig_iter = patterns
+
+    pub fn seven_segment() -> Self {
+        Self::new(7, &SEVEN_SEGMENT_DIGITS).unwrap()
+    }
+
+    /// Return the number of symbols with the given number of lit segments
+    pub fn symbols_with_len(&self, len: usize) -> usize {
+        self.symbols
+            .keys()
+            .filter(|mask| mask.count_ones() as usize == len)
+            .count()
+    }
+
+    /// Decode patterns of scrambled wires (all from one display with the same wiring) into symbols
+    pub fn decode(&self, patterns: &[&str]) -> Result<Vec<char>, DecodeError> {
+        let masks: Vec<u32> = patterns
+            .iter()
+            .map(|pattern| labels_to_mask(pattern, self.segments))
+            .collect::<Result<_, _>>()?;
+        let mut distinct = masks.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        // the patterns with the fewest candidate symbols first:
+        let mut candidates: Vec<(u32, Vec<u32>)> = distinct
+            .into_iter()
+            .map(|pattern| {
+                let symbols = self.symbols.keys();
+                let same_len = symbols.filter(|symbol| symbol.count_ones() == pattern.count_ones());
+                (pattern, same_len.copied().collect())
+            })
+            .collect();
+        candidates.sort_by_key(|(_, symbols)| symbols.len());
+        let mut search = Search {
+            symbols: &self.symbols,
+            candidates,
+            assigned: Vec::new(),
+            found: Vec::new(),
+        };
+        let classes = vec![0; self.segments];
+        search.run(&classes, &classes);
+        let symbol_of = |decoding: &[char], mask: u32| {
+            decoding[search
+                .candidates
+                .iter()
+                .position(|&(p, _)| p == mask)
+                .unwrap()]
+        };
+        match search.found.as_slice() {
+            [] => Err(DecodeError::Inconsistent),
+            [decoding] => Ok(masks
+                .iter()
+                .map(|&mask| symbol_of(decoding, mask))
+                .collect()),
+            [first, second, ..] => {
+                let (index, a, b) = masks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &mask)| (i, symbol_of(first, mask), symbol_of(second, mask)))
+                    .find(|(_, a, b)| a != b)
+                    .unwrap();
+                Err(DecodeError::Ambiguous(patterns[index].to_owned(), a, b))
+            }
+        }
+    }
+}
+
+/// Split the classes of wires by the pattern, and the classes of segments by the symbol,
+/// return None if a class does not have as many wires as segments any more
+fn refine(
+    wires: &[usize],
+    pattern: u32,
+    segments: &[usize],
+    symbol: u32,
+) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut ids: HashMap<(usize, bool), usize> = HashMap::new();
+    let mut counts: Vec<i32> = Vec::new();
+    let wires = wires
         .iter()
-        .filter(|&pat| {
-            pat.len() == 6
-                && digits[6].as_ref().unwrap() != pat
-                && digits[9].as_ref().unwrap() != pat
+        .enumerate()
+        .map(|(wire, &class)| {
+            let next = ids.len();
+            let id = *ids.entry((class, pattern & 1 << wire != 0)).or_insert(next);
+            if id == counts.len() {
+                counts.push(0);
+            }
+            counts[id] += 1;
+            id
         })
-        .collect::<Vec<_>>() // necessary to avoid double borrowing digits!
-        .into_iter();
-    extract_unique_digit(0, &mut dig_iter, &mut mapping, &mut digits);
-    // digits 2, 3 and 5 have 5 segments:
-    // 3 has 1 as a subset
-    let mut dig_iter = patterns
+        .collect();
+    let segments = segments
         .iter()
-        .filter(|pat| pat.len() == 5 && digits[1].as_ref().unwrap().is_subset(pat))
-        .collect::<Vec<_>>() // necessary to avoid double borrowing digits!
-        .into_iter();
-    extract_unique_digit(3, &mut dig_iter, &mut mapping, &mut digits);
-    // 2 intersects uniquely with 4 to leave 2 segments
-    let mut dig_iter = patterns
+        .enumerate()
+        .map(|(segment, &class)| {
+            let &id = ids.get(&(class, symbol & 1 << segment != 0))?;
+            counts[id] -= 1;
+            Some(id)
+        })
+        .collect::<Option<_>>()?;
+    counts
         .iter()
-        .filter(|pat| pat.len() == 5 && digits[4].as_ref().unwrap().intersection(pat).count() == 2)
-        .collect::<Vec<_>>() // necessary to avoid double borrowing digits!
-        .into_iter();
-    extract_unique_digit(2, &mut dig_iter, &mut mapping, &mut digits);
-    // 5 is the other one
-    let mut dig_iter = patterns
+        .all(|&count| count == 0)
+        .then_some((wires, segments))
+}
+
+/// Backtracking over the distinct patterns, assigning them symbols with as many lit segments
+struct Search<'a> {
+    symbols: &'a BTreeMap<u32, char>,
+    /// the distinct patterns with their candidate symbols
+    candidates: Vec<(u32, Vec<u32>)>,
+    /// the symbols of the first patterns
+    assigned: Vec<u32>,
+    /// the different decodings found, the search stops at two
+    found: Vec<Vec<char>>,
+}
+
+impl Search<'_> {
+    /// Assign the next pattern, the classes of wires and segments have equal sizes so far
+    fn run(&mut self, wires: &[usize], segments: &[usize]) {
+        let depth = self.assigned.len();
+        if depth == self.candidates.len() {
+            let decoding: Vec<char> = self.assigned.iter().map(|s| self.symbols[s]).collect();
+            if !self.found.contains(&decoding) {
+                self.found.push(decoding);
+            }
+            return;
+        }
+        let pattern = self.candidates[depth].0;
+        for i in 0..self.candidates[depth].1.len() {
+            let symbol = self.candidates[depth].1[i];
+            if self.found.len() == 2 {
+                return;
+            }
+            if self.assigned.contains(&symbol) {
+                continue;
+            }
+            if let Some((wires, segments)) = refine(wires, pattern, segments, symbol) {
+                self.assigned.push(symbol);
+                self.run(&wires, &segments);
+                self.assigned.pop();
+            }
+        }
+    }
+}
+
+/// an entry has the unique segment patterns observed, and the output values seen
+type DisplayEntry<'a> = (Vec<&'a str>, Vec<&'a str>);
+
+/// count the outputs that can only be one symbol, by their number of segments
+fn count_unique_len_outputs(entries: &[DisplayEntry], display: &SegmentDisplay) -> usize {
+    entries
         .iter()
-        .filter(|&pat| {
-            pat.len() == 5
-                && digits[2].as_ref().unwrap() != pat
-                && digits[3].as_ref().unwrap() != pat
+        .map(|entry| {
+            entry
+                .1
+                .iter()
+                .filter(|patt| display.symbols_with_len(patt.len()) == 1)
+                .count()
         })
-        .collect::<Vec<_>>() // necessary to avoid double borrowing digits!
-        .into_iter();
-    extract_unique_digit(5, &mut dig_iter, &mut mapping, &mut digits);
-    // done!
-    mapping
+        .sum()
 }
 
-fn find_output_value(outputs: &[SegmentPattern; 4], digit_mapping: &DigitMapping) -> usize {
-    1000 * digit_mapping.get(&outputs[0]).unwrap()
-        + 100 * digit_mapping.get(&outputs[1]).unwrap()
-        + 10 * digit_mapping.get(&outputs[2]).unwrap()
-        + digit_mapping.get(&outputs[3]).unwrap()
+/// decode all patterns of an entry and return the output value
+fn find_output_value(entry: &DisplayEntry, display: &SegmentDisplay) -> Result<usize, DecodeError> {
+    let (patterns, outputs) = entry;
+    let all_patterns: Vec<&str> = patterns.iter().chain(outputs).copied().collect();
+    let decoded = display.decode(&all_patterns)?;
+    let output: String = decoded[patterns.len()..].iter().collect();
+    output.parse().map_err(|_| DecodeError::NotANumber(output))
 }
 
-fn sum_outputs(entries: &[DisplayEntry]) -> usize {
+/// Return the sum of the outputs, and messages for the entries that could not be decoded
+fn sum_outputs(entries: &[DisplayEntry], display: &SegmentDisplay) -> (usize, Vec<String>) {
     let mut sum = 0;
-    for entry in entries {
-        let (patterns, outputs) = entry;
-        // create a mapping from set of segments to digit based on the patterns:
-        let digit_mapping = create_digit_mapping(patterns);
-        // apply map to output values, add to sum:
-        sum += find_output_value(outputs, &digit_mapping);
+    let mut errors = Vec::new();
+    for (line, entry) in entries.iter().enumerate() {
+        match find_output_value(entry, display) {
+            Ok(value) => sum += value,
+            Err(error) => errors.push(format!("entry {}: {}", line + 1, error)),
+        }
     }
-    sum
+    (sum, errors)
 }
 
-fn str_to_display_entry(a_str: &str) -> DisplayEntry {
-    let (ten_patterns, four_outputs) = a_str.split_once(" | ").unwrap();
-    let make_display_entries = |patterns: &str| {
-        patterns
-            .split_ascii_whitespace()
-            .map(|pt| pt.chars().collect())
-            .collect::<Vec<_>>()
-    };
+fn str_to_display_entry(a_str: &str) -> DisplayEntry<'_> {
+    let (patterns, outputs) = a_str.split_once(" | ").unwrap();
     (
-        make_display_entries(ten_patterns).try_into().unwrap(),
-        make_display_entries(four_outputs).try_into().unwrap(),
+        patterns.split_ascii_whitespace().collect(),
+        outputs.split_ascii_whitespace().collect(),
     )
 }
 
+/// ```
+/// use advent_of_code_202x::generated::year2021day08::process_input;
+/// let output = process_input("ab abdef abd | ab abd ab ba\nab abd | bd ab");
+/// assert!(output.ends_with("sum of outputs: 1711\nentry 2: no wiring matches all patterns"));
+/// ```
 pub fn process_input(input: &str) -> String {
     let input = input.trim().split('\n');
     let displays: Vec<DisplayEntry> = input.map(str_to_display_entry).collect();
     //println!("displays:\n{:?}", displays);
-    let num_of_unique_outputs = count_unique_len_outputs(&displays);
-    let sum_of_outputs = sum_outputs(&displays);
-    format!(
+    let display = SegmentDisplay::seven_segment();
+    let num_of_unique_outputs = count_unique_len_outputs(&displays, &display);
+    let (sum_of_outputs, errors) = sum_outputs(&displays, &display);
+    let mut result = format!(
         "num of unique len outputs: {}\nsum of outputs: {}",
         num_of_unique_outputs, sum_of_outputs
-    )
+    );
+    for error in errors {
+        result += &format!("\n{}", error);
+    }
+    result
 }
 
 pub fn run_example() -> String {
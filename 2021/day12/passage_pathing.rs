@@ -1,5 +1,6 @@
 //! https://adventofcode.com/2021/day/12
 //! Enumerate all paths in a graph. Probably breadth-first / dikstra / uniform-cost search
+//! turns out only the number of paths is needed, so count them with memoized depth-first search
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2021day12::run;
@@ -56,95 +57,41 @@ pj-fs
 start-RW
 "; // 226 paths
 
-use std::collections::HashMap;
+use crate::graph::{Graph, NodeId, NodeSet};
 
-/// need to construct a network mapping:
-type Connections = HashMap<&'static str, Vec<&'static str>>;
+/// path state for the visit constraints: small caves visited so far,
+/// and whether the one allowed repeat visit of a small cave has been used up
+type PathState = (NodeSet, bool);
 
-/// Add a connection to the connections dictionary
-/// filters out connections to 'start' and from 'end'
-fn add_connection(connections: &mut Connections, from: &'static str, to: &'static str) {
-    if from == "end" || to == "start" {
-        return;
-    }
-    connections.entry(from).or_insert_with(Vec::new).push(to);
-}
-
-/// Return a dict from node name to list of node names, both directions are in the dict for every edge
-fn parse_connections(puzzle_input: &'static str) -> Connections {
-    let mut result = HashMap::new();
-    for line in puzzle_input.split('\n') {
-        let (origin, destination) = line.split_once('-').unwrap();
-        add_connection(&mut result, origin, destination);
-        add_connection(&mut result, destination, origin);
-    }
-    result
-}
-
-/// depth-first search with special cases based on the case of a node label. uppercase can be visited unlimited times
-/// lowercase can only be visited once, unless `small_visited_twice` is false, then one can be visited twice
-/// TODO: optimization after testing is in place: don't remember paths, just return a count!
-/// possible further optimization: graph could be based on ints or even bit-based adjacency
-fn dfs(
-    connections: &Connections,
-    node: &'static str,
-    goal: &'static str,
-    current_path: &mut Vec<&'static str>,
-    paths: &mut Vec<Vec<&'static str>>,
-    small_visited_twice: bool,
-) {
-    current_path.push(node);
-    //println!("considering {:?}", current_path);
-    if node == goal {
-        paths.push(current_path.clone())
-    } else {
-        for neigh in &connections[node] {
-            if neigh.chars().next().unwrap().is_ascii_lowercase() && current_path.contains(neigh) {
-                // small cave that we've already visited, check if still possible:
-                if !small_visited_twice {
-                    dfs(connections, neigh, goal, current_path, paths, true)
-                }
-            } else {
-                dfs(
-                    connections,
-                    neigh,
-                    goal,
-                    current_path,
-                    paths,
-                    small_visited_twice,
-                )
-            }
-        }
-    }
-    current_path.pop();
-}
-
-/// Count the numer of paths from start to end, only allowing repeat visits to uppercase nodes in the connection graph
+/// Count the number of paths from start to end, only allowing repeat visits to uppercase nodes in the graph,
+/// unless `small_visited_twice` is false, then one lowercase node can be visited twice (but not start)
 /// (assumes that there are no direct connections between uppercase nodes - that would result in infinite loops)
-/// modified depth-first (or uniform-cost ?) search that counts all possible paths
-fn count_paths(
-    connections: &Connections,
-    start: &'static str,
-    end: &'static str,
-    small_visited_twice: bool,
-) -> usize {
-    let mut paths = Vec::new();
-    let mut path = Vec::new();
-    dfs(
-        connections,
-        start,
-        end,
-        &mut path,
-        &mut paths,
-        small_visited_twice,
-    );
-    paths.len()
+/// paths are never materialized, the graph counts them with memoization on the path state
+fn count_paths(graph: &Graph, start: NodeId, end: NodeId, small_visited_twice: bool) -> u64 {
+    let small_caves = graph.nodes_where(|name| name.chars().next().unwrap().is_ascii_lowercase());
+    let initial: PathState = (NodeSet::single(start), small_visited_twice);
+    graph.count_paths(start, end, initial, |neigh, &(visited, twice)| {
+        if neigh == start {
+            None
+        } else if !small_caves.contains(neigh) {
+            Some((visited, twice))
+        } else if !visited.contains(neigh) {
+            Some((visited.with(neigh), twice))
+        } else if !twice {
+            // small cave that we've already visited, still possible once:
+            Some((visited, true))
+        } else {
+            None
+        }
+    })
 }
 
-pub fn process_input(input: &'static str) -> String {
-    let connections = parse_connections(input.trim());
-    let num_paths = count_paths(&connections, "start", "end", true);
-    let num_paths_small_twice = count_paths(&connections, "start", "end", false);
+/// works on any input, e.g. one loaded from a file at runtime
+pub fn process_input(input: &str) -> String {
+    let graph = Graph::from_undirected_edges(input, '-');
+    let (start, end) = (graph.id("start").unwrap(), graph.id("end").unwrap());
+    let num_paths = count_paths(&graph, start, end, true);
+    let num_paths_small_twice = count_paths(&graph, start, end, false);
     format!(
         "num paths from start to end: {}\nnum paths from start to end: {}\n",
         num_paths, num_paths_small_twice
@@ -94,7 +94,7 @@ fn plot(coords: HashSet<Coord>) -> String {
         }
         let line = lines.get_mut(coord[1]).unwrap();
         if coord[0] >= line.len() {
-            line.extend(std::iter::repeat(".").take(coord[0] + 1 - line.len()));
+            line.extend(std::iter::repeat_n(".", coord[0] + 1 - line.len()));
         }
         line.replace_range(coord[0]..coord[0] + 1, "#");
     }
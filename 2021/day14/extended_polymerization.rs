@@ -37,99 +37,117 @@ CC -> N
 CN -> C
 "; // most common (B, 1749) minus least common element (H, 161) produces 1749 - 161 = 1588
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-/// need to deal with pairs of chars, and tracking counts of pairs and individual chars:
-/// assume that chars are always upppercase characters, so we can use fixed-size arrays instead of hashmaps
-const MAX_CHAR_INDEX: usize = 26;
-const A_INDEX: usize = 'A' as usize;
+use crate::linear_recurrence::{Arithmetic, CountError, LinearRecurrence};
+use crate::Params;
 
-fn char_to_index(c: char) -> usize {
-    c as usize - A_INDEX
+/// the polymer is only tracked as counts of pairs (each insertion turns a pair into two pairs),
+/// and counts of elements (an insertion adds one element)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum State {
+    Pair(char, char),
+    Element(char),
 }
 
-#[derive(Debug, Default, Clone)]
+/// rules for extending: a pair gets a new element inserted in the middle
+type Rules = HashMap<(char, char), char>;
+
+#[derive(Debug, Clone)]
 struct Polymer {
-    pair_counts: [[u64; MAX_CHAR_INDEX]; MAX_CHAR_INDEX],
-    char_counts: [u64; MAX_CHAR_INDEX],
+    template: Vec<char>,
+    rules: LinearRecurrence<State>,
 }
 
-/// rules for extending: a pair is replaced by two new pairs and increase the count of one char's index
-type Pair = (usize, usize);
-type Rules = HashMap<Pair, (Pair, Pair, usize)>;
-
 impl Polymer {
-    /// Create a new polymer from a string specification
-    fn new(polymer_str: &str) -> Self {
-        let mut polymer = Self::default();
-        for c in polymer_str.chars() {
-            polymer.char_counts[char_to_index(c)] += 1;
+    /// Create a new polymer from a template and the pair insertion rules,
+    /// pairs without a rule and elements stay as they are
+    fn new(template: &str, rules: &Rules) -> Self {
+        let template: Vec<char> = template.chars().collect();
+        let mut recurrence = LinearRecurrence::new();
+        let mut pairs: HashSet<(char, char)> = template.windows(2).map(|w| (w[0], w[1])).collect();
+        let mut elements: HashSet<char> = template.iter().copied().collect();
+        for (&(left, right), &new_char) in rules {
+            pairs.extend([(left, right), (left, new_char), (new_char, right)]);
+            elements.extend([left, right, new_char]);
+        }
+        for (left, right) in pairs {
+            let pair = State::Pair(left, right);
+            match rules.get(&(left, right)) {
+                Some(&new_char) => {
+                    recurrence.add_transition(pair, State::Pair(left, new_char), 1);
+                    recurrence.add_transition(pair, State::Pair(new_char, right), 1);
+                    recurrence.add_transition(pair, State::Element(new_char), 1);
+                }
+                None => recurrence.add_transition(pair, pair, 1),
+            }
+        }
+        for element in elements {
+            recurrence.add_transition(State::Element(element), State::Element(element), 1);
         }
-        for pair in polymer_str.chars().collect::<Vec<char>>()[..].windows(2) {
-            let [left, right]: [char; 2] = pair.try_into().unwrap();
-            polymer.pair_counts[char_to_index(left)][char_to_index(right)] += 1;
+        Self {
+            template,
+            rules: recurrence,
         }
-        polymer
     }
 
-    /// apply the rules once, returning a new instance of Polymer
-    fn apply_rules(self, rules: &Rules) -> Self {
-        let mut new_polymer = self.clone();
-        for (pair, (left, right, new_char)) in rules {
-            let pair_count = self.pair_counts[pair.0][pair.1];
-            if pair_count > 0 {
-                // fill new_polymer with updated values and increase the char count
-                new_polymer.pair_counts[pair.0][pair.1] -= pair_count;
-                new_polymer.pair_counts[left.0][left.1] += pair_count;
-                new_polymer.pair_counts[right.0][right.1] += pair_count;
-                new_polymer.char_counts[*new_char] += pair_count;
-            }
-        }
-        new_polymer
+    /// apply the rules `steps` times, and return the element counts
+    fn apply_rules(&self, steps: u64) -> Result<HashMap<char, u128>, CountError<State>> {
+        let pairs = self
+            .template
+            .windows(2)
+            .map(|pair| (State::Pair(pair[0], pair[1]), 1));
+        let elements = self.template.iter().map(|&c| (State::Element(c), 1));
+        let counts = self
+            .rules
+            .count_after(pairs.chain(elements), steps, Arithmetic::Checked)?;
+        Ok(counts
+            .into_iter()
+            .filter_map(|(state, count)| match state {
+                State::Element(c) => Some((c, count)),
+                State::Pair(..) => None,
+            })
+            .collect())
     }
 
-    /// Return the max-count minus the min-count (that is non-zero)
-    fn count_diff_most_least(&self) -> u64 {
-        let max = self.char_counts.iter().max().unwrap();
-        let min = self
-            .char_counts
-            .iter()
+    /// Return the max-count minus the min-count (that is non-zero) after `steps` steps
+    fn count_diff_most_least(&self, steps: u64) -> Result<u128, CountError<State>> {
+        let element_counts = self.apply_rules(steps)?;
+        let max = element_counts.values().max().unwrap();
+        let min = element_counts
+            .values()
             .filter(|ele| **ele > 0)
             .min()
             .unwrap();
-        max - min
+        Ok(max - min)
     }
 }
 
-/// Return a Rules hashmap, with chars already transformed to indices
-fn parse_rules(puzzle_input: &'static str) -> Rules {
+fn parse_rules(puzzle_input: &str) -> Rules {
     let mut result = Rules::new();
     for (a, b, c) in puzzle_input.split('\n').map(|line| {
         let (left, right) = line.split_once(" -> ").unwrap();
         let (mut left, mut right) = (left.chars(), right.chars());
         (
-            char_to_index(left.next().unwrap()),
-            char_to_index(left.next().unwrap()),
-            char_to_index(right.next().unwrap()),
+            left.next().unwrap(),
+            left.next().unwrap(),
+            right.next().unwrap(),
         )
     }) {
-        result.entry((a, b)).or_insert(((a, c), (c, b), c));
+        result.entry((a, b)).or_insert(c);
     }
     result
 }
 
-pub fn process_input(input: &'static str) -> String {
+fn parse_polymer(input: &str) -> Polymer {
     let (polymer_str, rules_str) = input.trim().split_once("\n\n").unwrap();
-    let mut polymer: Polymer = Polymer::new(polymer_str);
-    let rules = parse_rules(rules_str);
-    for _ in 0..10 {
-        polymer = polymer.apply_rules(&rules);
-    }
-    let count_diff_10 = polymer.count_diff_most_least();
-    for _ in 0..30 {
-        polymer = polymer.apply_rules(&rules);
-    }
-    let count_diff_40 = polymer.count_diff_most_least();
+    Polymer::new(polymer_str, &parse_rules(rules_str))
+}
+
+pub fn process_input(input: &str) -> String {
+    let polymer = parse_polymer(input);
+    let count_diff_10 = polymer.count_diff_most_least(10).unwrap();
+    let count_diff_40 = polymer.count_diff_most_least(40).unwrap();
     format!(
         "Difference of most common and least common element after 10 rounds: {}\nafter 40 rounds: {:?}\n",
         count_diff_10, count_diff_40
@@ -143,3 +161,34 @@ pub fn run_example() -> String {
 pub fn run() -> String {
     process_input(INPUT)
 }
+
+/// Apply the rules `steps=N` times, e.g. `cargo run 14 2021 steps=100`
+/// ```
+/// use advent_of_code_202x::generated::year2021day14::run_with_params;
+/// let params = [("steps".to_string(), "100".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "Difference of most common and least common element after 100 rounds: 2535296262066596202993060773164");
+/// let params = [("steps".to_string(), "200".to_string())].into_iter().collect();
+/// assert!(run_with_params(&params, true).contains("does not fit into u128"));
+/// let params = [("steps".to_string(), "many".to_string())].into_iter().collect();
+/// assert_eq!(run_with_params(&params, true), "invalid steps=many, expected a non-negative integer");
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let polymer = parse_polymer(if use_example { EXAMPLE_INPUT } else { INPUT });
+    let steps = match params
+        .get("steps")
+        .map(|steps| (steps, steps.parse::<u64>()))
+    {
+        Some((_, Ok(steps))) => steps,
+        Some((steps, Err(_))) => {
+            return format!("invalid steps={}, expected a non-negative integer", steps)
+        }
+        None => 40,
+    };
+    match polymer.count_diff_most_least(steps) {
+        Ok(diff) => format!(
+            "Difference of most common and least common element after {} rounds: {}",
+            steps, diff
+        ),
+        Err(error) => format!("Difference after {} rounds: {}", steps, error),
+    }
+}
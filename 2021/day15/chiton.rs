@@ -67,8 +67,8 @@ impl Grid {
                     .collect()
             })
             .collect();
-        let len_x = vals.len() as usize;
-        let len_y = vals.get(0).unwrap().len() as usize;
+        let len_x = vals.len();
+        let len_y = vals.first().unwrap().len();
         Self {
             vals,
             len_x,
@@ -2,6 +2,7 @@
 //! Decode a binary packet format, representing numeric expressions, given as hex
 //! exercise in bit-level parsing: let's try using the nom package!
 //! (binread looks interesting too, but does not seem to be made for bit-level)
+//! packets can also be encoded again (with either length type) and pretty-printed as s-expressions
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2021day16::run;
@@ -36,22 +37,73 @@ F600BC2D8F
 9C0141080250320F1802104A08
 "; // see AOC page for details
 
+use std::fmt;
+
 /// we have to deal with an expression tree - a recursive struct, expressions containing packets:
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expr {
     Literal(u64),
     Operator(Vec<Packet>),
 }
 
 /// and packets containing expressions:
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Packet {
     version: u8,
     type_id: u8, // 4 for literals, otherwise it's an operator
     expr: Expr,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    InvalidHex,
+    /// the bits do not form a valid packet
+    Malformed,
+    /// whole bytes left over after the outermost packet
+    TrailingBytes,
+    UnknownTypeId(u8),
+    /// wrong number of operands for the operator type
+    OperandCount(u8, usize),
+    Overflow,
+    /// a version, type id or operand length does not fit into its bits when encoding
+    FieldTooLarge,
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PacketError::InvalidHex => write!(f, "invalid hexadecimal string"),
+            PacketError::Malformed => write!(f, "malformed packet"),
+            PacketError::TrailingBytes => write!(f, "trailing bytes after packet"),
+            PacketError::UnknownTypeId(type_id) => write!(f, "unknown packet type id {}", type_id),
+            PacketError::OperandCount(type_id, count) => {
+                write!(f, "{} operands for packet type id {}", count, type_id)
+            }
+            PacketError::Overflow => write!(f, "arithmetic overflow"),
+            PacketError::FieldTooLarge => write!(f, "field too large to encode"),
+        }
+    }
+}
+
+impl std::error::Error for PacketError {}
+
 impl Packet {
+    pub fn literal(version: u8, value: u64) -> Self {
+        Packet {
+            version,
+            type_id: 4,
+            expr: Expr::Literal(value),
+        }
+    }
+
+    pub fn operator(version: u8, type_id: u8, operands: Vec<Packet>) -> Self {
+        Packet {
+            version,
+            type_id,
+            expr: Expr::Operator(operands),
+        }
+    }
+
     /// recursively sum all version numbers
     fn version_sum(&self) -> u32 {
         match &self.expr {
@@ -63,26 +115,66 @@ impl Packet {
     }
 
     /// recursively evaluate the tree of expressions
-    fn evaluate(&self) -> u64 {
+    pub fn evaluate(&self) -> Result<u64, PacketError> {
         match &self.expr {
-            Expr::Literal(value) => *value,
+            Expr::Literal(value) => Ok(*value),
             Expr::Operator(pack_vec) => {
-                let mut evaluated_iter = pack_vec.iter().map(|ele| ele.evaluate());
+                let values = pack_vec
+                    .iter()
+                    .map(|ele| ele.evaluate())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let operand_count_error = PacketError::OperandCount(self.type_id, values.len());
                 match self.type_id {
-                    0 => evaluated_iter.sum(),
-                    1 => evaluated_iter.product(),
-                    2 => evaluated_iter.min().unwrap(),
-                    3 => evaluated_iter.max().unwrap(),
-                    5 => (evaluated_iter.next().unwrap() > evaluated_iter.next().unwrap()) as u64,
-                    6 => (evaluated_iter.next().unwrap() < evaluated_iter.next().unwrap()) as u64,
-                    7 => (evaluated_iter.next().unwrap() == evaluated_iter.next().unwrap()) as u64,
-                    _ => panic!("Unknown packet type id {}", self.type_id),
+                    0 => values
+                        .iter()
+                        .try_fold(0u64, |acc, &val| acc.checked_add(val))
+                        .ok_or(PacketError::Overflow),
+                    1 => values
+                        .iter()
+                        .try_fold(1u64, |acc, &val| acc.checked_mul(val))
+                        .ok_or(PacketError::Overflow),
+                    2 => values.into_iter().min().ok_or(operand_count_error),
+                    3 => values.into_iter().max().ok_or(operand_count_error),
+                    5..=7 => match values[..] {
+                        [first, second] => Ok(match self.type_id {
+                            5 => first > second,
+                            6 => first < second,
+                            _ => first == second,
+                        } as u64),
+                        _ => Err(operand_count_error),
+                    },
+                    _ => Err(PacketError::UnknownTypeId(self.type_id)),
                 }
             }
         }
     }
 }
 
+/// Pretty-print as s-expression, e.g. `(sum 1 (max 2 3))` (versions are not shown)
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.expr {
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Operator(pack_vec) => {
+                match self.type_id {
+                    0 => write!(f, "(sum")?,
+                    1 => write!(f, "(product")?,
+                    2 => write!(f, "(min")?,
+                    3 => write!(f, "(max")?,
+                    5 => write!(f, "(gt")?,
+                    6 => write!(f, "(lt")?,
+                    7 => write!(f, "(eq")?,
+                    type_id => write!(f, "(op{}", type_id)?,
+                }
+                for packet in pack_vec {
+                    write!(f, " {}", packet)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
 /// All parsing code in a separate module:
 pub mod parse {
     use hex::FromHex;
@@ -94,31 +186,31 @@ pub mod parse {
     use nom::{Err, IResult, InputLength};
     use std::cmp::Ordering;
 
-    use super::{Expr, Packet};
+    use super::{Expr, Packet, PacketError};
 
     /// nom's idiom for bits: a byte slice with an offset (0-7) into the first byte
     type Bits<'a> = (&'a [u8], usize);
 
     /// Return a vec of (n) bytes from a hexadecimal string (2n long)
-    pub fn hex2bytes(input: &'static str) -> Vec<u8> {
-        Vec::from_hex(input).expect("Invalid Hexadecimal String")
+    pub fn hex2bytes(input: &str) -> Result<Vec<u8>, PacketError> {
+        Vec::from_hex(input).map_err(|_| PacketError::InvalidHex)
     }
 
-    /// Parse a full packet given as bytes (also asserts there are no extra bytes left and panics on fail)
-    pub fn bytes2packet(input: &[u8]) -> Packet {
+    /// Parse a full packet given as bytes (also checks there are no extra bytes left)
+    pub fn bytes2packet(input: &[u8]) -> Result<Packet, PacketError> {
         let result = bits::<_, _, Error<Bits>, Error<&[u8]>, _>(packet)(input);
         match result {
-            Ok((unparsed, packet)) => {
-                assert!(unparsed.is_empty());
-                packet
-            }
-            Err(err) => {
-                println!("Error {:?}", err);
-                panic!("Parsing packet failed!")
-            }
+            Ok((unparsed, _)) if !unparsed.is_empty() => Err(PacketError::TrailingBytes),
+            Ok((_, packet)) => Ok(packet),
+            Err(_) => Err(PacketError::Malformed),
         }
     }
 
+    /// Parse a full packet given as hexadecimal string
+    pub fn hex2packet(input: &str) -> Result<Packet, PacketError> {
+        bytes2packet(&hex2bytes(input)?)
+    }
+
     /// Parse bit stream into a packet, i.e. a version number and either a literal or an operator
     fn packet(input: Bits) -> IResult<Bits, Packet> {
         let result = tuple((take(3usize), alt((literal, operator))))(input);
@@ -139,17 +231,19 @@ pub mod parse {
     }
 
     /// Parse bit stream that holds the literal value, groups of 5 bits, the first bit being 0 on the last one,
-    /// and calculate the resulting number as u64 (there is no defined max-length, so this could overflow!)
+    /// and calculate the resulting number as u64 (there is no defined max-length, so fail on overflow)
     fn literal_value(input: Bits) -> IResult<Bits, Expr> {
-        let (unparsed, (parts_vec, final_part)) =
+        let (unparsed, (mut parts_vec, final_part)) =
             many_till(literal_value_part(true), literal_value_part(false))(input)?;
-        let parts_val = parts_vec
-            .iter()
-            .fold(0u64, |acc, ele| acc * 0b10000 + *ele as u64);
-        Ok((
-            unparsed,
-            Expr::Literal(parts_val * 0b10000 + final_part as u64),
-        ))
+        parts_vec.push(final_part);
+        let value = parts_vec.iter().try_fold(0u64, |acc, ele| {
+            acc.checked_mul(0b10000)
+                .map(|shifted| shifted + *ele as u64)
+        });
+        match value {
+            Some(value) => Ok((unparsed, Expr::Literal(value))),
+            None => Err(Err::Failure(Error::new(input, ErrorKind::TooLarge))),
+        }
     }
 
     /// Create a parser for a part of the literal representation, 5 bits with the first being 1 or 0 (the `tag_value`)
@@ -199,14 +293,144 @@ pub mod parse {
     }
 }
 
-pub fn process_input(input: &'static str) -> String {
-    let byte_slice = parse::hex2bytes(input.trim());
-    let test_result = parse::bytes2packet(&byte_slice[..]);
-    format!(
-        "Version sum of packets: {}\nEvaluates to: {}\n",
-        test_result.version_sum(),
-        test_result.evaluate()
-    )
+/// Encoding a packet tree back into bits, the reverse of the parse module
+/// ```
+/// use advent_of_code_202x::generated::year2021day16::encode::{packet2hex, LengthType};
+/// use advent_of_code_202x::generated::year2021day16::parse::hex2packet;
+/// use advent_of_code_202x::generated::year2021day16::{Packet, PacketError};
+/// use advent_of_code_202x::testing::XorShift;
+/// let packet = hex2packet("9C0141080250320F1802104A08").unwrap();
+/// assert_eq!(packet.to_string(), "(eq (sum 1 3) (product 2 2))");
+/// assert_eq!(packet.evaluate(), Ok(1));
+/// assert_eq!(packet2hex(&Packet::literal(6, 2021), LengthType::TotalBits).unwrap(), "D2FE28");
+/// assert_eq!(hex2packet("C200B40A82").unwrap().to_string(), "(sum 1 2)");
+/// // evaluation reports errors instead of panicking:
+/// let compare_one = Packet::operator(0, 5, vec![Packet::literal(0, 1)]);
+/// assert_eq!(compare_one.evaluate(), Err(PacketError::OperandCount(5, 1)));
+/// assert_eq!(Packet::operator(0, 4, vec![]).evaluate(), Err(PacketError::UnknownTypeId(4)));
+/// assert_eq!(hex2packet("D2FE2"), Err(PacketError::InvalidHex));
+///
+/// // property test: decode(encode(p)) == p for random packet trees, with both length types
+/// let mut rng = XorShift::new(0x2021_1216);
+/// fn random_packet(rng: &mut XorShift, depth: u32) -> Packet {
+///     let version = rng.below(8) as u8;
+///     if depth == 0 || rng.below(3) == 0 {
+///         Packet::literal(version, rng.below(u64::MAX) >> rng.below(64))
+///     } else {
+///         let type_id = [0, 1, 2, 3, 5, 6, 7][rng.index(7)];
+///         let num_operands = if type_id >= 5 { 2 } else { 1 + rng.below(4) };
+///         let operands = (0..num_operands).map(|_| random_packet(rng, depth - 1)).collect();
+///         Packet::operator(version, type_id, operands)
+///     }
+/// }
+/// for _ in 0..200 {
+///     let packet = random_packet(&mut rng, 4);
+///     for length_type in [LengthType::TotalBits, LengthType::PacketCount] {
+///         let hex = packet2hex(&packet, length_type).unwrap();
+///         assert_eq!(hex2packet(&hex).unwrap(), packet);
+///     }
+/// }
+/// ```
+pub mod encode {
+    use super::{Expr, Packet, PacketError};
+
+    /// How operator packets encode the length of their operands
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum LengthType {
+        /// length type 0: 15 bits for the total number of bits of the operands
+        TotalBits,
+        /// length type 1: 11 bits for the number of operand packets
+        PacketCount,
+    }
+
+    /// Bits in the order they are written, most significant first
+    #[derive(Debug, Default)]
+    struct BitWriter {
+        bits: Vec<bool>,
+    }
+
+    impl BitWriter {
+        /// Write the lowest `width` bits of value, fail if it does not fit
+        fn push(&mut self, value: u64, width: usize) -> Result<(), PacketError> {
+            if width < 64 && value >> width != 0 {
+                return Err(PacketError::FieldTooLarge);
+            }
+            self.bits
+                .extend((0..width).rev().map(|bit| (value >> bit) & 1 == 1));
+            Ok(())
+        }
+
+        /// Return the bits as bytes in hex, zero-padded at the end to a full byte
+        fn to_hex(&self) -> String {
+            let bytes: Vec<u8> = self
+                .bits
+                .chunks(8)
+                .map(|chunk| {
+                    (0..8).fold(0u8, |acc, bit| {
+                        (acc << 1) | *chunk.get(bit).unwrap_or(&false) as u8
+                    })
+                })
+                .collect();
+            hex::encode_upper(bytes)
+        }
+    }
+
+    /// Encode a packet tree as hex, with the same length type for all operators
+    pub fn packet2hex(packet: &Packet, length_type: LengthType) -> Result<String, PacketError> {
+        let mut writer = BitWriter::default();
+        write_packet(&mut writer, packet, length_type)?;
+        Ok(writer.to_hex())
+    }
+
+    fn write_packet(
+        writer: &mut BitWriter,
+        packet: &Packet,
+        length_type: LengthType,
+    ) -> Result<(), PacketError> {
+        writer.push(packet.version as u64, 3)?;
+        writer.push(packet.type_id as u64, 3)?;
+        match (&packet.expr, packet.type_id) {
+            (Expr::Literal(value), 4) => {
+                // groups of 4 bits, each preceded by a bit that tells whether more groups follow:
+                let num_groups = (64 - value.leading_zeros() as usize).div_ceil(4);
+                for group in (0..num_groups.max(1)).rev() {
+                    writer.push((group > 0) as u64, 1)?;
+                    writer.push((value >> (4 * group)) & 0b1111, 4)?;
+                }
+            }
+            (Expr::Operator(pack_vec), type_id) if type_id != 4 => match length_type {
+                LengthType::TotalBits => {
+                    let mut operands = BitWriter::default();
+                    for operand in pack_vec {
+                        write_packet(&mut operands, operand, length_type)?;
+                    }
+                    writer.push(0, 1)?;
+                    writer.push(operands.bits.len() as u64, 15)?;
+                    writer.bits.extend(operands.bits);
+                }
+                LengthType::PacketCount => {
+                    writer.push(1, 1)?;
+                    writer.push(pack_vec.len() as u64, 11)?;
+                    for operand in pack_vec {
+                        write_packet(writer, operand, length_type)?;
+                    }
+                }
+            },
+            (_, type_id) => return Err(PacketError::UnknownTypeId(type_id)),
+        }
+        Ok(())
+    }
+}
+
+pub fn process_input(input: &str) -> String {
+    let result = parse::hex2packet(input.trim()).and_then(|packet| {
+        Ok(format!(
+            "Version sum of packets: {}\nEvaluates to: {}\n",
+            packet.version_sum(),
+            packet.evaluate()?
+        ))
+    });
+    result.unwrap_or_else(|err| format!("Invalid packet: {}\n", err))
 }
 
 pub fn run_example() -> String {
@@ -1,16 +1,14 @@
 //! https://adventofcode.com/2021/day/17
-//! Calculate a trajectory to reach a target area, there is probably a closed-form solution
-//! but we could of course also try reasonable values.
-//! For part 1, x needs to be the minimum with a triangular number that reaches the target x
+//! Calculate a trajectory to reach a target area.
+//! Solved exactly with integers, for a target anywhere around the launcher:
+//! for each x velocity find the range of steps in which x is in the target,
+//! and for each such step the range of y velocities that are in the target after that many steps.
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2021day17::run;
 //! assert!(run().contains("Max height: 19503\nvalid initial velocities: 5200"));
 //! ```
 
-use itertools::Itertools;
-use std::{cmp::Ordering, collections::HashSet, ops::RangeInclusive};
-
 const INPUT: &str = include_str!("input");
 
 /// ```
@@ -21,210 +19,275 @@ const EXAMPLE_INPUT: &str = "
 target area: x=20..30, y=-10..-5
 "; // highest point reached with 6,9: y of 45
 
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::Params;
+
 /// Return the triangular number for a number, i.e. the sum of 1 to n, or equivalently n * (n+1) / 2
-fn triangular_number(number: i32) -> i32 {
+fn triangular_number(number: i64) -> i64 {
     number * (number + 1) / 2
 }
 
-/// Return the triangular root of a number, e.g. n for triangular number T(n) or in between
-fn triangular_root(number: i32) -> f32 {
-    ((number as f32 * 8f32 + 1f32).sqrt() - 1f32) / 2f32
+/// The target area, bounds are inclusive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub x: RangeInclusive<i64>,
+    pub y: RangeInclusive<i64>,
 }
 
-/// Return the maximum initial y velocity that avoids missing the target area
-fn find_max_initial_y(target: [[i32; 2]; 2]) -> i32 {
-    // find y: y will increase and then decrease back to 0,
-    // ideally we want the next step to land right in the target, so exactly the lower bound of the range,
-    // (the one further away from 0), we therefore need to start with lowerbound - 1:
-    target[1][0].abs() - 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTargetError(String);
+
+impl fmt::Display for ParseTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid target area {:?}", self.0)
+    }
 }
 
-/// Return the maximum y value of the projectile so it hits the target area
-fn find_max_height(target: [[i32; 2]; 2]) -> i32 {
-    // maximum height is the triangular number of maximum y
-    triangular_number(find_max_initial_y(target))
-}
-
-/// Return f(v,n) ... position after n steps for initial velocities v
-/// This treats x and y the same, i.e. both subtract 1 on each step even beyond 0
-/// Note: this is almost only used for debugging, not needed for actual solution!
-#[allow(dead_code)]
-fn pos_after_n_steps(velocity: (u32, i32), n: u32) -> [i32; 2] {
-    // e.g. f(6,0) = 0 = T(6) - T(6-0), f(6,1) = 6 = T(6) - T(6-1), f(6,2) = 11 = T(6) - T(6-2)
-    // expanded:
-    // f(x,n) = x * (x + 1) / 2 - (x-n) * (x-n+1) / 2
-    //        = 1/2 * (x * (x + 1) - (x-n) * (x-n+1))
-    //        = 1/2 * (x2 + x - x2 + nx + nx - n2 - x + n)
-    //        = 1/2 * (2nx + n - n2)
-    //        = n/2 * (2x + 1 - n)
-    // BUT: for x values, n cannot go above x as it would decrease the result again
-    let val_calc = |val, n| (2 * val + 1 - n as i32) * n as i32 / 2;
-    [
-        val_calc(velocity.0 as i32, std::cmp::min(n, velocity.0)),
-        val_calc(velocity.1, n),
-    ]
-}
-
-/// Compare a position with a target area and return a comparison result
-fn cmp_pos_target(pos: [i32; 2], target: [[i32; 2]; 2]) -> Ordering {
-    if pos[0] > target[0][1] || pos[1] < target[1][0] {
-        Ordering::Greater // pos overshot!
-    } else if pos[0] < target[0][0] || pos[1] > target[1][1] {
-        Ordering::Less // pos could still reach target
-    } else {
-        Ordering::Equal // pos is inside target area!
+impl std::error::Error for ParseTargetError {}
+
+/// Parse a range like -10..5 (or 5..-10)
+fn parse_range(range: &str) -> Option<RangeInclusive<i64>> {
+    let (a, b) = range.trim().split_once("..")?;
+    let (a, b): (i64, i64) = (a.parse().ok()?, b.parse().ok()?);
+    Some(a.min(b)..=a.max(b))
+}
+
+impl std::str::FromStr for Target {
+    type Err = ParseTargetError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = || ParseTargetError(input.to_owned());
+        let (x, y) = input
+            .trim()
+            .strip_prefix("target area: x=")
+            .and_then(|ranges| ranges.split_once(", y="))
+            .ok_or_else(error)?;
+        Ok(Target {
+            x: parse_range(x).ok_or_else(error)?,
+            y: parse_range(y).ok_or_else(error)?,
+        })
     }
 }
 
-/// Return whether the velocity pair reaches target area after n or more steps
-fn validate_velocity(target: [[i32; 2]; 2], velocity: (u32, i32), mut n: u32) -> bool {
-    loop {
-        let pos = pos_after_n_steps(velocity, n);
-        match cmp_pos_target(pos, target) {
-            Ordering::Equal => return true,
-            Ordering::Less => n += 1,
-            Ordering::Greater => {
-                // println!(
-                //     "velocity {:?} after n {} at pos {:?} not in target {:?}",
-                //     velocity, n, pos, target
-                // );
-                return false;
+/// x position after n steps for a positive initial velocity, it stops after vx steps
+fn x_after_steps(vx: i64, n: i64) -> i64 {
+    let m = n.min(vx);
+    m * vx - m * (m - 1) / 2
+}
+
+/// Return the steps (>= 1) in which x is inside the range for a non-negative velocity,
+/// the end is None if it stays there forever
+fn steps_in_x_range(vx: i64, x: &RangeInclusive<i64>) -> Option<(i64, Option<i64>)> {
+    if vx == 0 {
+        return x.contains(&0).then_some((1, None));
+    }
+    // x is non-decreasing in the steps, binary search for the first step reaching x_min
+    // and the last step not beyond x_max (within the vx steps where it still moves):
+    let first_step_where = |pred: &dyn Fn(i64) -> bool| {
+        let (mut low, mut high) = (1, vx + 1);
+        while low < high {
+            let mid = (low + high) / 2;
+            if pred(x_after_steps(vx, mid)) {
+                high = mid;
+            } else {
+                low = mid + 1;
             }
         }
+        low
+    };
+    let first = first_step_where(&|pos| pos >= *x.start());
+    let beyond = first_step_where(&|pos| pos > *x.end());
+    if first >= beyond {
+        return None;
     }
+    let last = (beyond <= vx).then_some(beyond - 1);
+    Some((first, last))
+}
+
+/// y velocities that are inside the y range after exactly n steps:
+/// y(n) = n * vy - n * (n - 1) / 2 in the range
+fn vy_in_y_range_after(n: i64, y: &RangeInclusive<i64>) -> RangeInclusive<i64> {
+    let offset = n * (n - 1) / 2;
+    // rounding the lower bound up and the upper bound down:
+    (y.start() + offset).div_euclid(n) + ((y.start() + offset).rem_euclid(n) != 0) as i64
+        ..=(y.end() + offset).div_euclid(n)
 }
 
-/// Return the initial velocity component (as float) that reachest closest to target after n steps
-fn xy_root_for_target_after_n_steps(target: i32, n: u32) -> f32 {
-    // reversing f(x, n) see fn pos_after_n_steps:
-    // x based on n and f (the resulting position)? (need >= and <= eventually)
-    //   1/2 (2nx + n - n2) = f
-    //   2nx = 2f - n + n2
-    //   x   = (2f - n + n2) / 2n
-    //   x   = f/n - 1/2 + n/2
-    //   x   = f/n + (n-1)/2
-    assert!(n > 0);
-    target as f32 / n as f32 + (n - 1) as f32 / 2f32
-}
-
-/// Return a range of x-values that will reach x-target in n steps
-fn x_values_in_target_after_n_steps(x_target: [i32; 2], n: u32) -> RangeInclusive<u32> {
-    // use ceil for lower bound and floor for upper bound
-    let lower = xy_root_for_target_after_n_steps(x_target[0], n).ceil() as u32;
-    let upper = xy_root_for_target_after_n_steps(x_target[1], n).floor() as u32;
-    lower..=upper
-}
-
-/// Return a range of y-values that will reach y-target in n steps
-fn y_values_in_target_after_n_steps(y_target: [i32; 2], n: u32) -> RangeInclusive<i32> {
-    // use ceil for lower bound and floor for upper bound
-    let lower = xy_root_for_target_after_n_steps(y_target[0], n).ceil() as i32;
-    let upper = xy_root_for_target_after_n_steps(y_target[1], n).floor() as i32;
-    lower..=upper
-}
-
-/// Return the number of valid initial velocities, i.e. the one that hit the target area
-/// we are assuming that the target area is always in the positive x and negative y direction!
-fn count_valid_initial_v(target: [[i32; 2]; 2]) -> i32 {
-    // we have to distinguish some cases based on x values:
-    // (A) direct hits that get to the target in one go, i.e. x-values in the target range,
-    //     y-values can therefore also be from the whole target range:
-    let direct_hits =
-        ((target[0][0] - target[0][1]).abs() + 1) * ((target[1][0] - target[1][1]).abs() + 1);
-    //println!("Direct hits, in target after 1 step: {}", direct_hits);
-    // (B) reaching the target in the x dimension after 2 or more steps
-    //     either staying in the target area eventually or overshooting.
-    //     calculate the bounds for both cases, find matching y-values,
-    //     collect resulting pairs in a set to eliminate duplicates
-    let mut result_set: HashSet<(u32, i32)> = HashSet::new();
-    // (B1) x-values that reach and then stay in the target area, i.e. triangular roots.
-    //     This allows higher y, specifically up to the one used in part 1, max_y,
-    //     but the possible positive y-values have to be found.
-    //     first find lowest triangular number inside x range!
-    let min_stay_x = triangular_root(target[0][0]).ceil() as u32;
-    let max_stay_x = triangular_root(target[0][1]).floor() as u32;
-    let max_y = find_max_initial_y(target);
-    // println!(
-    //     "X values that end up staying in target range (after x steps): {:?}",
-    //     min_stay_x..=max_stay_x
-    // );
-    for n in min_stay_x..=max_stay_x {
-        let valid_y_range = y_values_in_target_after_n_steps(target[1], n);
-        // println!(
-        //     "Considering for x/n {} y_range {:?} max_y {}",
-        //     n, valid_y_range, max_y
-        // );
-        // The following overestimates the y-range considerably and there is a way of getting the
-        // exact range (full y-target range but offset by 1, then probably several smaller ranges depending on target-range)
-        // but by now the search space is massively reduced, so just validate all:
-        // extend the range up to max_y and combine with n as x
-        for v in (n..n + 1).cartesian_product(*valid_y_range.start()..=max_y) {
-            if validate_velocity(target, v, n) {
-                result_set.insert(v);
+/// merge overlapping or adjacent ranges
+fn merge_ranges(mut ranges: Vec<RangeInclusive<i64>>) -> Vec<RangeInclusive<i64>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| *range.start());
+    let mut merged: Vec<RangeInclusive<i64>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if *range.start() <= last.end() + 1 => {
+                *last = *last.start()..=*last.end().max(range.end());
             }
+            _ => merged.push(range),
         }
     }
-    //println!("Len of result set: {}", result_set.len());
-    // (B2) enumerate x-values that reach the target area in n>1 steps but then overshoot,
-    //     then find y values with the same steps.
-    //     iterate up to and not including n that reaches and then stays in the target area
-    //println!("Remaining steps-range to test: {:?}", 2..min_stay_x);
-    for n in 2u32..min_stay_x {
-        let valid_x_range = x_values_in_target_after_n_steps(target[0], n);
-        let valid_x_range_clone = valid_x_range.clone();
-        //println!("valid x values for {} steps: {:?}", n, valid_x_range);
-        let valid_y_range = y_values_in_target_after_n_steps(target[1], n);
-        //println!("valid y values for {} steps: {:?}", n, valid_y_range);
-        let y_range_end = valid_y_range.end() + 1;
-        result_set.extend(valid_x_range.cartesian_product(valid_y_range));
-        // unfortunately the previous misses a small amount of lucky shots, so we extend the range here too and validate:
-        for v in valid_x_range_clone.cartesian_product(y_range_end..=max_y) {
-            if validate_velocity(target, v, n) {
-                result_set.insert(v);
-            }
+    merged
+}
+
+/// Return the ranges of valid vy for each vx that has any, None if there are infinitely many.
+/// That happens if y can be 0 in the target while x stays in the target:
+/// then every upwards shot comes back down to 0 eventually.
+fn valid_velocity_ranges(target: &Target) -> Option<Vec<(i64, Vec<RangeInclusive<i64>>)>> {
+    // if y = 0 is not in the target, y is in the target only for n up to 2 * max |y|
+    // (beyond that, it skips over the target between two steps):
+    let max_steps = 2 * target.y.start().abs().max(target.y.end().abs()) + 1;
+    // a larger x velocity overshoots in the first step:
+    let vx_range = (*target.x.start()).min(0)..=(*target.x.end()).max(0);
+    let mut result = Vec::new();
+    for vx in vx_range {
+        // shots to the left are a mirror image of shots to the right:
+        let x_range = if vx < 0 {
+            -target.x.end()..=-target.x.start()
+        } else {
+            target.x.clone()
+        };
+        let Some((first, last)) = steps_in_x_range(vx.abs(), &x_range) else {
+            continue;
+        };
+        if last.is_none() && target.y.contains(&0) {
+            return None;
+        }
+        let last = last.unwrap_or(max_steps).min(max_steps);
+        let vy_ranges = merge_ranges(
+            (first..=last)
+                .map(|n| vy_in_y_range_after(n, &target.y))
+                .collect(),
+        );
+        if !vy_ranges.is_empty() {
+            result.push((vx, vy_ranges));
         }
     }
-    //println!("Len of result set: {}", result_set.len());
-
-    // TODO other cases, x values that can reach with negative or zero y, cannot be smaller than triangular root of lower x,
-    // because that would never reach the target in the x dimension,
-    // must not overshoot the target e.g. bigger than max_x_target / 2 + 2
-    // cases with positive y probably need to be close to the triangular roots of the x target bounds for x!
-
-    // x will be triangular number of x, so
-    // find y: y will increase and then decrease back to 0,
-    // ideally we want the next step to land right in the target, so exactly the lower bound of the range,
-    // (further away from 0), we therefore need to start with lowerbound - 1:
-
-    direct_hits + result_set.len() as i32
-}
-
-/// Return an x range and a y range from the string input
-fn parse_target_area(puzzle_input: &'static str) -> [[i32; 2]; 2] {
-    let values: Vec<[i32; 2]> = puzzle_input
-        .trim_start_matches("target area: x=")
-        .split(", y=")
-        .map(|ele| {
-            ele.split("..")
-                .map(|val| val.parse::<i32>().unwrap())
-                .collect::<Vec<i32>>()
-                .try_into()
-                .unwrap()
-        })
-        .collect();
-    values.try_into().unwrap()
+    Some(result)
 }
 
-pub fn process_input(input: &'static str) -> String {
-    let target_area = parse_target_area(input.trim());
-    let max_height = find_max_height(target_area);
-    let num_valid = count_valid_initial_v(target_area);
-    format!(
-        "Max height: {}\nvalid initial velocities: {}\n",
-        max_height, num_valid
+/// Return the number of valid initial velocities, i.e. the ones that hit the target area
+/// after some step, or None if there are infinitely many
+/// ```
+/// use advent_of_code_202x::generated::year2021day17::{count_valid_velocities, Target};
+/// let target = |x, y| Target { x, y };
+/// assert_eq!(count_valid_velocities(&target(20..=30, -10..=-5)), Some(112));
+/// // mirrored to the left:
+/// assert_eq!(count_valid_velocities(&target(-30..=-20, -10..=-5)), Some(112));
+/// // straight up or down:
+/// assert_eq!(count_valid_velocities(&target(-1..=1, 3..=3)), Some(6));
+/// assert_eq!(count_valid_velocities(&target(0..=0, -2..=-1)), Some(4));
+/// // the probe always comes back down to y = 0, and stays at x = 3 after 2 steps with vx = 2:
+/// assert_eq!(count_valid_velocities(&target(3..=4, -1..=1)), None);
+/// ```
+///
+/// Compare with simulating the shots, for random targets:
+/// ```
+/// use advent_of_code_202x::generated::year2021day17::{valid_velocities, Target};
+/// use advent_of_code_202x::testing::XorShift;
+/// let mut rng = XorShift::default();
+/// let hits = |target: &Target, (mut vx, mut vy): (i64, i64)| {
+///     let (mut x, mut y) = (0, 0);
+///     for _ in 0..200 {
+///         (x, y) = (x + vx, y + vy);
+///         vx -= vx.signum();
+///         vy -= 1;
+///         if target.x.contains(&x) && target.y.contains(&y) {
+///             return true;
+///         }
+///     }
+///     false
+/// };
+/// for _ in 0..300 {
+///     let (x1, x2, y1, y2) = (rng.range(-25, 25), rng.range(-25, 25), rng.range(-25, 25), rng.range(-25, 25));
+///     let target = Target { x: x1.min(x2)..=x1.max(x2), y: y1.min(y2)..=y1.max(y2) };
+///     let Some(velocities) = valid_velocities(&target) else {
+///         // infinitely many, only possible with y = 0 in the target:
+///         assert!(target.y.contains(&0));
+///         continue;
+///     };
+///     let brute_force: Vec<(i64, i64)> = (-30..=30)
+///         .flat_map(|vx| (-60..=60).map(move |vy| (vx, vy)))
+///         .filter(|&v| hits(&target, v))
+///         .collect();
+///     assert_eq!(velocities, brute_force, "{:?}", target);
+/// }
+/// ```
+pub fn count_valid_velocities(target: &Target) -> Option<u64> {
+    let ranges = valid_velocity_ranges(target)?;
+    Some(
+        ranges
+            .iter()
+            .flat_map(|(_, vy_ranges)| vy_ranges)
+            .map(|range| (range.end() - range.start() + 1) as u64)
+            .sum(),
+    )
+}
+
+/// Return all valid initial velocities, sorted by x and then y, or None if there are infinitely many
+pub fn valid_velocities(target: &Target) -> Option<Vec<(i64, i64)>> {
+    let ranges = valid_velocity_ranges(target)?;
+    Some(
+        ranges
+            .into_iter()
+            .flat_map(|(vx, vy_ranges)| {
+                vy_ranges
+                    .into_iter()
+                    .flat_map(move |range| range.map(move |vy| (vx, vy)))
+            })
+            .collect(),
     )
 }
 
+/// Return the maximum y value of the projectile so it hits the target area
+/// ```
+/// use advent_of_code_202x::generated::year2021day17::{find_max_height, Target};
+/// assert_eq!(find_max_height(&Target { x: 20..=30, y: -10..=-5 }), Some(45));
+/// // above the launcher, straddling x = 0, hit in the first step with vy = 12:
+/// assert_eq!(find_max_height(&Target { x: -2..=5, y: 10..=12 }), Some(78));
+/// ```
+pub fn find_max_height(target: &Target) -> Option<i64> {
+    let ranges = valid_velocity_ranges(target)?;
+    ranges
+        .iter()
+        .filter_map(|(_, vy_ranges)| vy_ranges.last().map(|range| *range.end()))
+        .max()
+        .map(|max_vy| triangular_number(max_vy.max(0)))
+}
+
+fn describe(target: &Target, list: bool) -> String {
+    let (Some(max_height), Some(velocities)) = (find_max_height(target), valid_velocities(target))
+    else {
+        return "infinitely many valid initial velocities\n".to_owned();
+    };
+    let mut result = format!(
+        "Max height: {}\nvalid initial velocities: {}\n",
+        max_height,
+        velocities.len()
+    );
+    if list {
+        // one line per vx:
+        let mut by_vx: HashMap<i64, Vec<i64>> = HashMap::new();
+        velocities
+            .iter()
+            .for_each(|&(vx, vy)| by_vx.entry(vx).or_default().push(vy));
+        let mut lines: Vec<_> = by_vx.into_iter().collect();
+        lines.sort_unstable();
+        for (vx, vys) in lines {
+            let vys: Vec<_> = vys.iter().map(|vy| vy.to_string()).collect();
+            result += &format!("vx {}: vy {}\n", vx, vys.join(","));
+        }
+    }
+    result
+}
+
+pub fn process_input(input: &str) -> String {
+    let target: Target = input.parse().unwrap();
+    describe(&target, false)
+}
+
 pub fn run_example() -> String {
     process_input(EXAMPLE_INPUT)
 }
@@ -232,3 +295,28 @@ pub fn run_example() -> String {
 pub fn run() -> String {
     process_input(INPUT)
 }
+
+/// Choose another target with `x=-30..-20` and/or `y=5..10`, and list all velocities with `list=true`,
+/// e.g. `cargo run 17 2021 x x=-30..-20 list=true`
+/// ```
+/// use advent_of_code_202x::generated::year2021day17::run_with_params;
+/// let params = [("x", "-1..1"), ("y", "3..3"), ("list", "true")];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(
+///     run_with_params(&params, true),
+///     "Max height: 6\nvalid initial velocities: 6\nvx -1: vy 2,3\nvx 0: vy 2,3\nvx 1: vy 2,3\n"
+/// );
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let mut target: Target = (if use_example { EXAMPLE_INPUT } else { INPUT })
+        .parse()
+        .unwrap();
+    if let Some(x) = params.get("x") {
+        target.x = parse_range(x).expect("x needs to be a range like -30..-20");
+    }
+    if let Some(y) = params.get("y") {
+        target.y = parse_range(y).expect("y needs to be a range like 5..10");
+    }
+    let list = params.get("list").is_some_and(|list| list == "true");
+    describe(&target, list)
+}
@@ -1,6 +1,8 @@
 //! https://adventofcode.com/2021/day/18
 //! Nested pairs of numbers, need to be simplified ("exploded" if nested too deep or "split" if too large)
 //! recursive parsing, depth-first mostly, maybe with a little clever backtracking?
+//! the backtracking turned out to be unnecessary: with a flat list of (depth, value) pairs
+//! the neighbours of an exploding pair are just the adjacent entries (the tree version is kept in `tree`)
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2021day18::run;
@@ -26,24 +28,162 @@ const EXAMPLE_INPUT: &str = "
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]
 "; // final sum: [[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]] magnitude 4140
 
-use std::{fmt, ops::Add, str::Chars};
+use std::{fmt, ops::Add, str::FromStr};
 
 use itertools::Itertools;
 
-/// We need to deal with snailfish numbers: nested pairs of numbers, so we need a recursive type
-#[derive(Debug, Clone)]
-enum SnailfishNumber {
-    Number(u8),
-    Pair(Box<SnailfishNumber>, Box<SnailfishNumber>),
+/// Largest regular number accepted by the parser. Explosions and splits keep the sum of all
+/// regular numbers or make it smaller, so no number can grow beyond that sum in a `u64`.
+const MAX_VALUE: u64 = u32::MAX as u64;
+
+/// Deepest nesting accepted by the parser, so that depths fit into a `u8`
+/// (and stay there: reducing brings all depths down to 4)
+const MAX_DEPTH: usize = 64;
+
+/// A regular number in a snailfish number, with the number of pairs it is nested in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Leaf {
+    depth: u8,
+    value: u64,
+}
+
+/// Snailfish numbers as the flat list of their regular numbers from left to right, each with its depth.
+/// The depths determine the pair structure, so no recursion is needed for reducing,
+/// and the neighbours for an explosion are simply the adjacent entries.
+/// ```
+/// use advent_of_code_202x::generated::year2021day18::SnailfishNumber;
+/// let a: SnailfishNumber = "[[[[4,3],4],4],[7,[[8,4],9]]]".parse().unwrap();
+/// let b: SnailfishNumber = "[1,1]".parse().unwrap();
+/// let sum = a + b;
+/// assert_eq!(sum.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+/// assert_eq!(sum.magnitude(), 1384);
+/// // display round-trips with the parser:
+/// let text = "[[1,[23,4]],[[5,6],7]]";
+/// assert_eq!(text.parse::<SnailfishNumber>().unwrap().to_string(), text);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnailfishNumber {
+    leaves: Vec<Leaf>,
+}
+
+/// Parse failure with the char position in the input and what was expected there
+/// ```
+/// use advent_of_code_202x::generated::year2021day18::{ParseError, SnailfishNumber};
+/// let err = "[1,2".parse::<SnailfishNumber>().unwrap_err();
+/// assert_eq!(err, ParseError { pos: 4, expected: "']'" });
+/// assert_eq!(err.to_string(), "expected ']' at position 4");
+/// let err = "[1;2]".parse::<SnailfishNumber>().unwrap_err();
+/// assert_eq!(err, ParseError { pos: 2, expected: "','" });
+/// let err = "[1,2]]".parse::<SnailfishNumber>().unwrap_err();
+/// assert_eq!(err, ParseError { pos: 5, expected: "end of input" });
+/// // numbers and nesting are limited, so that reducing cannot overflow:
+/// let err = "[4294967296,1]".parse::<SnailfishNumber>().unwrap_err();
+/// assert_eq!(err, ParseError { pos: 1, expected: "a number up to 4294967295" });
+/// let deep = "[".repeat(65) + "1,2" + &"]".repeat(65);
+/// let err = deep.parse::<SnailfishNumber>().unwrap_err();
+/// assert_eq!(err.to_string(), "expected at most 64 nested pairs at position 64");
+/// // an explosion adding up to more than 255:
+/// let big: SnailfishNumber = "[[[[1,255],255],0],0]".parse().unwrap();
+/// let sum = big + "[0,0]".parse().unwrap();
+/// assert_eq!(sum.to_string(), "[[[[0,7],[7,7]],[[7,7],[7,7]]],[[[7,7],[7,7]],[[7,6],[7,6]]]]");
+/// // unreduced input nested deeper than five pairs explodes its innermost pairs first:
+/// let deep: SnailfishNumber = "[[[[[1,[2,3]],4],5],6],7]".parse().unwrap();
+/// let sum = deep + "[0,0]".parse().unwrap();
+/// assert_eq!((sum.to_string(), sum.magnitude()), ("[[[[6,0],[6,6]],7],[0,0]]".to_owned(), 1068));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub pos: usize,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {} at position {}", self.expected, self.pos)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse without recursion, keeping a stack of the open pairs
+/// (false while in the left element of the pair, true in the right one)
+impl FromStr for SnailfishNumber {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = |pos, expected| Err(ParseError { pos, expected });
+        let mut leaves = Vec::new();
+        let mut open_pairs: Vec<bool> = Vec::new();
+        let mut expect_element = true;
+        let mut chars = input.chars().enumerate().peekable();
+        while let Some((pos, c)) = chars.next() {
+            if expect_element {
+                match c {
+                    '[' if open_pairs.len() == MAX_DEPTH => {
+                        return error(pos, "at most 64 nested pairs")
+                    }
+                    '[' => open_pairs.push(false),
+                    '0'..='9' => {
+                        let mut value = c.to_digit(10).unwrap() as u64;
+                        while let Some(digit) = chars.peek().and_then(|&(_, d)| d.to_digit(10)) {
+                            chars.next();
+                            value = value * 10 + digit as u64;
+                            if value > MAX_VALUE {
+                                return error(pos, "a number up to 4294967295");
+                            }
+                        }
+                        let depth = open_pairs.len() as u8;
+                        leaves.push(Leaf { depth, value });
+                        expect_element = false;
+                    }
+                    _ => return error(pos, "'[' or digit"),
+                }
+            } else {
+                match (c, open_pairs.last_mut()) {
+                    (',', Some(in_right @ false)) => {
+                        *in_right = true;
+                        expect_element = true;
+                    }
+                    (']', Some(true)) => {
+                        open_pairs.pop();
+                    }
+                    (_, Some(false)) => return error(pos, "','"),
+                    (_, Some(true)) => return error(pos, "']'"),
+                    (_, None) => return error(pos, "end of input"),
+                }
+            }
+        }
+        let end = input.chars().count();
+        match (expect_element, open_pairs.last()) {
+            (true, _) => error(end, "'[' or digit"),
+            (false, Some(_)) => error(end, "']'"),
+            (false, None) => Ok(SnailfishNumber { leaves }),
+        }
+    }
 }
 
-/// Display SnailfishNumbers in the same way as the input
+/// Display SnailfishNumbers in the same way as the input,
+/// rebuilding the pairs from the depths with a stack of the number of elements done per open pair
 impl fmt::Display for SnailfishNumber {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            SnailfishNumber::Number(num) => write!(f, "{}", num),
-            SnailfishNumber::Pair(left, right) => write!(f, "[{},{}]", left, right),
+        let mut open_pairs: Vec<u8> = Vec::new();
+        for leaf in &self.leaves {
+            while open_pairs.len() < leaf.depth as usize {
+                write!(f, "[")?;
+                open_pairs.push(0);
+            }
+            write!(f, "{}", leaf.value)?;
+            while let Some(elements_done) = open_pairs.last_mut() {
+                *elements_done += 1;
+                if *elements_done == 1 {
+                    write!(f, ",")?;
+                    break;
+                }
+                write!(f, "]")?;
+                open_pairs.pop();
+            }
         }
+        Ok(())
     }
 }
 
@@ -51,172 +191,327 @@ impl fmt::Display for SnailfishNumber {
 impl Add for SnailfishNumber {
     type Output = Self;
 
-    fn add(self, other: Self) -> Self {
-        Self::Pair(Box::new(self), Box::new(other)).reduce()
+    fn add(mut self, other: Self) -> Self {
+        self.leaves.extend(other.leaves);
+        for leaf in &mut self.leaves {
+            leaf.depth += 1;
+        }
+        self.reduce();
+        self
     }
 }
 
-/// Possible results passed back up when dealing with an explosion
-enum Explosion {
-    No,
-    Done,
-    Both(u8, u8),
-    Left(u8),
-    Right(u8),
-}
-
 impl SnailfishNumber {
     /// Reduce the SnailfishNumber: Apply rules repeatedly, always a before b
     /// (a) If any pair is nested inside four pairs, the leftmost such pair explodes: (add left value to next on the left, right to the right)
     /// (b) If any regular number is 10 or greater, the leftmost such regular number splits. (new pair with [floor(num/2), ceil(num/2)])
-    fn reduce(mut self) -> Self {
-        loop {
-            if self.explode() {
-                continue;
+    fn reduce(&mut self) {
+        while self.explode() || self.split() {}
+    }
+
+    /// Explode the leftmost pair of two regular numbers nested inside four or more pairs,
+    /// return whether an explosion occurred (when adding reduced numbers, all pairs that deep
+    /// consist of two regular numbers)
+    fn explode(&mut self) -> bool {
+        // unreduced input can nest deeper than 5 pairs, then the first leaf that deep
+        // may not start a pair, but the leftmost two adjacent leaves of the same depth do:
+        let Some(index) = self
+            .leaves
+            .windows(2)
+            .position(|pair| pair[0].depth > 4 && pair[0].depth == pair[1].depth)
+        else {
+            return false;
+        };
+        let right = self.leaves.remove(index + 1);
+        let left = self.leaves[index];
+        if index > 0 {
+            self.leaves[index - 1].value += left.value;
+        }
+        if let Some(next) = self.leaves.get_mut(index + 1) {
+            next.value += right.value;
+        }
+        self.leaves[index] = Leaf {
+            depth: left.depth - 1,
+            value: 0,
+        };
+        true
+    }
+
+    /// Split the leftmost regular number of 10 or more, return true if a split occurred
+    fn split(&mut self) -> bool {
+        let Some(index) = self.leaves.iter().position(|leaf| leaf.value > 9) else {
+            return false;
+        };
+        let Leaf { depth, value } = self.leaves[index];
+        self.leaves[index] = Leaf {
+            depth: depth + 1,
+            value: value / 2,
+        };
+        self.leaves.insert(
+            index + 1,
+            Leaf {
+                depth: depth + 1,
+                value: value / 2 + value % 2,
+            },
+        );
+        true
+    }
+
+    /// Magnitude without recursion: merge the top two stack entries while they are the two
+    /// elements of the same pair (i.e. have the same depth).
+    /// Saturates at `u64::MAX` for large numbers nested deeply.
+    pub fn magnitude(&self) -> u64 {
+        let mut stack: Vec<(u8, u64)> = Vec::new();
+        for leaf in &self.leaves {
+            let (mut depth, mut magnitude) = (leaf.depth, leaf.value);
+            while let Some(&(left_depth, left_magnitude)) = stack.last() {
+                if left_depth != depth || depth == 0 {
+                    break;
+                }
+                stack.pop();
+                depth -= 1;
+                magnitude = left_magnitude
+                    .saturating_mul(3)
+                    .saturating_add(magnitude.saturating_mul(2));
             }
-            if self.split() {
-                continue;
+            stack.push((depth, magnitude));
+        }
+        stack.last().map_or(0, |&(_, magnitude)| magnitude)
+    }
+}
+
+/// Return the largest magnitude of the sum of any two different numbers
+pub fn max_pair_magnitude(numbers: &[SnailfishNumber]) -> u64 {
+    numbers
+        .iter()
+        .permutations(2)
+        .map(|pair| (pair[0].clone() + pair[1].clone()).magnitude())
+        .max()
+        .unwrap()
+}
+
+/// The first version: snailfish numbers as a recursive tree with boxed pairs,
+/// kept for comparison in the benchmark
+pub mod tree {
+    use std::{fmt, ops::Add, str::Chars};
+
+    use itertools::Itertools;
+
+    /// We need to deal with snailfish numbers: nested pairs of numbers, so we need a recursive type
+    #[derive(Debug, Clone)]
+    pub enum SnailfishNumber {
+        Number(u8),
+        Pair(Box<SnailfishNumber>, Box<SnailfishNumber>),
+    }
+
+    /// Display SnailfishNumbers in the same way as the input
+    impl fmt::Display for SnailfishNumber {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                SnailfishNumber::Number(num) => write!(f, "{}", num),
+                SnailfishNumber::Pair(left, right) => write!(f, "[{},{}]", left, right),
             }
-            break;
         }
-        self
     }
 
-    /// Explode, return whether an explosion occurred
-    fn explode(&mut self) -> bool {
-        !matches!(self.explode_depth(0u8), Explosion::No)
-    }
-
-    /// Explode recursively as if at depth depth, return Explosion type
-    fn explode_depth(&mut self, depth: u8) -> Explosion {
-        if depth < 4u8 {
-            if let SnailfishNumber::Pair(left, right) = self {
-                match left.explode_depth(depth + 1) {
-                    Explosion::Both(a, b) => {
-                        // explosion just happened, send a part to the right and one up
-                        right.add_to_leftmost(b);
-                        Explosion::Left(a)
-                    }
-                    Explosion::Right(b) => {
-                        // explosion leftover that needs to be added to the leftmost number on the right
-                        right.add_to_leftmost(b);
-                        Explosion::Done
-                    }
-                    Explosion::No => {
-                        // need to try exploding the right side instead:
-                        match right.explode_depth(depth + 1) {
-                            Explosion::Both(a, b) => {
-                                // explosion just happened, send a part to the right and one up
-                                left.add_to_rightmost(a);
-                                Explosion::Right(b)
-                            }
-                            Explosion::Left(a) => {
-                                // explosion leftover that needs to be added to the leftmost number on the right
-                                left.add_to_rightmost(a);
-                                Explosion::Done
+    /// Addition for SnailfishNumbers is forming a new pair and then reducing it
+    impl Add for SnailfishNumber {
+        type Output = Self;
+
+        fn add(self, other: Self) -> Self {
+            Self::Pair(Box::new(self), Box::new(other)).reduce()
+        }
+    }
+
+    /// Possible results passed back up when dealing with an explosion
+    enum Explosion {
+        No,
+        Done,
+        Both(u8, u8),
+        Left(u8),
+        Right(u8),
+    }
+
+    impl SnailfishNumber {
+        /// Reduce the SnailfishNumber: Apply rules repeatedly, always a before b
+        /// (a) If any pair is nested inside four pairs, the leftmost such pair explodes: (add left value to next on the left, right to the right)
+        /// (b) If any regular number is 10 or greater, the leftmost such regular number splits. (new pair with [floor(num/2), ceil(num/2)])
+        fn reduce(mut self) -> Self {
+            loop {
+                if self.explode() {
+                    continue;
+                }
+                if self.split() {
+                    continue;
+                }
+                break;
+            }
+            self
+        }
+
+        /// Explode, return whether an explosion occurred
+        fn explode(&mut self) -> bool {
+            !matches!(self.explode_depth(0u8), Explosion::No)
+        }
+
+        /// Explode recursively as if at depth depth, return Explosion type
+        fn explode_depth(&mut self, depth: u8) -> Explosion {
+            if depth < 4u8 {
+                if let SnailfishNumber::Pair(left, right) = self {
+                    match left.explode_depth(depth + 1) {
+                        Explosion::Both(a, b) => {
+                            // explosion just happened, send a part to the right and one up
+                            right.add_to_leftmost(b);
+                            Explosion::Left(a)
+                        }
+                        Explosion::Right(b) => {
+                            // explosion leftover that needs to be added to the leftmost number on the right
+                            right.add_to_leftmost(b);
+                            Explosion::Done
+                        }
+                        Explosion::No => {
+                            // need to try exploding the right side instead:
+                            match right.explode_depth(depth + 1) {
+                                Explosion::Both(a, b) => {
+                                    // explosion just happened, send a part to the right and one up
+                                    left.add_to_rightmost(a);
+                                    Explosion::Right(b)
+                                }
+                                Explosion::Left(a) => {
+                                    // explosion leftover that needs to be added to the leftmost number on the right
+                                    left.add_to_rightmost(a);
+                                    Explosion::Done
+                                }
+                                other => other, // either no/done explosion, or Explosion::Right that needs to be passed up
                             }
-                            other => other, // either no/done explosion, or Explosion::Right that needs to be passed up
                         }
+                        other => other, // either explosion done, or Explosion::Left that needs to be passed up
                     }
-                    other => other, // either explosion done, or Explosion::Left that needs to be passed up
+                } else {
+                    Explosion::No
                 }
             } else {
-                Explosion::No
-            }
-        } else {
-            match self {
-                SnailfishNumber::Number(_) => Explosion::No,
-                SnailfishNumber::Pair(left, right) => {
-                    // need to pass it up, then replace with 0
-                    match (&**left, &**right) {
-                        (SnailfishNumber::Number(left_num), SnailfishNumber::Number(right_num)) => {
-                            let explosion = Explosion::Both(*left_num, *right_num);
-                            *self = SnailfishNumber::Number(0u8);
-                            explosion
-                        }
-                        _ => {
-                            panic!("SnailfishNumber nested deeper than 4 levels!")
+                match self {
+                    SnailfishNumber::Number(_) => Explosion::No,
+                    SnailfishNumber::Pair(left, right) => {
+                        // need to pass it up, then replace with 0
+                        match (&**left, &**right) {
+                            (
+                                SnailfishNumber::Number(left_num),
+                                SnailfishNumber::Number(right_num),
+                            ) => {
+                                let explosion = Explosion::Both(*left_num, *right_num);
+                                *self = SnailfishNumber::Number(0u8);
+                                explosion
+                            }
+                            _ => {
+                                panic!("SnailfishNumber nested deeper than 4 levels!")
+                            }
                         }
                     }
                 }
             }
         }
-    }
 
-    /// Add u8 to leftmost element in the number tree
-    fn add_to_leftmost(&mut self, summand: u8) {
-        match self {
-            SnailfishNumber::Number(num) => {
-                *num += summand;
+        /// Add u8 to leftmost element in the number tree
+        fn add_to_leftmost(&mut self, summand: u8) {
+            match self {
+                SnailfishNumber::Number(num) => {
+                    *num += summand;
+                }
+                SnailfishNumber::Pair(left, _) => left.add_to_leftmost(summand),
             }
-            SnailfishNumber::Pair(left, _) => left.add_to_leftmost(summand),
         }
-    }
 
-    /// Add u8 to rightmost element in the number tree
-    fn add_to_rightmost(&mut self, summand: u8) {
-        match self {
-            SnailfishNumber::Number(num) => {
-                *num += summand;
+        /// Add u8 to rightmost element in the number tree
+        fn add_to_rightmost(&mut self, summand: u8) {
+            match self {
+                SnailfishNumber::Number(num) => {
+                    *num += summand;
+                }
+                SnailfishNumber::Pair(_, right) => right.add_to_rightmost(summand),
             }
-            SnailfishNumber::Pair(_, right) => right.add_to_rightmost(summand),
         }
-    }
 
-    /// Split, return true if a split occurred
-    fn split(&mut self) -> bool {
-        match self {
-            SnailfishNumber::Number(num) => {
-                if *num > 9u8 {
-                    *self = SnailfishNumber::Pair(
-                        Box::new(SnailfishNumber::Number(*num / 2u8)),
-                        Box::new(SnailfishNumber::Number(*num / 2u8 + *num % 2u8)),
-                    );
-                    true
-                } else {
-                    false
+        /// Split, return true if a split occurred
+        fn split(&mut self) -> bool {
+            match self {
+                SnailfishNumber::Number(num) => {
+                    if *num > 9u8 {
+                        *self = SnailfishNumber::Pair(
+                            Box::new(SnailfishNumber::Number(*num / 2u8)),
+                            Box::new(SnailfishNumber::Number(*num / 2u8 + *num % 2u8)),
+                        );
+                        true
+                    } else {
+                        false
+                    }
                 }
+                SnailfishNumber::Pair(left, right) => left.split() || right.split(),
+            }
+        }
+
+        pub fn magnitude(&self) -> u32 {
+            match self {
+                SnailfishNumber::Number(num) => *num as u32,
+                SnailfishNumber::Pair(left, right) => left.magnitude() * 3 + right.magnitude() * 2,
             }
-            SnailfishNumber::Pair(left, right) => left.split() || right.split(),
         }
     }
 
-    fn magnitude(&self) -> u32 {
-        match self {
-            SnailfishNumber::Number(num) => *num as u32,
-            SnailfishNumber::Pair(left, right) => left.magnitude() * 3 + right.magnitude() * 2,
+    /// Return the snailfishnumber from a mutable iterator of chars advancing as necessary
+    fn parse_snailfish_number_from_iter(char_iter: &mut Chars) -> SnailfishNumber {
+        let next = char_iter.next().unwrap();
+        if next == '[' {
+            let left = parse_snailfish_number_from_iter(char_iter);
+            assert!(char_iter.next().unwrap() == ',');
+            let right = parse_snailfish_number_from_iter(char_iter);
+            assert!(char_iter.next().unwrap() == ']');
+            SnailfishNumber::Pair(Box::new(left), Box::new(right))
+        } else {
+            // must be a digit!
+            SnailfishNumber::Number(
+                next.to_digit(10).expect("Digit expected at this position!") as u8
+            )
         }
     }
-}
 
-/// Return the snailfishnumber from a mutable iterator of chars advancing as necessary
-fn parse_snailfish_number_from_iter(char_iter: &mut Chars) -> SnailfishNumber {
-    let next = char_iter.next().unwrap();
-    if next == '[' {
-        let left = parse_snailfish_number_from_iter(char_iter);
-        assert!(char_iter.next().unwrap() == ',');
-        let right = parse_snailfish_number_from_iter(char_iter);
-        assert!(char_iter.next().unwrap() == ']');
-        SnailfishNumber::Pair(Box::new(left), Box::new(right))
-    } else {
-        // must be a digit!
-        SnailfishNumber::Number(next.to_digit(10).expect("Digit expected at this position!") as u8)
+    /// Return the snailfishnumber represented in the string
+    pub fn parse_snailfish_number(puzzle_input: &str) -> SnailfishNumber {
+        parse_snailfish_number_from_iter(&mut puzzle_input.chars())
     }
-}
 
-/// Return the snailfishnumber represented in the string
-fn parse_snailfish_number(puzzle_input: &'static str) -> SnailfishNumber {
-    parse_snailfish_number_from_iter(&mut puzzle_input.chars())
+    /// Return the largest magnitude of the sum of any two different numbers
+    pub fn max_pair_magnitude(numbers: &[SnailfishNumber]) -> u32 {
+        numbers
+            .iter()
+            .permutations(2)
+            .map(|pair| (pair[0].clone() + pair[1].clone()).magnitude())
+            .max()
+            .unwrap()
+    }
 }
 
-pub fn process_input(input: &'static str) -> String {
-    let snailfish_numbers = input.trim().split('\n').map(parse_snailfish_number);
-    let result = snailfish_numbers.clone().reduce(|a, b| a + b).unwrap();
-    let perms = snailfish_numbers.permutations(2);
-    let max_magnitude = perms
-        .map(|mut ele_vec| (ele_vec.pop().unwrap() + ele_vec.pop().unwrap()).magnitude())
-        .max()
+pub fn process_input(input: &str) -> String {
+    let snailfish_numbers: Result<Vec<SnailfishNumber>, _> = input
+        .trim()
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            line.parse()
+                .map_err(|err| format!("Invalid snailfish number in line {}: {}", index + 1, err))
+        })
+        .collect();
+    let snailfish_numbers = match snailfish_numbers {
+        Ok(numbers) => numbers,
+        Err(message) => return message,
+    };
+    let result = snailfish_numbers
+        .iter()
+        .cloned()
+        .reduce(|a, b| a + b)
         .unwrap();
+    let max_magnitude = max_pair_magnitude(&snailfish_numbers);
     format!(
         "Sum magnitude: {}\nMax Magnitude: {}\n",
         result.magnitude(),
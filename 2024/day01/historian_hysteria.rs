@@ -8,7 +8,9 @@
 //!     "sum of differences: 1889772\nsimilarity score: 23228917"));
 //! ```
 
-use std::collections::HashSet;
+use crate::{process_file_param, Params};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
 
 use itertools::Itertools;
 
@@ -29,12 +31,8 @@ const EXAMPLE_INPUT: &str = "
 3   3
 ";
 
-fn str_to_i32(a_str: &str) -> i32 {
-    a_str.parse::<i32>().unwrap()
-}
-
-fn count_occ_in_vec(the_vec: &[i32], the_num: i32) -> i32 {
-    the_vec.iter().filter(|&n| *n == the_num).count() as i32
+fn str_to_i64(a_str: &str) -> i64 {
+    a_str.parse::<i64>().unwrap()
 }
 
 pub fn process_input(input: &str) -> String {
@@ -43,24 +41,24 @@ pub fn process_input(input: &str) -> String {
         .split('\n')
         .map(|e| {
             e.split_whitespace()
-                .map(str_to_i32)
-                .collect_tuple::<(i32, i32)>()
+                .map(str_to_i64)
+                .collect_tuple::<(i64, i64)>()
                 .unwrap()
         })
         .unzip();
     //println!("a_list: {:?}\nb_list: {:?}", a_list, b_list);
     // sort, and then sum the differences
-    let summed_diffs: i32 = a_list
+    // the sums are i128, so even long lists of large numbers cannot overflow them
+    let summed_diffs: i128 = a_list
         .iter()
         .sorted()
         .zip_eq(b_list.iter().sorted())
-        .map(|(a, b)| (a - b).abs())
+        .map(|(&a, &b)| (a as i128 - b as i128).abs())
         .sum();
-    let a_set: HashSet<_> = a_list.iter().cloned().collect();
-    let b_set: HashSet<_> = b_list.iter().cloned().collect();
-    let similarity_score: i32 = a_set
-        .intersection(&b_set)
-        .map(|&num| num * count_occ_in_vec(&a_list, num) * count_occ_in_vec(&b_list, num))
+    let b_counts = b_list.iter().counts();
+    let similarity_score: i128 = a_list
+        .iter()
+        .map(|num| *num as i128 * b_counts.get(num).copied().unwrap_or(0) as i128)
         .sum();
 
     format!(
@@ -69,6 +67,101 @@ pub fn process_input(input: &str) -> String {
     )
 }
 
+/// Same as `process_input`, but reads the input line by line.
+/// Instead of both lists, it keeps how often each number occurs in them,
+/// so the memory grows with the number of different values: that saves a lot for inputs
+/// with many repeated numbers, but for random numbers it is still proportional to the input.
+/// ```
+/// use advent_of_code_202x::generated::year2024day01::{process_input, process_reader};
+/// let input = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+/// assert_eq!(process_reader(input.as_bytes()).unwrap(), process_input(input));
+/// let big = "9000000000000000000   9000000000000000000\n-9000000000000000000   9000000000000000000\n";
+/// assert_eq!(process_reader(big.as_bytes()).unwrap(), process_input(big));
+/// assert_eq!(
+///     process_input(big),
+///     "sum of differences: 18000000000000000000\nsimilarity score: 18000000000000000000"
+/// );
+/// assert!(process_reader("1 2\n3\n".as_bytes()).is_err());
+/// ```
+pub fn process_reader(mut reader: impl BufRead) -> io::Result<String> {
+    let mut counts: BTreeMap<i64, (i64, i64)> = BTreeMap::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        if !line.trim().is_empty() {
+            let (a, b) = line
+                .split_whitespace()
+                .map(str::parse::<i64>)
+                .collect_tuple()
+                .ok_or_else(|| invalid_line(&line))?;
+            let (a, b) = (
+                a.map_err(|_| invalid_line(&line))?,
+                b.map_err(|_| invalid_line(&line))?,
+            );
+            counts.entry(a).or_default().0 += 1;
+            counts.entry(b).or_default().1 += 1;
+        }
+        line.clear();
+    }
+    // pair the sorted numbers of both lists, a run of equal numbers at a time:
+    let mut lefts = counts
+        .iter()
+        .map(|(&n, &(a, _))| (n, a))
+        .filter(|&(_, a)| a > 0);
+    let mut rights = counts
+        .iter()
+        .map(|(&n, &(_, b))| (n, b))
+        .filter(|&(_, b)| b > 0);
+    let (mut left, mut right) = (lefts.next(), rights.next());
+    let mut summed_diffs: i128 = 0;
+    while let (Some((a, a_count)), Some((b, b_count))) = (left, right) {
+        let pairs = a_count.min(b_count);
+        summed_diffs += pairs as i128 * (a as i128 - b as i128).abs();
+        left = if a_count > pairs {
+            Some((a, a_count - pairs))
+        } else {
+            lefts.next()
+        };
+        right = if b_count > pairs {
+            Some((b, b_count - pairs))
+        } else {
+            rights.next()
+        };
+    }
+    let similarity_score: i128 = counts
+        .iter()
+        .map(|(&n, &(a, b))| n as i128 * a as i128 * b as i128)
+        .sum();
+    Ok(format!(
+        "sum of differences: {}\nsimilarity score: {}",
+        summed_diffs, similarity_score,
+    ))
+}
+
+fn invalid_line(line: &str) -> io::Error {
+    let message = format!("expected two numbers: {:?}", line.trim_end());
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// With `file=path`, stream the input from a file, as long as its different numbers fit in memory
+/// ```
+/// use advent_of_code_202x::generated::year2024day01::run_with_params;
+/// let path = std::env::temp_dir().join("historian_hysteria_example");
+/// std::fs::write(&path, "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n").unwrap();
+/// let params = [("file", path.to_str().unwrap())];
+/// let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, false), "sum of differences: 11\nsimilarity score: 31");
+/// std::fs::remove_file(path).unwrap();
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    process_file_param(params, process_reader).unwrap_or_else(|| {
+        if use_example {
+            run_example()
+        } else {
+            run()
+        }
+    })
+}
+
 pub fn run_example() -> String {
     process_input(EXAMPLE_INPUT)
 }
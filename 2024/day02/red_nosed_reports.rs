@@ -8,7 +8,9 @@
 //!     "safe reports: 526\ndampened: 566"));
 //! ```
 
+use crate::{process_file_param, Params};
 use itertools::Itertools;
+use std::io::{self, BufRead};
 
 const INPUT: &str = include_str!("input");
 
@@ -70,6 +72,49 @@ pub fn process_input(input: &str) -> String {
     )
 }
 
+/// Same as `process_input`, but reads the input line by line and checks each report on its own,
+/// so it can be of any size
+/// ```
+/// use advent_of_code_202x::generated::year2024day02::{process_input, process_reader};
+/// let input = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9\n";
+/// assert_eq!(process_reader(input.as_bytes()).unwrap(), process_input(input));
+/// assert!(process_reader("1 2 x\n".as_bytes()).is_err());
+/// ```
+pub fn process_reader(mut reader: impl BufRead) -> io::Result<String> {
+    let (mut safe_count, mut dampened_safe_count) = (0u64, 0u64);
+    let mut line = String::new();
+    let mut report = Vec::new();
+    while reader.read_line(&mut line)? > 0 {
+        report.clear();
+        for level in line.split_whitespace() {
+            report.push(level.parse().map_err(|_| {
+                let message = format!("invalid level {:?} in {:?}", level, line.trim_end());
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            })?);
+        }
+        if !report.is_empty() {
+            safe_count += is_safe_report(&report) as u64;
+            dampened_safe_count += is_dampened_safe_report(&report) as u64;
+        }
+        line.clear();
+    }
+    Ok(format!(
+        "safe reports: {}\ndampened: {}",
+        safe_count, dampened_safe_count
+    ))
+}
+
+/// With `file=path`, stream the input from a file of any size
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    process_file_param(params, process_reader).unwrap_or_else(|| {
+        if use_example {
+            run_example()
+        } else {
+            run()
+        }
+    })
+}
+
 pub fn run_example() -> String {
     process_input(EXAMPLE_INPUT)
 }
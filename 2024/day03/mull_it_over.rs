@@ -1,6 +1,10 @@
 //! https://adventofcode.com/2024/day/3
 //! look for uncorrupted mul instructions
-//! part 2:
+//! part 2: do() and don't() turn the mul instructions on and off
+//!
+//! The memory is scanned byte by byte with a small state machine into a stream of instructions,
+//! from any reader and in constant memory. An interpreter passes each instruction to handlers,
+//! one for each part (or any other closure).
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2024day03::run;
@@ -8,7 +12,8 @@
 //!     "mul result: 164730528\ndodont result: 70478672"));
 //! ```
 
-use regex::Regex;
+use std::fmt;
+use std::io::{self, Read};
 
 const INPUT: &str = include_str!("input");
 
@@ -21,34 +26,234 @@ const INPUT: &str = include_str!("input");
 const EXAMPLE_INPUT: &str =
     "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
 
-fn str_to_i32(a_str: &str) -> i32 {
-    a_str.parse::<i32>().unwrap()
+/// longest instruction name kept, only its end matters
+const MAX_NAME_LEN: usize = 8;
+/// longest arguments of a call, for two numbers with up to 3 digits
+const MAX_ARGS_LEN: usize = 7;
+/// bytes read at once
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// A call `name(args)` found in the memory, args are digits and commas.
+/// Corrupted memory in front of the name is fine, so `xmul(2,4)` is a mul.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(u32, u32),
+    Do,
+    Dont,
+    /// any other call, with (the end of) its name and its args
+    Unknown(String, String),
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Mul(a, b) => write!(f, "mul({},{})", a, b),
+            Instruction::Do => write!(f, "do()"),
+            Instruction::Dont => write!(f, "don't()"),
+            Instruction::Unknown(name, args) => write!(f, "{}({})", name, args),
+        }
+    }
+}
+
+impl Instruction {
+    fn from_call(name: &[u8], args: &[u8]) -> Self {
+        let numbers: Vec<&[u8]> = args.split(|&b| b == b',').collect();
+        let is_number = |n: &[u8]| (1..=3).contains(&n.len());
+        let number = |n: &[u8]| n.iter().fold(0, |acc, d| acc * 10 + (d - b'0') as u32);
+        if name.ends_with(b"mul") && numbers.len() == 2 && numbers.iter().all(|n| is_number(n)) {
+            Instruction::Mul(number(numbers[0]), number(numbers[1]))
+        } else if name.ends_with(b"don't") && args.is_empty() {
+            Instruction::Dont
+        } else if name.ends_with(b"do") && args.is_empty() {
+            Instruction::Do
+        } else {
+            let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+            Instruction::Unknown(text(name), text(args))
+        }
+    }
+}
+
+/// Scan corrupted memory from a reader into instructions, reading it in chunks
+/// ```
+/// use advent_of_code_202x::generated::year2024day03::{Instruction, Scanner};
+/// let memory = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)undo()?don't()";
+/// let instructions: Vec<Instruction> = Scanner::new(memory.as_bytes()).map(Result::unwrap).collect();
+/// assert_eq!(instructions, [
+///     Instruction::Mul(2, 4),
+///     Instruction::Mul(5, 5),
+///     Instruction::Mul(11, 8),
+///     Instruction::Do,
+///     Instruction::Dont,
+/// ]);
+/// let instructions: Vec<String> = Scanner::new("what()from(1)mul(1234,5)mul(4,5,6)".as_bytes())
+///     .map(|i| i.unwrap().to_string())
+///     .collect();
+/// assert_eq!(instructions, ["what()", "from(1)", "mul(1234,5)", "mul(4,5,6)"]);
+/// ```
+pub struct Scanner<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// position and end of the unscanned bytes in the buffer
+    pos: usize,
+    end: usize,
+    name: Vec<u8>,
+    /// the arguments, after an opening bracket
+    args: Option<Vec<u8>>,
+}
+
+impl<R: Read> Scanner<R> {
+    pub fn new(reader: R) -> Self {
+        Scanner {
+            reader,
+            buffer: vec![0; CHUNK_SIZE],
+            pos: 0,
+            end: 0,
+            name: Vec::new(),
+            args: None,
+        }
+    }
+
+    /// Feed one byte to the state machine, return an instruction if it completes one
+    fn scan(&mut self, byte: u8) -> Option<Instruction> {
+        if let Some(args) = &mut self.args {
+            match byte {
+                b')' => {
+                    let instruction = Instruction::from_call(&self.name, args);
+                    self.args = None;
+                    self.name.clear();
+                    return Some(instruction);
+                }
+                b'0'..=b'9' | b',' if args.len() < MAX_ARGS_LEN => {
+                    args.push(byte);
+                    return None;
+                }
+                // not a call, but the byte could start a new name:
+                _ => {
+                    self.args = None;
+                    self.name.clear();
+                }
+            }
+        }
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'\'' => {
+                if self.name.len() == MAX_NAME_LEN {
+                    self.name.remove(0);
+                }
+                self.name.push(byte);
+            }
+            b'(' if !self.name.is_empty() => self.args = Some(Vec::new()),
+            _ => self.name.clear(),
+        }
+        None
+    }
+}
+
+impl<R: Read> Iterator for Scanner<R> {
+    type Item = io::Result<Instruction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pos < self.end {
+                let byte = self.buffer[self.pos];
+                self.pos += 1;
+                if let Some(instruction) = self.scan(byte) {
+                    return Some(Ok(instruction));
+                }
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(len) => (self.pos, self.end) = (0, len),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
 }
 
-fn get_mul_sum(mul_string: &str) -> i32 {
-    // regexp for mul(ddd,ddd)
-    let re = Regex::new(r"mul\(([0-9]{1,3}?),([0-9]{1,3}?)\)").unwrap();
-    re.captures_iter(mul_string)
-        .map(|caps| {
-            let (_, [a, b]) = caps.extract();
-            str_to_i32(a) * str_to_i32(b)
-        })
-        .sum()
+/// Something that reacts to the instructions of the program
+pub trait Handler {
+    fn handle(&mut self, instruction: &Instruction);
+}
+
+impl<F: FnMut(&Instruction)> Handler for F {
+    fn handle(&mut self, instruction: &Instruction) {
+        self(instruction)
+    }
+}
+
+/// Sums up the products of all mul instructions
+#[derive(Debug, Default)]
+pub struct MulSum(pub u64);
+
+impl Handler for MulSum {
+    fn handle(&mut self, instruction: &Instruction) {
+        if let Instruction::Mul(a, b) = instruction {
+            self.0 += *a as u64 * *b as u64;
+        }
+    }
+}
+
+/// Sums up the products of the mul instructions that are enabled by do() and don't()
+#[derive(Debug)]
+pub struct EnabledMulSum {
+    pub sum: u64,
+    enabled: bool,
+}
+
+impl Default for EnabledMulSum {
+    fn default() -> Self {
+        EnabledMulSum {
+            sum: 0,
+            enabled: true,
+        }
+    }
+}
+
+impl Handler for EnabledMulSum {
+    fn handle(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::Mul(a, b) if self.enabled => self.sum += *a as u64 * *b as u64,
+            Instruction::Do => self.enabled = true,
+            Instruction::Dont => self.enabled = false,
+            _ => {}
+        }
+    }
+}
+
+/// Run the instructions in the memory through all handlers, in order
+/// ```
+/// use advent_of_code_202x::generated::year2024day03::{interpret, Handler, Instruction, MulSum};
+/// use std::io::Read;
+/// // a megabyte of garbage around the instructions:
+/// let memory = std::io::repeat(b'x').take(1 << 20)
+///     .chain("mul(3,4)don't()mul(5,6)who()".as_bytes())
+///     .chain(std::io::repeat(b'm').take(1 << 20));
+/// let mut sum = MulSum::default();
+/// let mut unknown = Vec::new();
+/// let mut log_unknown = |i: &Instruction| if let Instruction::Unknown(name, _) = i {
+///     unknown.push(name.clone());
+/// };
+/// interpret(memory, &mut [&mut sum, &mut log_unknown]).unwrap();
+/// assert_eq!(sum.0, 42);
+/// assert_eq!(unknown, ["who"]);
+/// ```
+pub fn interpret(input: impl Read, handlers: &mut [&mut dyn Handler]) -> io::Result<()> {
+    for instruction in Scanner::new(input) {
+        let instruction = instruction?;
+        for handler in handlers.iter_mut() {
+            handler.handle(&instruction);
+        }
+    }
+    Ok(())
 }
 
 pub fn process_input(input: &str) -> String {
-    let mul_string: &str = input.trim();
-    let mul_result = get_mul_sum(mul_string);
-    // part two: initially iterated over more complicted regex with do and dont matches
-    // turning evaluation on and off, but just extracting the disabled parts is better:
-    // (important: could also be a don't() at the end of the input without matching do!)
-    let re_dont_do = Regex::new(r"don\'t\(\)[\s\S]*?(?:do\(\)|$)").unwrap();
-    let shortened_mul_string = re_dont_do.replace_all(mul_string, "");
-    let mul_do_dont_result = get_mul_sum(&shortened_mul_string);
-    //println!("muls: {:?}", muls);
+    let mut mul_sum = MulSum::default();
+    let mut enabled_mul_sum = EnabledMulSum::default();
+    interpret(input.as_bytes(), &mut [&mut mul_sum, &mut enabled_mul_sum]).unwrap();
     format!(
         "mul result: {}\ndodont result: {}",
-        mul_result, mul_do_dont_result
+        mul_sum.0, enabled_mul_sum.sum
     )
 }
 
@@ -1,6 +1,6 @@
 //! https://adventofcode.com/2024/day/5
 //! check sorting order
-//! part 2:
+//! part 2: sort the queues that are out of order, by the rules among their pages
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2024day05::run;
@@ -8,8 +8,8 @@
 //!     "sum of middles: 5955\nsum of sorted middles: 4030"));
 //! ```
 
+use crate::partial_order::PartialOrder;
 use itertools::Itertools;
-use std::{cmp::Ordering, collections::HashMap};
 
 const INPUT: &str = include_str!("input");
 
@@ -50,84 +50,75 @@ const EXAMPLE_INPUT: &str = "
 97,13,75,29,47
 ";
 
-/// rules for print queue ordering, an entry for a number lists the ones that need to be after it if present
-type Rules = HashMap<u8, Vec<u8>>;
+/// rules for print queue ordering: pages that need to be printed before others, if both are present
+type Rules = PartialOrder<u8>;
 
 fn str_to_u8(a_str: &str) -> u8 {
     a_str.parse::<u8>().unwrap()
 }
 
-/// Return a Rules hashmap, with page numbers mapped to page numbers that follow
-fn parse_rules(rules_str: &'static str) -> Rules {
+/// Return the rules, with page numbers ordered before page numbers that follow
+fn parse_rules(rules_str: &str) -> Rules {
     let mut result = Rules::new();
     for (a, b) in rules_str
         .split('\n')
         .map(|line| line.split("|").map(str_to_u8).next_tuple().unwrap())
     {
-        result.entry(a).or_default().push(b);
+        result.add(a, b);
     }
     result
 }
 
 /// Return a list of lists of numbers
-fn parse_queues(queues_str: &'static str) -> Vec<Vec<u8>> {
+fn parse_queues(queues_str: &str) -> Vec<Vec<u8>> {
     queues_str
         .split('\n')
         .map(|line| line.split(',').map(str_to_u8).collect())
         .collect()
 }
 
-/// Return true iff the queue conforms to the rules
-fn check_queue(queue: &Vec<u8>, rules: &Rules) -> bool {
-    //println!("\nChecking queue {:?}", queue);
-    queue
-        .iter()
-        .enumerate()
-        .all(|(index, ele)| match rules.get(ele) {
-            Some(rulelist) => {
-                //println!("Rulelist for {:?}: {:?}", ele, rulelist);
-                rulelist
-                    .iter()
-                    .all(|&other| match queue.iter().position(|&e| e == other) {
-                        Some(other_index) => index < other_index,
-                        None => true,
-                    })
-            }
-            _ => true,
-        })
-}
-
-pub fn process_input(input: &'static str) -> String {
+/// Queues that are not sorted get sorted by the rules among their pages.
+/// Without a rule between two pages, they keep their order,
+/// and queues where the rules form a cycle are reported:
+/// ```
+/// use advent_of_code_202x::generated::year2024day05::process_input;
+/// // 2 is not ordered with 1 or 3, but 1 needs to be before 3:
+/// assert_eq!(process_input("1|3\n\n3,2,1"), "sum of middles: 0\nsum of sorted middles: 1");
+/// assert_eq!(
+///     process_input("1|2\n2|3\n3|1\n4|5\n\n4,5,1\n1,2,3\n5,1,4"),
+///     "sum of middles: 5\nsum of sorted middles: 4\nqueue 2: cycle 1 2 3 1"
+/// );
+/// ```
+pub fn process_input(input: &str) -> String {
     let (rules_str, queues_str) = input.trim().split_once("\n\n").unwrap();
     let rules = parse_rules(rules_str);
     let queues: Vec<Vec<u8>> = parse_queues(queues_str);
-    //println!("rules {:?}\n\nqueues {:?}", rules, queues);
-    let (correct_queues, wrong_queues): (Vec<Vec<u8>>, Vec<Vec<u8>>) =
-        queues.into_iter().partition(|q| check_queue(q, &rules));
-    // filter the correct ones and sum up the middle numbers:
-    let sum_middles: u32 = correct_queues.iter().map(|q| q[q.len() / 2] as u32).sum();
-    let sorted_queues: Vec<Vec<u8>> = wrong_queues
-        .into_iter()
-        .map(|q| {
-            q.into_iter()
-                .sorted_by(|a, b| match rules.get(a) {
-                    Some(rulelist) => {
-                        if rulelist.contains(b) {
-                            Ordering::Less
-                        } else {
-                            Ordering::Greater
-                        }
-                    }
-                    _ => Ordering::Greater,
-                })
-                .collect()
-        })
-        .collect();
-    let sum_sorted_middles: u32 = sorted_queues.iter().map(|q| q[q.len() / 2] as u32).sum();
-    format!(
+    let middle = |q: &Vec<u8>| q[q.len() / 2] as u32;
+    // sum up the middle numbers of the correct ones:
+    let sum_middles: u32 = queues
+        .iter()
+        .filter(|q| rules.is_sorted(q))
+        .map(middle)
+        .sum();
+    let mut sum_sorted_middles = 0;
+    let mut cycles = Vec::new();
+    for (index, queue) in queues.iter().enumerate() {
+        if rules.is_sorted(queue) {
+            continue;
+        }
+        match rules.sort(queue) {
+            Ok(sorted) => sum_sorted_middles += middle(&sorted),
+            Err(cycle) => cycles.push(format!("queue {}: {}", index + 1, cycle)),
+        }
+    }
+    let mut result = format!(
         "sum of middles: {}\nsum of sorted middles: {}",
         sum_middles, sum_sorted_middles
-    )
+    );
+    for cycle in cycles {
+        result += &format!("\n{}", cycle);
+    }
+    result
 }
 
 pub fn run_example() -> String {
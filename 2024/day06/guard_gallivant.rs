@@ -1,6 +1,11 @@
 //! https://adventofcode.com/2024/day/6
 //! moving guard on a grid
-//! part 2:
+//! part 2: count the cells where one more obstruction makes the guard walk in a loop
+//!
+//! The guard only turns at obstacles, so for every cell and direction the table `jumps` has the cell
+//! where the guard stops in front of the next obstacle. A candidate obstruction only changes the stop
+//! when it is between the guard and that cell, so the table is used as is, with this one correction.
+//! Visited directions are a 4-bit mask per cell, and the candidates are checked in parallel.
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2024day06::run;
@@ -8,9 +13,7 @@
 //!     "squares visited: 5131\nblock options: 1784"));
 //! ```
 
-use ndarray::prelude::*;
-use num::integer::div_floor;
-use std::collections::HashSet;
+use std::thread;
 
 const INPUT: &str = include_str!("input");
 
@@ -33,210 +36,192 @@ const EXAMPLE_INPUT: &str = "
 ......#...
 ";
 
-const UP: char = '^';
-const DOWN: char = 'v';
-const LEFT: char = '<';
-const RIGHT: char = '>';
-const DOT: char = '.';
+/// directions in clockwise order, so turning right is adding one
+const DIRECTIONS: [char; 4] = ['^', '>', 'v', '<'];
 const BLOCKED: char = '#';
-const VISITED: char = 'X';
+/// jump target for leaving the grid
+const EXIT: u32 = u32::MAX;
+
+type Direction = usize;
 
+fn turn_right(dir: Direction) -> Direction {
+    (dir + 1) % 4
+}
+
+/// The lab grid with obstacles as cells indexed row by row, and the guard's start
 #[derive(Debug, Clone)]
-struct CharGrid(Array2<char>);
+struct Lab {
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    start: (usize, Direction),
+    /// for each direction and cell: the last cell before the next obstacle, or EXIT
+    jumps: [Vec<u32>; 4],
+}
 
-impl CharGrid {
+impl Lab {
     fn from_str(grid_str: &str) -> Self {
-        let row_length = grid_str.find('\n').unwrap();
-        let num_rows = div_floor(grid_str.len(), row_length);
-        println!("Row length: {:?}, num rows: {:?}", row_length, num_rows);
-        CharGrid {
-            0: Array::from_shape_vec(
-                (num_rows, row_length),
-                grid_str.chars().filter(|&c| c != '\n').collect(),
-            )
-            .unwrap(),
-        }
+        let lines: Vec<&str> = grid_str.lines().collect();
+        let (height, width) = (lines.len(), lines[0].len());
+        let cells: Vec<char> = lines.iter().flat_map(|line| line.chars()).collect();
+        let start = cells
+            .iter()
+            .enumerate()
+            .find_map(|(index, c)| Some((index, DIRECTIONS.iter().position(|d| d == c)?)))
+            .unwrap();
+        let blocked: Vec<bool> = cells.iter().map(|&c| c == BLOCKED).collect();
+        let mut lab = Lab {
+            width,
+            height,
+            blocked,
+            start,
+            jumps: Default::default(),
+        };
+        lab.jumps = [0, 1, 2, 3].map(|dir| lab.jump_table(dir));
+        lab
     }
 
-    fn walk_guard(&mut self) -> u32 {
-        // find starting position/direction and change it to X:
-        let (mut guard_pos, &guard_dir) = self
-            .0
-            .indexed_iter()
-            .find(|(_, &ele)| ele != DOT && ele != BLOCKED)
-            .unwrap();
-        let mut guard_dir = guard_dir;
-        self.0[guard_pos] = VISITED;
-        let mut changed: u32 = 1;
-        // loop walking the guard until they exit the grid
-        while let Some(next_pos) = self.get_pos_in_dir(guard_pos, guard_dir) {
-            match self.0[next_pos] {
-                DOT => {
-                    guard_pos = next_pos;
-                    self.0[guard_pos] = VISITED;
-                    changed += 1;
-                }
-                VISITED => {
-                    guard_pos = next_pos; // already visited, just advance
-                }
-                BLOCKED => {
-                    // don't advance, change direction towards the right:
-                    guard_dir = self.turn_right(guard_dir);
-                }
-                _ => {
-                    panic!("Found an unknown cell!")
-                }
-            }
+    /// Return the neighbouring cell in the direction, if it is on the grid
+    fn step(&self, cell: usize, dir: Direction) -> Option<usize> {
+        let (row, col) = (cell / self.width, cell % self.width);
+        match dir {
+            0 => (row > 0).then(|| cell - self.width),
+            1 => (col + 1 < self.width).then(|| cell + 1),
+            2 => (row + 1 < self.height).then(|| cell + self.width),
+            _ => (col > 0).then(|| cell - 1),
         }
-        changed
     }
 
-    fn turn_right(&self, dir: char) -> char {
-        match dir {
-            UP => RIGHT,
-            RIGHT => DOWN,
-            DOWN => LEFT,
-            LEFT => UP,
-            _ => panic!("Found an unknown direction!"),
+    /// Where the guard stops when walking from each cell in the direction,
+    /// filled in from the far side so the next cell is always known already
+    fn jump_table(&self, dir: Direction) -> Vec<u32> {
+        let mut jumps = vec![EXIT; self.blocked.len()];
+        let cells: Box<dyn Iterator<Item = usize>> = match dir {
+            0 | 3 => Box::new(0..self.blocked.len()),
+            _ => Box::new((0..self.blocked.len()).rev()),
+        };
+        for cell in cells {
+            jumps[cell] = match self.step(cell, dir) {
+                None => EXIT,
+                Some(next) if self.blocked[next] => cell as u32,
+                Some(next) => jumps[next],
+            };
         }
+        jumps
     }
 
-    fn block_guard(&mut self) -> usize {
-        // find starting position/direction:
-        let (start_pos, &guard_dir) = self
-            .0
-            .indexed_iter()
-            .find(|(_, &ele)| ele != DOT && ele != BLOCKED)
-            .unwrap();
-        let (mut guard_pos, mut guard_dir) = (start_pos, guard_dir);
-        let mut spots_checked = HashSet::new();
-        let mut obstruction_candidates = HashSet::new();
-        // loop walking the guard until they exit the grid
-        // check every next_pos for possible blocking
-        // by virtually walking right, checking for a previously trodden path:
-        while let Some(next_pos) = self.get_pos_in_dir(guard_pos, guard_dir) {
-            match self.0[next_pos] {
-                DOT => {
-                    // check if putting a blocker here would lead to a loop
-                    if spots_checked.insert((next_pos, guard_dir)) {
-                        if !obstruction_candidates.contains(&next_pos) {
-                            self.0[next_pos] = BLOCKED;
-                            if self.check_loop(
-                                guard_pos,
-                                self.turn_right(guard_dir),
-                                spots_checked.clone(),
-                            ) {
-                                obstruction_candidates.insert(next_pos);
-                            };
-                        };
-                    };
-                    guard_pos = next_pos;
-                    self.0[guard_pos] = guard_dir;
-                }
-                UP | DOWN | LEFT | RIGHT => {
-                    // we walked through that square earlier and must have checked it
-                    spots_checked.insert((next_pos, guard_dir));
-                    guard_pos = next_pos;
-                    self.0[guard_pos] = guard_dir;
-                }
-                BLOCKED => {
-                    // don't advance, change direction towards the right:
-                    guard_dir = self.turn_right(guard_dir);
-                }
-                _ => {
-                    panic!("Found an unknown cell!")
-                }
+    /// Walk the guard step by step, and return the visited directions per cell,
+    /// and for every visited cell but the start the guard's position and direction before first entering it
+    fn walk_guard(&self) -> (Vec<u8>, Vec<(usize, usize, Direction)>) {
+        let mut visited = vec![0u8; self.blocked.len()];
+        let mut first_entries = Vec::new();
+        let (mut cell, mut dir) = self.start;
+        visited[cell] |= 1 << dir;
+        while let Some(next) = self.step(cell, dir) {
+            if self.blocked[next] {
+                dir = turn_right(dir);
+            } else {
+                if visited[next] == 0 {
+                    first_entries.push((next, cell, dir));
+                }
+                cell = next;
             }
+            visited[cell] |= 1 << dir;
         }
-        obstruction_candidates.remove(&start_pos);
-        obstruction_candidates.len()
+        (visited, first_entries)
     }
 
-    fn check_loop(
-        &self,
-        mut pos: (usize, usize),
-        mut dir: char,
-        mut checked_squares: HashSet<((usize, usize), char)>,
-    ) -> bool {
-        if self.0[pos] == dir {
-            return true;
-        }
-        checked_squares.insert((pos, dir));
-        while let Some(next_pos) = self.get_pos_in_dir(pos, dir) {
-            if checked_squares.contains(&(next_pos, dir)) {
-                return true;
+    /// Return the stop when jumping from the cell, with an extra obstruction
+    fn jump(&self, cell: usize, dir: Direction, obstruction: usize) -> u32 {
+        let stop = self.jumps[dir][cell];
+        let (row, col) = (cell / self.width, cell % self.width);
+        // distances ahead, to the obstruction and to the stop:
+        let ahead = |target: usize| -> Option<usize> {
+            let (t_row, t_col) = (target / self.width, target % self.width);
+            match dir {
+                0 if t_col == col && t_row <= row => Some(row - t_row),
+                1 if t_row == row && t_col >= col => Some(t_col - col),
+                2 if t_col == col && t_row >= row => Some(t_row - row),
+                3 if t_row == row && t_col <= col => Some(col - t_col),
+                _ => None,
             }
-            match self.0[next_pos] {
-                pos_dir if pos_dir == dir => {
-                    return true; // same direction as we are searching, blocker would have worked
-                }
-                DOT | UP | DOWN | RIGHT | LEFT => {
-                    pos = next_pos; // no match, just advance
-                    checked_squares.insert((pos, dir));
-                }
-                BLOCKED => {
-                    // continue checking for loop
-                    dir = self.turn_right(dir);
-                    if self.0[pos] == dir {
-                        return true;
-                    }
-                    checked_squares.insert((pos, dir));
-                }
-                _ => {
-                    panic!("Found an unknown cell!")
-                }
+        };
+        match ahead(obstruction) {
+            Some(distance)
+                if distance > 0 && (stop == EXIT || distance <= ahead(stop as usize).unwrap()) =>
+            {
+                // stop in front of the obstruction:
+                self.step(obstruction, (dir + 2) % 4).unwrap() as u32
             }
+            _ => stop,
         }
-        false
     }
 
-    fn get_pos_in_dir(&self, (pos_r, pos_c): (usize, usize), dir: char) -> Option<(usize, usize)> {
-        match dir {
-            UP => {
-                if pos_r == 0 {
-                    None
-                } else {
-                    Some((pos_r - 1, pos_c))
-                }
-            }
-            DOWN => {
-                if pos_r >= self.0.dim().0 - 1 {
-                    None
-                } else {
-                    Some((pos_r + 1, pos_c))
-                }
-            }
-            LEFT => {
-                if pos_c == 0 {
-                    None
-                } else {
-                    Some((pos_r, pos_c - 1))
-                }
+    /// Return true iff the guard walks in a loop with the obstruction, starting at the cell in the direction.
+    /// `seen` has a 4-bit mask of directions per cell, and is all zero again afterwards.
+    fn is_loop(
+        &self,
+        mut cell: usize,
+        mut dir: Direction,
+        obstruction: usize,
+        seen: &mut [u8],
+    ) -> bool {
+        let mut touched = Vec::new();
+        let is_loop = loop {
+            let stop = self.jump(cell, dir, obstruction);
+            if stop == EXIT {
+                break false;
             }
-            RIGHT => {
-                if pos_c >= self.0.dim().1 - 1 {
-                    None
-                } else {
-                    Some((pos_r, pos_c + 1))
-                }
+            cell = stop as usize;
+            dir = turn_right(dir);
+            if seen[cell] & 1 << dir != 0 {
+                break true;
             }
-            _ => {
-                panic!("Found an unknown direction!")
+            if seen[cell] == 0 {
+                touched.push(cell);
             }
+            seen[cell] |= 1 << dir;
+        };
+        for cell in touched {
+            seen[cell] = 0;
         }
+        is_loop
+    }
+
+    /// Count the cells on the guard's path where an obstruction leads to a loop,
+    /// starting the check from just before the guard would first walk into it
+    fn count_loop_obstructions(&self, first_entries: &[(usize, usize, Direction)]) -> usize {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = first_entries.len().div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            let handles: Vec<_> = first_entries
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut seen = vec![0u8; self.blocked.len()];
+                        chunk
+                            .iter()
+                            .filter(|&&(obstruction, cell, dir)| {
+                                self.is_loop(cell, turn_right(dir), obstruction, &mut seen)
+                            })
+                            .count()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        })
     }
 }
 
 pub fn process_input(input: &str) -> String {
-    let mut lab_grid: CharGrid = CharGrid::from_str(input.trim());
-    //println!("lab grid: {:?}", lab_grid);
-    // walk the guard changing all visited positions to X, returning the count of changes made:
-    let num_changes = lab_grid.clone().walk_guard();
-    let num_blockers = lab_grid.block_guard();
-    //println!("lab grid: {:?}", lab_grid);
+    let lab = Lab::from_str(input.trim());
+    // walk the guard, counting all visited positions:
+    let (visited, first_entries) = lab.walk_guard();
+    let num_visited = visited.iter().filter(|&&dirs| dirs != 0).count();
+    let num_blockers = lab.count_loop_obstructions(&first_entries);
     format!(
         "squares visited: {}\nblock options: {}",
-        num_changes, num_blockers
+        num_visited, num_blockers
     )
 }
 
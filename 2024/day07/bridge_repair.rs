@@ -1,6 +1,12 @@
 //! https://adventofcode.com/2024/day/7
 //! evaluate equations with missing operators
-//! part 2:
+//! part 2: with concatenation of numbers as a third operator
+//!
+//! The operators are evaluated left to right, so the last one is applied last. Searching backwards
+//! from the test value, each operator is undone with its inverse (subtract, divide, strip the digits),
+//! and a branch ends as soon as an inverse does not exist, e.g. when a division has a remainder.
+//! Operators are plain functions with their inverses, so more can be defined and picked on the command line:
+//! `cargo run 7 2024 ops=add,mul,concat,sub list=true`
 //!
 //! ```
 //! use advent_of_code_202x::generated::year2024day07::run;
@@ -8,7 +14,8 @@
 //!     "sum calibrated: 1289579105366\nsum extended: 92148721834692"));
 //! ```
 
-use itertools::{repeat_n, Itertools};
+use crate::Params;
+use itertools::Itertools;
 
 const INPUT: &str = include_str!("input");
 
@@ -30,39 +37,159 @@ const EXAMPLE_INPUT: &str = "
 292: 11 6 16 20
 ";
 
-#[derive(Debug, Clone)]
-enum OP {
-    ADD,
-    MULTIPLY,
-    CONCAT,
+/// The left operands that give a result with a given right operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inverse {
+    Impossible,
+    Unique(u64),
+    /// every left operand gives the result, like for a product with 0
+    Any,
 }
 
-const ELE_OPS: [OP; 2] = [OP::ADD, OP::MULTIPLY];
+impl From<Option<u64>> for Inverse {
+    fn from(left: Option<u64>) -> Self {
+        left.map_or(Inverse::Impossible, Inverse::Unique)
+    }
+}
 
-const MORE_OPS: [OP; 3] = [OP::ADD, OP::MULTIPLY, OP::CONCAT];
+/// A binary operator on u64, with the inverse for its left operand:
+/// `undo(apply(left, right)?, right)` is `Unique(left)` or `Any` for all left and right where apply works.
+/// Apply returns None outside of u64 or outside of the operator's domain.
+#[derive(Debug, Clone, Copy)]
+pub struct Op {
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub apply: fn(u64, u64) -> Option<u64>,
+    pub undo: fn(u64, u64) -> Inverse,
+}
 
-impl OP {
-    fn evaluate(&self, left: u64, right: u64) -> u64 {
-        match self {
-            OP::ADD => left + right,
-            OP::MULTIPLY => left * right,
-            OP::CONCAT => left * 10u64.pow(right.ilog10() + 1) + right,
-        }
-    }
+/// Return 10 to the power of the number of digits of n
+fn digits_power(n: u64) -> Option<u64> {
+    10u64.checked_pow(n.checked_ilog10().unwrap_or(0) + 1)
 }
 
+pub const ADD: Op = Op {
+    name: "add",
+    symbol: "+",
+    apply: |left, right| left.checked_add(right),
+    undo: |result, right| result.checked_sub(right).into(),
+};
+
+/// a product with 0 is 0 for any left operand
+pub const MULTIPLY: Op = Op {
+    name: "mul",
+    symbol: "*",
+    apply: |left, right| left.checked_mul(right),
+    undo: |result, right| match (result, right) {
+        (0, 0) => Inverse::Any,
+        (_, 0) => Inverse::Impossible,
+        _ => (result % right == 0).then(|| result / right).into(),
+    },
+};
+
+pub const CONCAT: Op = Op {
+    name: "concat",
+    symbol: "||",
+    apply: |left, right| left.checked_mul(digits_power(right)?)?.checked_add(right),
+    undo: |result, right| {
+        digits_power(right)
+            .filter(|&power| result % power == right)
+            .map(|power| result / power)
+            .into()
+    },
+};
+
+pub const SUBTRACT: Op = Op {
+    name: "sub",
+    symbol: "-",
+    apply: |left, right| left.checked_sub(right),
+    undo: |result, right| result.checked_add(right).into(),
+};
+
+pub const XOR: Op = Op {
+    name: "xor",
+    symbol: "^",
+    apply: |left, right| Some(left ^ right),
+    undo: |result, right| Inverse::Unique(result ^ right),
+};
+
+/// the operators that can be picked with ops=name,name,...
+pub const OPERATORS: [Op; 5] = [ADD, MULTIPLY, CONCAT, SUBTRACT, XOR];
+
+const ELE_OPS: [Op; 2] = [ADD, MULTIPLY];
+
+const MORE_OPS: [Op; 3] = [ADD, MULTIPLY, CONCAT];
+
 fn str_to_u64(a_str: &str) -> u64 {
     a_str.parse::<u64>().unwrap()
 }
 
+/// ```
+/// use advent_of_code_202x::generated::year2024day07::{Equation, Inverse, Op, ADD, CONCAT, MULTIPLY};
+/// let equation = Equation::from_line("3267: 81 40 27");
+/// let solutions = equation.solve(&[ADD, MULTIPLY], usize::MAX);
+/// let solutions: Vec<String> = solutions.iter().map(|ops| equation.describe(ops)).collect();
+/// assert_eq!(solutions, ["3267 = 81 * 40 + 27", "3267 = 81 + 40 * 27"]);
+/// assert!(Equation::from_line("156: 15 6").solve(&[ADD, MULTIPLY], 1).is_empty());
+/// assert_eq!(Equation::from_line("156: 15 6").solve(&[ADD, MULTIPLY, CONCAT], 1).len(), 1);
+/// // a user defined operator, multiply and add one:
+/// let fma = Op {
+///     name: "fma",
+///     symbol: "*+1",
+///     apply: |left, right| left.checked_mul(right)?.checked_add(1),
+///     undo: |result, right| match (result, right) {
+///         (1, 0) => Inverse::Any,
+///         (0, _) | (_, 0) => Inverse::Impossible,
+///         _ => ((result - 1) % right == 0).then(|| (result - 1) / right).into(),
+///     },
+/// };
+/// let equation = Equation::from_line("22: 3 7");
+/// assert_eq!(equation.describe(&equation.solve(&[ADD, fma], 1)[0]), "22 = 3 *+1 7");
+/// // with a 0, the numbers before it can give anything:
+/// let equation = Equation::from_line("7: 3 0 7");
+/// let solutions = equation.solve(&[ADD, MULTIPLY, CONCAT], 10);
+/// let solutions: Vec<String> = solutions.iter().map(|ops| equation.describe(ops)).collect();
+/// assert_eq!(solutions, ["7 = 3 * 0 + 7", "7 = 3 * 0 || 7"]);
+/// assert_eq!(Equation::from_line("0: 5 0").solve(&[ADD, MULTIPLY], 10).len(), 1);
+/// ```
+///
+/// Compare with trying all operator sequences forward:
+/// ```
+/// use advent_of_code_202x::generated::year2024day07::{Equation, OPERATORS};
+/// use itertools::Itertools;
+/// use advent_of_code_202x::testing::XorShift;
+/// let mut rng = XorShift::default();
+/// for _ in 0..300 {
+///     let numbers: Vec<u64> = (0..1 + rng.below(5)).map(|_| rng.below(30)).collect();
+///     let ops: Vec<_> = OPERATORS.iter().copied().filter(|_| rng.below(2) == 0).collect();
+///     let all_results: Vec<u64> = std::iter::repeat_n(ops.iter(), numbers.len() - 1)
+///         .multi_cartesian_product()
+///         .filter_map(|seq| seq.iter().zip(&numbers[1..]).try_fold(numbers[0], |acc, (op, &n)| (op.apply)(acc, n)))
+///         .collect();
+///     let value = if rng.below(2) == 0 && !all_results.is_empty() {
+///         all_results[rng.below(all_results.len() as u64) as usize]
+///     } else {
+///         rng.below(1000)
+///     };
+///     let equation = Equation { value, numbers };
+///     let expected = all_results.iter().filter(|&&result| result == value).count();
+///     let solutions = equation.solve(&ops, usize::MAX);
+///     assert_eq!(solutions.len(), expected, "{:?}", equation);
+///     for solution in solutions {
+///         let numbers = &equation.numbers;
+///         let result = solution.iter().zip(&numbers[1..]).try_fold(numbers[0], |acc, (op, &n)| (op.apply)(acc, n));
+///         assert_eq!(result, Some(value));
+///     }
+/// }
+/// ```
 #[derive(Debug, Clone)]
-struct Equation {
-    value: u64,
-    numbers: Vec<u64>,
+pub struct Equation {
+    pub value: u64,
+    pub numbers: Vec<u64>,
 }
 
 impl Equation {
-    fn from_str(eq_str: &str) -> Self {
+    pub fn from_line(eq_str: &str) -> Self {
         let (val, nums) = eq_str.split(": ").next_tuple().unwrap();
         Equation {
             value: str_to_u64(val),
@@ -70,37 +197,122 @@ impl Equation {
         }
     }
 
-    fn possible_total(&self, use_ops: &[OP]) -> u64 {
-        // try all possible combinations of operators
-        // if any returns the value, return that, otherwise 0
-        let op_len = self.numbers.len() - 1;
-        for ops in repeat_n(use_ops, op_len).multi_cartesian_product() {
-            let mut nums_iter = self.numbers.clone().into_iter();
-            // take the first element of nubmers as starting value
-            let mut current_value: u64 = nums_iter.next().unwrap();
-            // loop throught the operators zipped with the rest of numbers
-            // if the value goes over value or ends up below it, continue loop
-            // if we hit the target, break out of loop returning value
-            for (op, num) in ops.iter().zip(nums_iter) {
-                //println!("{} {:?} {} =", current_value, op, num);
-                current_value = op.evaluate(current_value, num);
-                if current_value > self.value {
-                    break;
+    /// Return up to `limit` operator sequences that make the equation true
+    pub fn solve(&self, ops: &[Op], limit: usize) -> Vec<Vec<Op>> {
+        let mut solutions = Vec::new();
+        let mut sequence = Vec::with_capacity(self.numbers.len());
+        self.search(
+            ops,
+            self.value,
+            self.numbers.len() - 1,
+            &mut sequence,
+            &mut solutions,
+            limit,
+        );
+        solutions
+    }
+
+    /// Undo the operator before numbers[index] in all ways possible, collecting the sequences backwards
+    fn search(
+        &self,
+        ops: &[Op],
+        target: u64,
+        index: usize,
+        sequence: &mut Vec<Op>,
+        solutions: &mut Vec<Vec<Op>>,
+        limit: usize,
+    ) {
+        if index == 0 {
+            if target == self.numbers[0] {
+                solutions.push(sequence.iter().rev().copied().collect());
+            }
+            return;
+        }
+        for op in ops {
+            if solutions.len() >= limit {
+                return;
+            }
+            sequence.push(*op);
+            match (op.undo)(target, self.numbers[index]) {
+                Inverse::Impossible => {}
+                Inverse::Unique(left) => {
+                    self.search(ops, left, index - 1, sequence, solutions, limit);
+                }
+                Inverse::Any => {
+                    let mut prefix = Vec::with_capacity(index);
+                    self.evaluate_all(
+                        ops,
+                        self.numbers[0],
+                        &mut prefix,
+                        sequence,
+                        solutions,
+                        limit,
+                    );
                 }
             }
-            if current_value == self.value {
-                //println!("FOUND TOTAL {}", current_value);
-                return current_value;
+            sequence.pop();
+        }
+    }
+
+    /// Collect all operator sequences for the numbers before the reversed `sequence` found
+    /// backwards, that can be evaluated, followed by that sequence.
+    /// `prefix` holds the operators before numbers[1..=prefix.len()], which give `value`.
+    fn evaluate_all(
+        &self,
+        ops: &[Op],
+        value: u64,
+        prefix: &mut Vec<Op>,
+        sequence: &[Op],
+        solutions: &mut Vec<Vec<Op>>,
+        limit: usize,
+    ) {
+        if prefix.len() + sequence.len() + 1 == self.numbers.len() {
+            solutions.push(
+                prefix
+                    .iter()
+                    .chain(sequence.iter().rev())
+                    .copied()
+                    .collect(),
+            );
+            return;
+        }
+        for op in ops {
+            if solutions.len() >= limit {
+                return;
+            }
+            if let Some(next) = (op.apply)(value, self.numbers[prefix.len() + 1]) {
+                prefix.push(*op);
+                self.evaluate_all(ops, next, prefix, sequence, solutions, limit);
+                prefix.pop();
             }
         }
-        //println!("failed TOTAL {}", self.value);
-        0
     }
+
+    /// Return the test value if the equation can be made true with these operators, otherwise 0
+    fn possible_total(&self, ops: &[Op]) -> u64 {
+        if self.solve(ops, 1).is_empty() {
+            0
+        } else {
+            self.value
+        }
+    }
+
+    /// Show the equation with the operators filled in
+    pub fn describe(&self, ops: &[Op]) -> String {
+        let mut result = format!("{} = {}", self.value, self.numbers[0]);
+        for (op, number) in ops.iter().zip(&self.numbers[1..]) {
+            result += &format!(" {} {}", op.symbol, number);
+        }
+        result
+    }
+}
+
+fn parse_equations(input: &str) -> Vec<Equation> {
+    input.trim().split('\n').map(Equation::from_line).collect()
 }
 
 pub fn process_input(input: &str) -> String {
-    let equations: Vec<_> = input.trim().split('\n').map(Equation::from_str).collect();
-    //println!("equations: {:?}", equations);
+    let equations = parse_equations(input);
     // filter for equations tha could be correct and sum the test values:
     let sum_calibrated: u64 = equations.iter().map(|eq| eq.possible_total(&ELE_OPS)).sum();
     let sum_extended: u64 = equations
@@ -113,6 +325,51 @@ pub fn process_input(input: &str) -> String {
     )
 }
 
+/// ops=name,name,... picks the operators (add, mul, concat, sub, xor), default add,mul,
+/// and list=true shows an operator sequence for each equation that can be true
+/// ```
+/// use advent_of_code_202x::generated::year2024day07::run_with_params;
+/// let params = [("ops", "add,concat"), ("list", "true")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "sum with add,concat: 348\n156 = 15 || 6\n192 = 17 || 8 + 14");
+/// let params = [("ops", "add,mul,concat")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "sum with add,mul,concat: 11387");
+/// let params = [("ops", "add,div")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+/// assert_eq!(run_with_params(&params, true), "unknown operator div, use add, mul, concat, sub, xor");
+/// ```
+pub fn run_with_params(params: &Params, use_example: bool) -> String {
+    let equations = parse_equations(if use_example { EXAMPLE_INPUT } else { INPUT });
+    let names = params.get("ops").map_or("add,mul", String::as_str);
+    let mut ops = Vec::new();
+    for name in names.split(',') {
+        match OPERATORS.iter().find(|op| op.name == name) {
+            Some(op) => ops.push(*op),
+            None => {
+                return format!(
+                    "unknown operator {}, use {}",
+                    name,
+                    OPERATORS.iter().map(|op| op.name).join(", ")
+                )
+            }
+        }
+    }
+    let list = params.get("list").is_some_and(|list| list == "true");
+    let mut sum = 0;
+    let mut lines = Vec::new();
+    for equation in &equations {
+        if let Some(solution) = equation.solve(&ops, 1).first() {
+            sum += equation.value;
+            if list {
+                lines.push(equation.describe(solution));
+            }
+        }
+    }
+    let mut result = format!("sum with {}: {}", names, sum);
+    for line in lines {
+        result += &format!("\n{}", line);
+    }
+    result
+}
+
 pub fn run_example() -> String {
     process_input(EXAMPLE_INPUT)
 }
@@ -121,7 +121,7 @@ impl AntennaMap {
     fn get_antinodes(&self) -> HashSet<Position> {
         let mut result = HashSet::new();
         // for each letter,
-        for (_, positions) in &self.char_positions {
+        for positions in self.char_positions.values() {
             // get all pairs of antennas,
             for (pos_a, pos_b) in positions.iter().tuple_combinations() {
                 // calculate distance vector between the two points
@@ -146,7 +146,7 @@ impl AntennaMap {
     fn get_extended_antinodes(&self) -> HashSet<Position> {
         let mut result = HashSet::new();
         // for each letter,
-        for (_, positions) in &self.char_positions {
+        for positions in self.char_positions.values() {
             // get all pairs of antennas,
             for (pos_a, pos_b) in positions.iter().tuple_combinations() {
                 // calculate distance vector between the two points
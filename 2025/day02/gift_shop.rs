@@ -7,6 +7,9 @@
 //! ```
 
 use itertools::Itertools;
+use std::ops::RangeInclusive;
+
+use crate::number_theory::factorize;
 
 const INPUT: &str = include_str!("input");
 
@@ -24,11 +27,11 @@ fn str_to_u64(a_str: &str) -> u64 {
 
 /// Return all invalid IDs in the given range
 /// Invalid means a repeat of the same digit pattern twice, e.g. 6464
-fn find_invalid_ids(range_start: &str, range_end: &str) -> Vec<u64> {
+pub fn find_invalid_ids(range_start: &str, range_end: &str) -> Vec<u64> {
     // first check if the range_start and range_end have the same length
     let range_len = range_start.len();
     if range_len == range_end.len() {
-        if range_len % 2 != 0 {
+        if !range_len.is_multiple_of(2) {
             return vec![]; // cannot contain invalid ids
         }
         // even length, so split both start and end in two halfs:
@@ -62,7 +65,7 @@ fn find_invalid_ids(range_start: &str, range_end: &str) -> Vec<u64> {
 
 /// Return all invalid IDs in the given range
 /// This time Invalid means any repeated digit pattern, e.g. 6464, 111, 130130130
-fn find_invalid_ids_new_rules(range_start: &str, range_end: &str) -> Vec<u64> {
+pub fn find_invalid_ids_new_rules(range_start: &str, range_end: &str) -> Vec<u64> {
     // first check if the range_start and range_end have the same length
     let range_len = range_start.len();
     if range_len == range_end.len() {
@@ -75,7 +78,7 @@ fn find_invalid_ids_new_rules(range_start: &str, range_end: &str) -> Vec<u64> {
         // each divisor up to half the len can be a pattern length:
         (1..=range_len / 2)
             // but only exact divisors:
-            .filter(|pl| range_len % pl == 0)
+            .filter(|pl| range_len.is_multiple_of(*pl))
             // take possible patterns from the range_start and range_end strings:
             .flat_map(|pl| {
                 (str_to_u64(&range_start[..pl])..=str_to_u64(&range_end[..pl]))
@@ -97,33 +100,198 @@ fn find_invalid_ids_new_rules(range_start: &str, range_end: &str) -> Vec<u64> {
     }
 }
 
-pub fn process_input(input: &'static str) -> String {
-    let ranges: Vec<_> = input
+/// Which repeated digit patterns make an ID invalid
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Repetition {
+    /// a pattern repeated exactly twice, e.g. 6464
+    Twice,
+    /// a pattern repeated any number of times, e.g. 6464, 111, 130130130
+    AtLeastTwice,
+}
+
+/// Count and sum of the invalid IDs in a range,
+/// the sum is None if it (or a term of the inclusion-exclusion) does not fit into u128
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidIds {
+    pub count: u128,
+    pub sum: Option<u128>,
+}
+
+/// repunit multiplier for a pattern of length `pattern_len` repeated to a total length `len`:
+/// 1 + base^pattern_len + base^(2 * pattern_len) + ..., e.g. 1001001 for 3 times a 2 digit pattern
+fn repunit(base: u128, len: u32, pattern_len: u32) -> Option<u128> {
+    (0..len / pattern_len).try_fold(0u128, |repunit, i| {
+        repunit.checked_add(base.checked_pow(i * pattern_len)?)
+    })
+}
+
+/// Count and sum of all numbers with `len` digits in the range that repeat a pattern of `pattern_len` digits:
+/// these are exactly the multiples pattern * repunit for all patterns without leading zeros
+fn periodic_numbers(
+    range: &RangeInclusive<u128>,
+    base: u128,
+    len: u32,
+    pattern_len: u32,
+) -> (u128, Option<u128>) {
+    let repunit = repunit(base, len, pattern_len).unwrap();
+    let first_pattern = base
+        .pow(pattern_len - 1)
+        .max(range.start().div_ceil(repunit));
+    let last_pattern = (base.pow(pattern_len) - 1).min(range.end() / repunit);
+    if first_pattern > last_pattern {
+        return (0, Some(0));
+    }
+    let count = last_pattern - first_pattern + 1;
+    // sum of the arithmetic series of patterns, times the repunit:
+    let sum = series_sum(first_pattern, last_pattern).and_then(|sum| sum.checked_mul(repunit));
+    (count, sum)
+}
+
+/// first + (first + 1) + ... + last
+fn series_sum(first: u128, last: u128) -> Option<u128> {
+    let count = last - first + 1;
+    if count.is_multiple_of(2) {
+        (count / 2).checked_mul(first.checked_add(last)?)
+    } else {
+        count.checked_mul((first + last) / 2)
+    }
+}
+
+/// Count and sum the numbers in a range that consist of a repeated digit pattern in the given base (2 to 36),
+/// without generating them. For each number of digits, the numbers repeating a pattern with length d
+/// are the multiples of a repunit, and a number with a period d has all multiples of d (that divide the length)
+/// as periods too. So the periodic numbers are the union over the maximal periods len / prime,
+/// which is counted with inclusion-exclusion over sets of primes (i.e. over their gcds).
+/// ```
+/// use advent_of_code_202x::generated::year2025day02::{repeated_pattern_ids, Repetition, InvalidIds};
+/// let invalid_ids = repeated_pattern_ids(95..=115, 10, Repetition::AtLeastTwice);
+/// assert_eq!(invalid_ids, InvalidIds { count: 2, sum: Some(99 + 111) });
+/// let invalid_ids = repeated_pattern_ids(95..=115, 10, Repetition::Twice);
+/// assert_eq!(invalid_ids, InvalidIds { count: 1, sum: Some(99) });
+/// // 0b11, 0b111, 0b1010, 0b1111, 0b11111:
+/// assert_eq!(repeated_pattern_ids(0..=31, 2, Repetition::AtLeastTwice), InvalidIds { count: 5, sum: Some(66) });
+/// // in base 36, ZZ and ZZZ (1295, 46655) are below 36^3:
+/// assert_eq!(repeated_pattern_ids(1..=46655, 36, Repetition::AtLeastTwice).sum, Some(35 * 37 * 18 + 35 * 1333 * 18));
+/// // all of u128 (the 128 bit ones with a 64 bit pattern alone are 2^63), the sum does not fit:
+/// let all_ids = repeated_pattern_ids(0..=u128::MAX, 2, Repetition::AtLeastTwice);
+/// assert!(all_ids.count > 1 << 64 && all_ids.count < 1 << 65 && all_ids.sum.is_none());
+/// ```
+///
+/// Compare with the string based implementation for base 10, and brute force in other bases:
+/// ```
+/// use advent_of_code_202x::generated::year2025day02::{
+///     find_invalid_ids, find_invalid_ids_new_rules, repeated_pattern_ids, Repetition,
+/// };
+/// use advent_of_code_202x::testing::XorShift;
+/// let mut rng = XorShift::default();
+/// for _ in 0..300 {
+///     let (start_digits, range_digits) = (1 + rng.below(9) as u32, rng.below(8) as u32);
+///     let start = rng.below(10u64.pow(start_digits)) + 1;
+///     let end = start + rng.below(10u64.pow(range_digits));
+///     let (start_str, end_str) = (start.to_string(), end.to_string());
+///     for (repetition, ids) in [
+///         (Repetition::Twice, find_invalid_ids(&start_str, &end_str)),
+///         (Repetition::AtLeastTwice, find_invalid_ids_new_rules(&start_str, &end_str)),
+///     ] {
+///         let invalid_ids = repeated_pattern_ids(start as u128..=end as u128, 10, repetition);
+///         assert_eq!(invalid_ids.count, ids.len() as u128, "{}-{}", start, end);
+///         assert_eq!(invalid_ids.sum, Some(ids.iter().map(|&id| id as u128).sum()));
+///     }
+/// }
+/// let is_repeated = |n: u128, base: u128, twice_only: bool| {
+///     let mut digits = Vec::new();
+///     let mut rest = n;
+///     while rest > 0 {
+///         digits.push(rest % base);
+///         rest /= base;
+///     }
+///     (1..digits.len()).filter(|d| digits.len() % d == 0).any(|d| {
+///         (!twice_only || 2 * d == digits.len()) && (d..digits.len()).all(|i| digits[i] == digits[i - d])
+///     })
+/// };
+/// for _ in 0..300 {
+///     let base = 2 + rng.below(35) as u128;
+///     let start = rng.below(100_000) as u128;
+///     let end = start + rng.below(3_000) as u128;
+///     for (repetition, twice_only) in [(Repetition::Twice, true), (Repetition::AtLeastTwice, false)] {
+///         let ids: Vec<u128> = (start..=end).filter(|&n| is_repeated(n, base, twice_only)).collect();
+///         let invalid_ids = repeated_pattern_ids(start..=end, base as u32, repetition);
+///         assert_eq!(invalid_ids.count, ids.len() as u128, "base {}: {}-{}", base, start, end);
+///         assert_eq!(invalid_ids.sum, Some(ids.iter().sum()));
+///     }
+/// }
+/// ```
+pub fn repeated_pattern_ids(
+    range: RangeInclusive<u128>,
+    base: u32,
+    repetition: Repetition,
+) -> InvalidIds {
+    assert!((2..=36).contains(&base), "base needs to be in 2..=36");
+    let base = base as u128;
+    let mut count = 0;
+    let mut sum = Some(0u128);
+    let mut len = 1;
+    let mut len_start = 1u128; // smallest number with len digits
+    while len_start <= *range.end() {
+        // the numbers with len digits in the range:
+        let len_end = base.checked_pow(len).map_or(u128::MAX, |next| next - 1);
+        let len_range = *range.start().max(&len_start)..=*range.end().min(&len_end);
+        if !len_range.is_empty() {
+            // (+1 or -1, pattern length) for the terms of the inclusion-exclusion:
+            let terms: Vec<(bool, u32)> = match repetition {
+                Repetition::Twice if len % 2 == 0 => vec![(true, len / 2)],
+                Repetition::Twice => vec![],
+                Repetition::AtLeastTwice => factorize(len as u64)
+                    .into_iter()
+                    .map(|(prime, _)| prime as u32)
+                    .powerset()
+                    .skip(1)
+                    .map(|primes| (primes.len() % 2 == 1, len / primes.iter().product::<u32>()))
+                    .collect(),
+            };
+            let (mut added, mut subtracted) = (Some(0u128), Some(0u128));
+            for (add, pattern_len) in terms {
+                let (term_count, term_sum) = periodic_numbers(&len_range, base, len, pattern_len);
+                let total = if add { &mut added } else { &mut subtracted };
+                *total = total.zip(term_sum).and_then(|(a, b)| a.checked_add(b));
+                if add {
+                    count += term_count;
+                } else {
+                    count -= term_count;
+                }
+            }
+            let len_sum = added.zip(subtracted).map(|(a, b)| a - b);
+            sum = sum.zip(len_sum).and_then(|(a, b)| a.checked_add(b));
+        }
+        match len_start.checked_mul(base) {
+            Some(next) => len_start = next,
+            None => break,
+        }
+        len += 1;
+    }
+    InvalidIds { count, sum }
+}
+
+pub fn process_input(input: &str) -> String {
+    let ranges: Vec<RangeInclusive<u128>> = input
         .trim()
         .split(',')
-        .map(|r| r.split('-').collect::<Vec<_>>())
-        .collect();
-    //println!("ranges: {:?}", ranges);
-    let sum_of_invalids = ranges
-        .iter()
         .map(|r| {
-            find_invalid_ids(r.first().unwrap(), r.last().unwrap())
-                .iter()
-                .sum::<u64>()
+            let (start, end) = r.split_once('-').unwrap();
+            start.parse().unwrap()..=end.parse().unwrap()
         })
-        .sum::<u64>();
-    let sum_of_invalids_new = ranges
-        .iter()
-        .map(|r| {
-            find_invalid_ids_new_rules(r.first().unwrap(), r.last().unwrap())
-                .iter()
-                //.inspect(|ii| print!(" {} ", ii))
-                .sum::<u64>()
-        })
-        .sum::<u64>();
+        .collect();
+    //println!("ranges: {:?}", ranges);
+    let sum_invalid = |repetition| {
+        ranges
+            .iter()
+            .map(|r| repeated_pattern_ids(r.clone(), 10, repetition).sum.unwrap())
+            .sum::<u128>()
+    };
     format!(
         "Sum of invalid IDs: {:?}\nNew rules: {:?}\n",
-        sum_of_invalids, sum_of_invalids_new
+        sum_invalid(Repetition::Twice),
+        sum_invalid(Repetition::AtLeastTwice)
     )
 }
 
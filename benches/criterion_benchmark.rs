@@ -1,6 +1,9 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 
 use advent_of_code_202x::generated;
+use advent_of_code_202x::generated::year2020day23 as crab_cups;
+use advent_of_code_202x::generated::year2021day18 as snailfish;
+use advent_of_code_202x::generated::{year2015day01, year2024day01, year2024day02};
 
 fn criterion_benchmark(c: &mut Criterion) {
     // iterate through years and days and set up a bench for each:
@@ -15,5 +18,99 @@ fn criterion_benchmark(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, criterion_benchmark);
+/// compare the flat and the tree representation of snailfish numbers on the part 2 all-pairs search
+fn snailfish_benchmark(c: &mut Criterion) {
+    let input = include_str!("../2021/day18/input").trim();
+    let flat: Vec<snailfish::SnailfishNumber> =
+        input.lines().map(|line| line.parse().unwrap()).collect();
+    let trees: Vec<snailfish::tree::SnailfishNumber> = input
+        .lines()
+        .map(snailfish::tree::parse_snailfish_number)
+        .collect();
+    let mut group = c.benchmark_group("21day18_max_pair_magnitude");
+    group.bench_function("flat", |b| b.iter(|| snailfish::max_pair_magnitude(&flat)));
+    group.bench_function("tree", |b| {
+        b.iter(|| snailfish::tree::max_pair_magnitude(&trees))
+    });
+    group.finish();
+}
+
+/// compare u32 and usize links between cups, on a million cups (part 2 size) with fewer moves
+fn crab_cups_benchmark(c: &mut Criterion) {
+    let cups = [6, 1, 4, 7, 5, 2, 8, 3, 9];
+    let mut group = c.benchmark_group("20day23_crab_cups_links");
+    group.sample_size(10);
+    group.bench_function("u32", |b| {
+        b.iter(|| {
+            let mut circle: crab_cups::Circle<u32> =
+                crab_cups::Circle::new(&cups, 1_000_000, 3).unwrap();
+            circle.play(1_000_000);
+            circle.next(1)
+        })
+    });
+    group.bench_function("usize", |b| {
+        b.iter(|| {
+            let mut circle: crab_cups::Circle<usize> =
+                crab_cups::Circle::new(&cups, 1_000_000, 3).unwrap();
+            circle.play(1_000_000);
+            circle.next(1)
+        })
+    });
+    group.finish();
+}
+
+/// compare the throughput in MB/s of reading from a `BufRead` and from a `&str`,
+/// on the puzzle inputs repeated to a few megabytes, after checking that both give the same answer.
+/// Repeating keeps the number of different values of 2024 day 1 at that of its input,
+/// for random numbers its reader would need memory proportional to the input size.
+fn streaming_benchmark(c: &mut Criterion) {
+    type Process = fn(&str) -> String;
+    type ProcessReader = fn(&[u8]) -> std::io::Result<String>;
+    let days: [(&str, &str, Process, ProcessReader); 3] = [
+        (
+            "15day01",
+            include_str!("../2015/day01/input"),
+            year2015day01::process_input,
+            |input| year2015day01::process_reader(input),
+        ),
+        (
+            "24day01",
+            include_str!("../2024/day01/input"),
+            year2024day01::process_input,
+            |input| year2024day01::process_reader(input),
+        ),
+        (
+            "24day02",
+            include_str!("../2024/day02/input"),
+            year2024day02::process_input,
+            |input| year2024day02::process_reader(input),
+        ),
+    ];
+    for (name, input, process_input, process_reader) in days {
+        let input = input.trim().to_owned() + "\n";
+        let input = input.repeat(4_000_000 / input.len() + 1);
+        assert_eq!(
+            process_reader(input.as_bytes()).unwrap(),
+            process_input(&input),
+            "{} gives different answers",
+            name
+        );
+        let mut group = c.benchmark_group(format!("{}_streaming", name));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function("str", |b| b.iter(|| process_input(&input)));
+        group.bench_function("reader", |b| {
+            b.iter(|| process_reader(input.as_bytes()).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    snailfish_benchmark,
+    crab_cups_benchmark,
+    streaming_benchmark
+);
 criterion_main!(benches);
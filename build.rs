@@ -34,11 +34,16 @@ fn write_generated_module(
     writeln!(file, "}}")?;
     writeln!(file)?;
     writeln!(file, "pub type RunFn = fn() -> String;")?;
+    writeln!(
+        file,
+        "pub type ParamsRunFn = fn(&crate::Params, bool) -> String;"
+    )?;
     writeln!(file)?;
     writeln!(file, "#[derive(Copy, Clone)]")?;
     writeln!(file, "pub struct AOCRunFns {{")?;
     writeln!(file, "    pub run: RunFn,")?;
     writeln!(file, "    pub example: RunFn,")?;
+    writeln!(file, "    pub with_params: Option<ParamsRunFn>,")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
     writeln!(
@@ -76,6 +81,17 @@ fn write_generated_module(
                 year_entry,
                 path_str.get(5..10).unwrap(),
             )?;
+            // optional entry point for puzzles that take parameters from the command line:
+            if fs::read_to_string(path_buf)?.contains("pub fn run_with_params(") {
+                writeln!(
+                    file,
+                    "                with_params: Some(year{}{}::run_with_params),",
+                    year_entry,
+                    path_str.get(5..10).unwrap(),
+                )?;
+            } else {
+                writeln!(file, "                with_params: None,")?;
+            }
             writeln!(file, "            }});")?;
         }
         writeln!(file, "        }}")?;
@@ -124,12 +140,7 @@ fn main() -> io::Result<()> {
             let glob_result = glob(&(e.to_owned() + "/**/*.rs"));
             match glob_result {
                 Ok(paths) => {
-                    let path_list: Vec<_> = paths
-                        .filter_map(|p| match p {
-                            Ok(pb) => Some(pb),
-                            _ => None,
-                        })
-                        .collect();
+                    let path_list: Vec<_> = paths.filter_map(|p| p.ok()).collect();
                     if !path_list.is_empty() {
                         Some((year, path_list))
                     } else {
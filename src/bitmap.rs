@@ -0,0 +1,105 @@
+//! Write small images without any image crate: black and white as PBM (portable bitmap),
+//! and palette images as PNG. The PNG data is not compressed, zlib "stored" blocks are enough
+//! for puzzle sized images, and keep the writer short.
+//!
+//! ```
+//! use advent_of_code_202x::bitmap::{write_pbm, write_png};
+//! use ndarray::array;
+//! let pixels = array![[true, false, false], [false, true, true]];
+//! let mut pbm = Vec::new();
+//! write_pbm(pixels.view(), &mut pbm).unwrap();
+//! assert_eq!(String::from_utf8(pbm).unwrap(), "P1\n3 2\n1 0 0\n0 1 1\n");
+//! let mut png = Vec::new();
+//! write_png(array![[0, 1]].view(), &[[0, 0, 0], [255, 255, 255]], &mut png).unwrap();
+//! assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+//! // IHDR with 2x1 pixels, 8 bit palette, and its checksum:
+//! assert_eq!(&png[8..33], b"\0\0\0\x0dIHDR\0\0\0\x02\0\0\0\x01\x08\x03\0\0\0\xc3\xfc\x8f\xb8");
+//! assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+//! ```
+
+use std::io::{self, Write};
+
+use ndarray::ArrayView2;
+
+/// Write the pixels as plain PBM, true is black
+pub fn write_pbm(pixels: ArrayView2<bool>, out: &mut impl Write) -> io::Result<()> {
+    let (rows, cols) = pixels.dim();
+    writeln!(out, "P1\n{} {}", cols, rows)?;
+    for row in pixels.rows() {
+        let line: Vec<&str> = row.iter().map(|&p| if p { "1" } else { "0" }).collect();
+        writeln!(out, "{}", line.join(" "))?;
+    }
+    Ok(())
+}
+
+/// CRC-32 as used by PNG (and zip)
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut checked = kind.to_vec();
+    checked.extend_from_slice(data);
+    out.write_all(&checked)?;
+    out.write_all(&crc32(&checked).to_be_bytes())
+}
+
+/// Write the pixels as PNG, each pixel is an index into the palette of RGB colors (at most 256)
+pub fn write_png(
+    pixels: ArrayView2<u8>,
+    palette: &[[u8; 3]],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let (rows, cols) = pixels.dim();
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+    let mut header = Vec::new();
+    header.extend_from_slice(&(cols as u32).to_be_bytes());
+    header.extend_from_slice(&(rows as u32).to_be_bytes());
+    // 8 bits per pixel, palette colors, default compression, filter and no interlacing:
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"PLTE", &palette.concat())?;
+    // every row starts with filter type 0:
+    let mut raw = Vec::with_capacity(rows * (cols + 1));
+    for row in pixels.rows() {
+        raw.push(0);
+        raw.extend(row.iter());
+    }
+    // zlib header, then stored blocks of at most 65535 bytes:
+    let mut data = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        data.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        data.push(blocks.peek().is_none() as u8);
+        data.extend_from_slice(&len.to_le_bytes());
+        data.extend_from_slice(&(!len).to_le_bytes());
+        data.extend_from_slice(block);
+    }
+    data.extend_from_slice(&adler32(&raw).to_be_bytes());
+    write_chunk(out, b"IDAT", &data)?;
+    write_chunk(out, b"IEND", &[])
+}
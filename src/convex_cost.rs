@@ -0,0 +1,126 @@
+//! Find the integer position x that minimizes the sum of f(|x - p|) over all given positions p,
+//! for any cost f that is convex and non-decreasing in the distance (e.g. the crab fuel of 2021 day 7).
+//! Then the total cost is convex in x too, so a binary search on its slope
+//! (an integer ternary search) finds the minimum with exact integer arithmetic,
+//! evaluating the total O(log(max - min)) times.
+//!
+//! ```
+//! use advent_of_code_202x::convex_cost::{minimize_total_cost, Cost};
+//! let positions = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+//! assert_eq!(minimize_total_cost(&positions, |d| Cost::Linear.cost(d)), Some((2, 37)));
+//! assert_eq!(minimize_total_cost(&positions, |d| Cost::Triangular.cost(d)), Some((5, 168)));
+//! assert_eq!(minimize_total_cost(&positions, |d| Cost::Quadratic.cost(d)), Some((5, 291)));
+//! // any convex cost works, e.g. cubic:
+//! assert_eq!(minimize_total_cost(&positions, |d| (d as u128).pow(3)), Some((6, 2179)));
+//! // far away positions, where an f32 average is off by thousands:
+//! let positions = [1_000_000_000_001, 1_000_000_000_002, 1_000_000_000_006];
+//! assert_eq!(minimize_total_cost(&positions, |d| Cost::Triangular.cost(d)), Some((1_000_000_000_003, 10)));
+//! assert_eq!(minimize_total_cost(&[], |d| Cost::Linear.cost(d)), None);
+//! ```
+//!
+//! Compare with trying every position:
+//! ```
+//! use advent_of_code_202x::convex_cost::{minimize_total_cost, total_cost, Cost};
+//! use advent_of_code_202x::testing::XorShift;
+//! let mut rng = XorShift::default();
+//! for _ in 0..200 {
+//!     let len = 1 + rng.below(20) as usize;
+//!     let positions: Vec<i64> = (0..len).map(|_| rng.below(200) as i64 - 100).collect();
+//!     let cap = rng.below(10);
+//!     for cost in [Cost::Linear, Cost::Triangular, Cost::Quadratic, Cost::Capped(cap)] {
+//!         let cost = |d| cost.cost(d);
+//!         let best = (-100..100).map(|x| total_cost(&positions, x, cost).unwrap()).min();
+//!         let (x, total) = minimize_total_cost(&positions, cost).unwrap();
+//!         assert_eq!(Some(total), best);
+//!         assert_eq!(total_cost(&positions, x, cost), Some(total));
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Convex costs for moving a distance
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Cost {
+    /// one per step
+    Linear,
+    /// each step costs one more than the one before: 1 + 2 + ... + d
+    Triangular,
+    /// d^2
+    Quadratic,
+    /// like triangular, but a step never costs more than the cap
+    Capped(u64),
+}
+
+impl Cost {
+    pub fn cost(self, distance: u64) -> u128 {
+        let triangular = |d: u64| d as u128 * (d as u128 + 1) / 2;
+        let d = distance as u128;
+        match self {
+            Cost::Linear => d,
+            Cost::Triangular => triangular(distance),
+            Cost::Quadratic => d * d,
+            Cost::Capped(cap) if distance <= cap => triangular(distance),
+            Cost::Capped(cap) => triangular(cap) + cap as u128 * (distance - cap) as u128,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownCost(pub String);
+
+impl fmt::Display for UnknownCost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown cost {:?}, use linear, triangular, quadratic or capped:N",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownCost {}
+
+/// Parse linear, triangular, quadratic or capped:N
+impl FromStr for Cost {
+    type Err = UnknownCost;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once(':') {
+            None if input == "linear" => Ok(Cost::Linear),
+            None if input == "triangular" => Ok(Cost::Triangular),
+            None if input == "quadratic" => Ok(Cost::Quadratic),
+            Some(("capped", cap)) => cap
+                .parse()
+                .map(Cost::Capped)
+                .map_err(|_| UnknownCost(input.to_owned())),
+            _ => Err(UnknownCost(input.to_owned())),
+        }
+    }
+}
+
+/// Return the sum of the costs to move from all positions to the target, None if it overflows
+pub fn total_cost(positions: &[i64], target: i64, cost: impl Fn(u64) -> u128) -> Option<u128> {
+    positions.iter().try_fold(0u128, |total, pos| {
+        total.checked_add(cost(pos.abs_diff(target)))
+    })
+}
+
+/// Return the (smallest) position with minimal total cost, and that cost.
+/// The cost needs to be convex and non-decreasing in the distance,
+/// returns None if there are no positions or a total cost on the way overflows.
+pub fn minimize_total_cost(positions: &[i64], cost: impl Fn(u64) -> u128) -> Option<(i64, u128)> {
+    // the cost only increases when moving away from all positions:
+    let mut low = *positions.iter().min()?;
+    let mut high = *positions.iter().max()?;
+    while low < high {
+        let mid = (low as i128 + (high as i128 - low as i128) / 2) as i64;
+        if total_cost(positions, mid, &cost)? <= total_cost(positions, mid + 1, &cost)? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Some((low, total_cost(positions, low, &cost)?))
+}
@@ -0,0 +1,274 @@
+//! Integer expressions with configurable operator precedence.
+//! A tokenizer for multi-digit numbers, binary operators and parentheses,
+//! a Pratt parser driven by an `OpTable` of precedences and associativities,
+//! and a typed AST whose evaluation reports overflow instead of wrapping or panicking.
+//!
+//! ```
+//! use advent_of_code_202x::expr::{Assoc, BinOp, OpTable};
+//! let usual = OpTable::new()
+//!     .with('+', BinOp::Add, 1, Assoc::Left)
+//!     .with('*', BinOp::Mul, 2, Assoc::Left);
+//! let reversed = OpTable::new()
+//!     .with('+', BinOp::Add, 2, Assoc::Left)
+//!     .with('*', BinOp::Mul, 1, Assoc::Left);
+//! assert_eq!(usual.eval("2 * 3 + 40").unwrap(), 46);
+//! assert_eq!(reversed.eval("2 * 3 + 40").unwrap(), 86);
+//! assert_eq!(usual.parse("2 * 3 + 40").unwrap().to_string(), "((2 * 3) + 40)");
+//! let right = OpTable::new().with('-', BinOp::Sub, 1, Assoc::Right);
+//! assert_eq!(right.eval("10 - 4 - 3").unwrap(), 9);
+//! // all precedences work, also the highest one for left-associative operators:
+//! let highest = OpTable::new()
+//!     .with('-', BinOp::Sub, 255, Assoc::Left)
+//!     .with('*', BinOp::Mul, 0, Assoc::Left);
+//! assert_eq!(highest.parse("10 - 4 - 3 * 2").unwrap().to_string(), "(((10 - 4) - 3) * 2)");
+//! ```
+//!
+//! Malformed input and overflow are errors with the position in the input:
+//! ```
+//! use advent_of_code_202x::expr::{Assoc, BinOp, ExprError, OpTable};
+//! let table = OpTable::new().with('*', BinOp::Mul, 1, Assoc::Left);
+//! assert_eq!(table.eval("(2 * 3"), Err(ExprError::UnexpectedEnd));
+//! assert_eq!(table.eval("2 * x"), Err(ExprError::UnexpectedChar(4, 'x')));
+//! assert_eq!(table.eval("2 + 3"), Err(ExprError::UnexpectedChar(2, '+')));
+//! assert_eq!(table.eval("2 3"), Err(ExprError::UnexpectedToken(2)));
+//! assert_eq!(table.eval("4294967296 * 4294967296"), Err(ExprError::Overflow));
+//! assert_eq!(table.eval("99999999999999999999"), Err(ExprError::NumberTooLarge(0)));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprError {
+    /// character at the given position is not a digit, known operator, paren or whitespace
+    UnexpectedChar(usize, char),
+    /// number literal at the given position does not fit into u64
+    NumberTooLarge(usize),
+    /// token at the given position does not fit the expression structure
+    UnexpectedToken(usize),
+    /// input ended in the middle of an expression
+    UnexpectedEnd,
+    Overflow,
+    DivisionByZero,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprError::UnexpectedChar(pos, c) => write!(f, "unexpected char {:?} at {}", c, pos),
+            ExprError::NumberTooLarge(pos) => write!(f, "number too large at {}", pos),
+            ExprError::UnexpectedToken(pos) => write!(f, "unexpected token at {}", pos),
+            ExprError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ExprError::Overflow => write!(f, "arithmetic overflow"),
+            ExprError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    /// checked application, never wraps around
+    pub fn apply(self, lhs: u64, rhs: u64) -> Result<u64, ExprError> {
+        match self {
+            BinOp::Add => lhs.checked_add(rhs).ok_or(ExprError::Overflow),
+            BinOp::Sub => lhs.checked_sub(rhs).ok_or(ExprError::Overflow),
+            BinOp::Mul => lhs.checked_mul(rhs).ok_or(ExprError::Overflow),
+            BinOp::Div => lhs.checked_div(rhs).ok_or(ExprError::DivisionByZero),
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            BinOp::Add => '+',
+            BinOp::Sub => '-',
+            BinOp::Mul => '*',
+            BinOp::Div => '/',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Num(u64),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+/// A token with its (char) position in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub pos: usize,
+}
+
+/// Split `input` into tokens, skipping whitespace.
+/// Any char in `op_chars` is an operator token.
+pub fn tokenize(input: &str, op_chars: &[char]) -> Result<Vec<Token>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let kind = match c {
+            '0'..='9' => {
+                let mut num = c.to_digit(10).unwrap() as u64;
+                while let Some(digit) = chars.peek().and_then(|&(_, d)| d.to_digit(10)) {
+                    chars.next();
+                    num = num
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit as u64))
+                        .ok_or(ExprError::NumberTooLarge(pos))?;
+                }
+                TokenKind::Num(num)
+            }
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            _ if c.is_whitespace() => continue,
+            _ if op_chars.contains(&c) => TokenKind::Op(c),
+            _ => return Err(ExprError::UnexpectedChar(pos, c)),
+        };
+        tokens.push(Token { kind, pos });
+    }
+    Ok(tokens)
+}
+
+/// Abstract syntax tree of a parsed expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Num(u64),
+    BinOp(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self) -> Result<u64, ExprError> {
+        match self {
+            Expr::Num(num) => Ok(*num),
+            Expr::BinOp(op, lhs, rhs) => op.apply(lhs.eval()?, rhs.eval()?),
+        }
+    }
+}
+
+/// fully parenthesized, so the display shows the structure of the tree
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Num(num) => write!(f, "{}", num),
+            Expr::BinOp(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op.symbol(), rhs),
+        }
+    }
+}
+
+/// Operator table: for each operator char its operation, precedence (higher binds tighter)
+/// and associativity
+#[derive(Debug, Default, Clone)]
+pub struct OpTable {
+    ops: HashMap<char, (BinOp, u8, Assoc)>,
+}
+
+impl OpTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) an operator
+    pub fn with(mut self, symbol: char, op: BinOp, precedence: u8, assoc: Assoc) -> Self {
+        self.ops.insert(symbol, (op, precedence, assoc));
+        self
+    }
+
+    /// Tokenize and parse a complete expression
+    pub fn parse(&self, input: &str) -> Result<Expr, ExprError> {
+        let op_chars: Vec<char> = self.ops.keys().copied().collect();
+        let tokens = tokenize(input, &op_chars)?;
+        let mut parser = Parser {
+            table: self,
+            tokens: &tokens,
+            next: 0,
+        };
+        let expr = parser.parse_expr(0)?;
+        match parser.peek() {
+            Some(token) => Err(ExprError::UnexpectedToken(token.pos)),
+            None => Ok(expr),
+        }
+    }
+
+    /// Parse and evaluate a complete expression
+    pub fn eval(&self, input: &str) -> Result<u64, ExprError> {
+        self.parse(input)?.eval()
+    }
+}
+
+/// Pratt parser state over a token slice
+struct Parser<'a> {
+    table: &'a OpTable,
+    tokens: &'a [Token],
+    next: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn advance(&mut self) -> Result<Token, ExprError> {
+        let token = self.peek().ok_or(ExprError::UnexpectedEnd)?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    /// parse primary expr, either a number or a parenthesized expr
+    fn parse_primary(&mut self) -> Result<Expr, ExprError> {
+        let token = self.advance()?;
+        match token.kind {
+            TokenKind::Num(num) => Ok(Expr::Num(num)),
+            TokenKind::LParen => {
+                let expr = self.parse_expr(0)?;
+                match self.advance()?.kind {
+                    TokenKind::RParen => Ok(expr),
+                    _ => Err(ExprError::UnexpectedToken(self.tokens[self.next - 1].pos)),
+                }
+            }
+            _ => Err(ExprError::UnexpectedToken(token.pos)),
+        }
+    }
+
+    /// parse an expr, continue with operators while their precedence is at least `min_precedence`
+    /// (one more than any u8 precedence stops at all operators)
+    fn parse_expr(&mut self, min_precedence: u16) -> Result<Expr, ExprError> {
+        let mut lhs = self.parse_primary()?;
+        while let Some(token) = self.peek() {
+            let symbol = match token.kind {
+                TokenKind::Op(symbol) => symbol,
+                TokenKind::RParen => break,
+                _ => return Err(ExprError::UnexpectedToken(token.pos)),
+            };
+            let (op, precedence, assoc) = self.table.ops[&symbol];
+            let precedence = precedence as u16;
+            if precedence < min_precedence {
+                break;
+            }
+            self.next += 1; // consume peeked operator
+            let rhs_min_precedence = match assoc {
+                Assoc::Left => precedence + 1,
+                Assoc::Right => precedence,
+            };
+            let rhs = self.parse_expr(rhs_min_precedence)?;
+            lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+}
@@ -78,11 +78,13 @@ pub fn get_years() -> [usize; 5] {
 }
 
 pub type RunFn = fn() -> String;
+pub type ParamsRunFn = fn(&crate::Params, bool) -> String;
 
 #[derive(Copy, Clone)]
 pub struct AOCRunFns {
     pub run: RunFn,
     pub example: RunFn,
+    pub with_params: Option<ParamsRunFn>,
 }
 
 /// Return an array of 26 tuples of functions (run, run_example)
@@ -94,142 +96,175 @@ pub fn get_days(year: usize) -> [Option<AOCRunFns>; 26] {
             days[1] = Some(AOCRunFns {
                 run: year2015day01::run,
                 example: year2015day01::run_example,
+                with_params: Some(year2015day01::run_with_params),
             });
             days[2] = Some(AOCRunFns {
                 run: year2015day02::run,
                 example: year2015day02::run_example,
+                with_params: None,
             });
         }
         2020 => {
             days[16] = Some(AOCRunFns {
                 run: year2020day16::run,
                 example: year2020day16::run_example,
+                with_params: None,
             });
             days[17] = Some(AOCRunFns {
                 run: year2020day17::run,
                 example: year2020day17::run_example,
+                with_params: None,
             });
             days[18] = Some(AOCRunFns {
                 run: year2020day18::run,
                 example: year2020day18::run_example,
+                with_params: None,
             });
             days[19] = Some(AOCRunFns {
                 run: year2020day19::run,
                 example: year2020day19::run_example,
+                with_params: None,
             });
             days[20] = Some(AOCRunFns {
                 run: year2020day20::run,
                 example: year2020day20::run_example,
+                with_params: Some(year2020day20::run_with_params),
             });
             days[21] = Some(AOCRunFns {
                 run: year2020day21::run,
                 example: year2020day21::run_example,
+                with_params: Some(year2020day21::run_with_params),
             });
             days[22] = Some(AOCRunFns {
                 run: year2020day22::run,
                 example: year2020day22::run_example,
+                with_params: Some(year2020day22::run_with_params),
             });
             days[23] = Some(AOCRunFns {
                 run: year2020day23::run,
                 example: year2020day23::run_example,
+                with_params: Some(year2020day23::run_with_params),
             });
             days[24] = Some(AOCRunFns {
                 run: year2020day24::run,
                 example: year2020day24::run_example,
+                with_params: None,
             });
             days[25] = Some(AOCRunFns {
                 run: year2020day25::run,
                 example: year2020day25::run_example,
+                with_params: None,
             });
         }
         2021 => {
             days[5] = Some(AOCRunFns {
                 run: year2021day05::run,
                 example: year2021day05::run_example,
+                with_params: None,
             });
             days[6] = Some(AOCRunFns {
                 run: year2021day06::run,
                 example: year2021day06::run_example,
+                with_params: Some(year2021day06::run_with_params),
             });
             days[7] = Some(AOCRunFns {
                 run: year2021day07::run,
                 example: year2021day07::run_example,
+                with_params: Some(year2021day07::run_with_params),
             });
             days[8] = Some(AOCRunFns {
                 run: year2021day08::run,
                 example: year2021day08::run_example,
+                with_params: None,
             });
             days[12] = Some(AOCRunFns {
                 run: year2021day12::run,
                 example: year2021day12::run_example,
+                with_params: None,
             });
             days[13] = Some(AOCRunFns {
                 run: year2021day13::run,
                 example: year2021day13::run_example,
+                with_params: None,
             });
             days[14] = Some(AOCRunFns {
                 run: year2021day14::run,
                 example: year2021day14::run_example,
+                with_params: Some(year2021day14::run_with_params),
             });
             days[15] = Some(AOCRunFns {
                 run: year2021day15::run,
                 example: year2021day15::run_example,
+                with_params: None,
             });
             days[16] = Some(AOCRunFns {
                 run: year2021day16::run,
                 example: year2021day16::run_example,
+                with_params: None,
             });
             days[17] = Some(AOCRunFns {
                 run: year2021day17::run,
                 example: year2021day17::run_example,
+                with_params: Some(year2021day17::run_with_params),
             });
             days[18] = Some(AOCRunFns {
                 run: year2021day18::run,
                 example: year2021day18::run_example,
+                with_params: None,
             });
         }
         2024 => {
             days[1] = Some(AOCRunFns {
                 run: year2024day01::run,
                 example: year2024day01::run_example,
+                with_params: Some(year2024day01::run_with_params),
             });
             days[2] = Some(AOCRunFns {
                 run: year2024day02::run,
                 example: year2024day02::run_example,
+                with_params: Some(year2024day02::run_with_params),
             });
             days[3] = Some(AOCRunFns {
                 run: year2024day03::run,
                 example: year2024day03::run_example,
+                with_params: None,
             });
             days[4] = Some(AOCRunFns {
                 run: year2024day04::run,
                 example: year2024day04::run_example,
+                with_params: None,
             });
             days[5] = Some(AOCRunFns {
                 run: year2024day05::run,
                 example: year2024day05::run_example,
+                with_params: None,
             });
             days[6] = Some(AOCRunFns {
                 run: year2024day06::run,
                 example: year2024day06::run_example,
+                with_params: None,
             });
             days[7] = Some(AOCRunFns {
                 run: year2024day07::run,
                 example: year2024day07::run_example,
+                with_params: Some(year2024day07::run_with_params),
             });
             days[8] = Some(AOCRunFns {
                 run: year2024day08::run,
                 example: year2024day08::run_example,
+                with_params: None,
             });
         }
         2025 => {
             days[1] = Some(AOCRunFns {
                 run: year2025day01::run,
                 example: year2025day01::run_example,
+                with_params: None,
             });
             days[2] = Some(AOCRunFns {
                 run: year2025day02::run,
                 example: year2025day02::run_example,
+                with_params: None,
             });
         }
         _ => {}
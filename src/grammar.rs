@@ -0,0 +1,436 @@
+//! Context-free grammars compiled into indexed rules, with an Earley recognizer.
+//! Rules are written like in 2020 day 19: `name: a b | c "lit"`, i.e. choices of sequences
+//! of rule names and quoted literals. Rule bodies are split and interned only once,
+//! and Earley parsing handles any grammar, including ambiguous and left-recursive ones.
+//! Matched messages can be turned into one or all of their parse trees.
+//!
+//! ```
+//! use advent_of_code_202x::grammar::Grammar;
+//! // left-recursive and ambiguous:
+//! let grammar = Grammar::parse("sum: sum \"+\" sum | num\nnum: \"1\" | \"23\"").unwrap();
+//! assert!(grammar.recognize("sum", "1+23"));
+//! assert!(!grammar.recognize("sum", "1+"));
+//! assert_eq!(grammar.parse_trees("sum", "1+1+1", 10).len(), 2);
+//! let tree = grammar.parse_tree("sum", "1+23").unwrap();
+//! assert_eq!(tree.to_string(), "(sum (sum (num 1)) + (sum (num 2 3)))");
+//! ```
+//!
+//! ```
+//! use advent_of_code_202x::grammar::{Grammar, GrammarError};
+//! assert_eq!(Grammar::parse("0: 1 2\n1: \"a\""), Err(GrammarError::UndefinedRule("2".to_owned())));
+//! assert_eq!(Grammar::parse("0 1"), Err(GrammarError::MissingColon(1)));
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Index of a rule in the grammar
+pub type RuleId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    Rule(RuleId),
+    Char(char),
+}
+
+/// One choice of a rule body: a sequence of symbols (empty for an epsilon rule)
+pub type Sequence = Vec<Symbol>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarError {
+    /// the line with the given (1-based) number has no `name:` prefix
+    MissingColon(usize),
+    /// a literal that is not properly quoted
+    BadLiteral(String),
+    /// a rule that is referenced but never defined
+    UndefinedRule(String),
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrammarError::MissingColon(line) => write!(f, "missing colon in line {}", line),
+            GrammarError::BadLiteral(lit) => write!(f, "bad literal {}", lit),
+            GrammarError::UndefinedRule(name) => write!(f, "undefined rule {}", name),
+        }
+    }
+}
+
+impl std::error::Error for GrammarError {}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    names: Vec<String>,
+    ids: HashMap<String, RuleId>,
+    /// choices of each rule, `None` if the rule has been referenced but not defined yet
+    rules: Vec<Option<Vec<Sequence>>>,
+}
+
+impl Grammar {
+    /// Compile one rule per line (blank lines are ignored), and check that all
+    /// referenced rules are defined
+    pub fn parse(input: &str) -> Result<Self, GrammarError> {
+        let mut grammar = Self::default();
+        grammar.add_rules(input)?;
+        Ok(grammar)
+    }
+
+    /// Add or replace rules, e.g. for a modification of the puzzle rules
+    pub fn add_rules(&mut self, input: &str) -> Result<(), GrammarError> {
+        for (index, line) in input.lines().enumerate() {
+            if !line.trim().is_empty() {
+                self.add_rule(line).map_err(|err| match err {
+                    GrammarError::MissingColon(_) => GrammarError::MissingColon(index + 1),
+                    _ => err,
+                })?;
+            }
+        }
+        self.check_defined()
+    }
+
+    /// Compile a single `name: body` rule, replacing any previous definition
+    fn add_rule(&mut self, line: &str) -> Result<(), GrammarError> {
+        let (name, body) = line.split_once(':').ok_or(GrammarError::MissingColon(1))?;
+        let id = self.intern(name.trim());
+        let mut choices = Vec::new();
+        for option in body.split('|') {
+            let mut sequence = Sequence::new();
+            for elem in option.split_whitespace() {
+                if let Some(quoted) = elem.strip_prefix('"') {
+                    let literal = quoted
+                        .strip_suffix('"')
+                        .filter(|lit| !lit.is_empty())
+                        .ok_or_else(|| GrammarError::BadLiteral(elem.to_owned()))?;
+                    sequence.extend(literal.chars().map(Symbol::Char));
+                } else {
+                    sequence.push(Symbol::Rule(self.intern(elem)));
+                }
+            }
+            choices.push(sequence);
+        }
+        self.rules[id] = Some(choices);
+        Ok(())
+    }
+
+    fn intern(&mut self, name: &str) -> RuleId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        self.rules.push(None);
+        id
+    }
+
+    fn check_defined(&self) -> Result<(), GrammarError> {
+        match self.rules.iter().position(Option::is_none) {
+            Some(id) => Err(GrammarError::UndefinedRule(self.names[id].clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the id of a rule name
+    pub fn id(&self, name: &str) -> Option<RuleId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, rule: RuleId) -> &str {
+        &self.names[rule]
+    }
+
+    /// Return the choices of a rule
+    pub fn choices(&self, rule: RuleId) -> &[Sequence] {
+        self.rules[rule].as_deref().unwrap_or_default()
+    }
+
+    /// Return for each rule whether it can match the empty string
+    fn nullable(&self) -> Vec<bool> {
+        let mut nullable = vec![false; self.rules.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in 0..self.rules.len() {
+                if !nullable[rule]
+                    && self.choices(rule).iter().any(|seq| {
+                        seq.iter()
+                            .all(|sym| matches!(sym, Symbol::Rule(r) if nullable[*r]))
+                    })
+                {
+                    nullable[rule] = true;
+                    changed = true;
+                }
+            }
+        }
+        nullable
+    }
+
+    /// Return true if the rule `start` matches the whole msg.
+    /// Panics if `start` is not a rule name.
+    pub fn recognize(&self, start: &str, msg: &str) -> bool {
+        EarleyRun::new(self, self.id(start).unwrap(), msg).accepts()
+    }
+
+    /// Return a parse tree for msg if the rule `start` matches the whole msg
+    pub fn parse_tree(&self, start: &str, msg: &str) -> Option<ParseTree> {
+        self.parse_trees(start, msg, 1).pop()
+    }
+
+    /// Return up to `limit` distinct parse trees of msg under the rule `start`.
+    /// Derivations that go around a cycle of rules without consuming input
+    /// (e.g. `a: a | "x"`) are skipped, so the result is always finite.
+    pub fn parse_trees(&self, start: &str, msg: &str, limit: usize) -> Vec<ParseTree> {
+        let run = EarleyRun::new(self, self.id(start).unwrap(), msg);
+        if !run.accepts() {
+            return Vec::new();
+        }
+        let mut trees = TreeBuilder {
+            grammar: self,
+            msg: &run.msg,
+            completed: run.completed_spans(),
+            limit,
+            active: HashSet::new(),
+        };
+        trees.build_rule(run.start, 0, run.msg.len())
+    }
+}
+
+/// Derivation of (part of) a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTree {
+    Node(String, Vec<ParseTree>),
+    Leaf(char),
+}
+
+/// s-expression with the rule name first, leaves are printed as plain chars
+impl fmt::Display for ParseTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseTree::Leaf(c) => write!(f, "{}", c),
+            ParseTree::Node(name, children) => {
+                write!(f, "({}", name)?;
+                for child in children {
+                    write!(f, " {}", child)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Earley item: a rule choice with the position of the dot and the start position of the match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: RuleId,
+    choice: usize,
+    dot: usize,
+    origin: usize,
+}
+
+/// the chart of an Earley parse: the items for each position in msg
+struct EarleyRun<'a> {
+    grammar: &'a Grammar,
+    start: RuleId,
+    msg: Vec<char>,
+    chart: Vec<Vec<Item>>,
+}
+
+impl<'a> EarleyRun<'a> {
+    fn new(grammar: &'a Grammar, start: RuleId, msg: &str) -> Self {
+        let msg: Vec<char> = msg.chars().collect();
+        let mut run = EarleyRun {
+            grammar,
+            start,
+            chart: vec![Vec::new(); msg.len() + 1],
+            msg,
+        };
+        run.fill_chart();
+        run
+    }
+
+    fn next_symbol(&self, item: &Item) -> Option<Symbol> {
+        self.grammar.choices(item.rule)[item.choice]
+            .get(item.dot)
+            .copied()
+    }
+
+    /// predict, scan and complete until all item sets are closed
+    /// (predicting nullable rules also advances over them, as in Aycock & Horspool)
+    fn fill_chart(&mut self) {
+        let nullable = self.grammar.nullable();
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); self.chart.len()];
+        let mut add = |chart: &mut Vec<Vec<Item>>, pos: usize, item: Item| {
+            if seen[pos].insert(item) {
+                chart[pos].push(item);
+            }
+        };
+        for choice in 0..self.grammar.choices(self.start).len() {
+            let item = Item {
+                rule: self.start,
+                choice,
+                dot: 0,
+                origin: 0,
+            };
+            add(&mut self.chart, 0, item);
+        }
+        for pos in 0..self.chart.len() {
+            let mut index = 0;
+            while index < self.chart[pos].len() {
+                let item = self.chart[pos][index];
+                let advanced = Item {
+                    dot: item.dot + 1,
+                    ..item
+                };
+                match self.next_symbol(&item) {
+                    Some(Symbol::Char(c)) => {
+                        if self.msg.get(pos) == Some(&c) {
+                            add(&mut self.chart, pos + 1, advanced);
+                        }
+                    }
+                    Some(Symbol::Rule(rule)) => {
+                        for choice in 0..self.grammar.choices(rule).len() {
+                            let predicted = Item {
+                                rule,
+                                choice,
+                                dot: 0,
+                                origin: pos,
+                            };
+                            add(&mut self.chart, pos, predicted);
+                        }
+                        if nullable[rule] {
+                            add(&mut self.chart, pos, advanced);
+                        }
+                    }
+                    None => {
+                        // completed: advance all items in the origin set waiting for this rule
+                        let mut waiting_index = 0;
+                        while waiting_index < self.chart[item.origin].len() {
+                            let waiting = self.chart[item.origin][waiting_index];
+                            if self.next_symbol(&waiting) == Some(Symbol::Rule(item.rule)) {
+                                let advanced_waiting = Item {
+                                    dot: waiting.dot + 1,
+                                    ..waiting
+                                };
+                                add(&mut self.chart, pos, advanced_waiting);
+                            }
+                            waiting_index += 1;
+                        }
+                    }
+                }
+                index += 1;
+            }
+        }
+    }
+
+    fn accepts(&self) -> bool {
+        self.chart[self.msg.len()].iter().any(|item| {
+            item.rule == self.start && item.origin == 0 && self.next_symbol(item).is_none()
+        })
+    }
+
+    /// Return all (rule, choice, from, to) such that the rule choice derives msg[from..to]
+    fn completed_spans(&self) -> HashSet<(RuleId, usize, usize, usize)> {
+        let mut spans = HashSet::new();
+        for (pos, items) in self.chart.iter().enumerate() {
+            for item in items {
+                if self.next_symbol(item).is_none() {
+                    spans.insert((item.rule, item.choice, item.origin, pos));
+                }
+            }
+        }
+        spans
+    }
+}
+
+/// Build parse trees top-down, using the completed items of an Earley chart to
+/// only try splits of a span that can succeed
+struct TreeBuilder<'a> {
+    grammar: &'a Grammar,
+    msg: &'a [char],
+    completed: HashSet<(RuleId, usize, usize, usize)>,
+    limit: usize,
+    /// (rule, from, to) currently being built, to cut cyclic derivations
+    active: HashSet<(RuleId, usize, usize)>,
+}
+
+impl TreeBuilder<'_> {
+    fn build_rule(&mut self, rule: RuleId, from: usize, to: usize) -> Vec<ParseTree> {
+        if !self.active.insert((rule, from, to)) {
+            return Vec::new();
+        }
+        let mut trees = Vec::new();
+        for choice in 0..self.grammar.choices(rule).len() {
+            if trees.len() >= self.limit {
+                break;
+            }
+            if !self.completed.contains(&(rule, choice, from, to)) {
+                continue;
+            }
+            let sequence = &self.grammar.choices(rule)[choice];
+            for children in self.build_sequence(sequence, from, to) {
+                if trees.len() >= self.limit {
+                    break;
+                }
+                trees.push(ParseTree::Node(
+                    self.grammar.name(rule).to_owned(),
+                    children,
+                ));
+            }
+        }
+        self.active.remove(&(rule, from, to));
+        trees
+    }
+
+    /// Return all ways (up to the limit) of matching the sequence to msg[from..to]
+    fn build_sequence(
+        &mut self,
+        sequence: &[Symbol],
+        from: usize,
+        to: usize,
+    ) -> Vec<Vec<ParseTree>> {
+        let (first, rest) = match sequence.split_first() {
+            None => {
+                return if from == to {
+                    vec![Vec::new()]
+                } else {
+                    Vec::new()
+                }
+            }
+            Some(split) => split,
+        };
+        let mut results = Vec::new();
+        let heads: Vec<(ParseTree, usize)> = match *first {
+            Symbol::Char(c) => {
+                if from < to && self.msg[from] == c {
+                    vec![(ParseTree::Leaf(c), from + 1)]
+                } else {
+                    Vec::new()
+                }
+            }
+            Symbol::Rule(rule) => {
+                let mut heads = Vec::new();
+                for mid in from..=to {
+                    let derivable = (0..self.grammar.choices(rule).len())
+                        .any(|choice| self.completed.contains(&(rule, choice, from, mid)));
+                    if derivable {
+                        for tree in self.build_rule(rule, from, mid) {
+                            heads.push((tree, mid));
+                        }
+                    }
+                }
+                heads
+            }
+        };
+        for (head, mid) in heads {
+            for tail in self.build_sequence(rest, mid, to) {
+                if results.len() >= self.limit {
+                    return results;
+                }
+                let mut children = vec![head.clone()];
+                children.extend(tail);
+                results.push(children);
+            }
+        }
+        results
+    }
+}
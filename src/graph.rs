@@ -0,0 +1,212 @@
+//! Small graphs with interned node names and bitset adjacency.
+//! Node names are mapped to dense integer ids on insertion, so that sets of nodes
+//! (neighbours, visited nodes on a path) fit into a single `u64` bit mask.
+//! This limits a graph to 64 nodes, which is plenty for the path enumeration puzzles.
+//!
+//! ```
+//! use advent_of_code_202x::graph::{Graph, NodeSet};
+//! // input loaded at runtime, no 'static lifetime needed:
+//! let input = String::from("start-A\nA-b\nA-end\nb-end");
+//! let graph = Graph::from_undirected_edges(&input, '-');
+//! let (start, end) = (graph.id("start").unwrap(), graph.id("end").unwrap());
+//! // count simple paths (every node at most once):
+//! let num_paths = graph.count_paths(start, end, NodeSet::single(start), |node, visited| {
+//!     (!visited.contains(node)).then(|| visited.with(node))
+//! });
+//! assert_eq!(num_paths, 2);
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Dense integer id of an interned node name
+pub type NodeId = usize;
+
+/// Maximum number of nodes in a graph, given by the width of the `NodeSet` bit mask
+pub const MAX_NODES: usize = u64::BITS as usize;
+
+/// A set of node ids stored as a bit mask
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeSet(u64);
+
+impl NodeSet {
+    pub const EMPTY: NodeSet = NodeSet(0);
+
+    /// Return a set containing only `node`
+    pub fn single(node: NodeId) -> Self {
+        NodeSet(1 << node)
+    }
+
+    pub fn contains(self, node: NodeId) -> bool {
+        self.0 & (1 << node) != 0
+    }
+
+    /// Return a copy of the set with `node` added
+    pub fn with(self, node: NodeId) -> Self {
+        NodeSet(self.0 | (1 << node))
+    }
+
+    /// Add `node`, return true if it was not in the set before
+    pub fn insert(&mut self, node: NodeId) -> bool {
+        let was_new = !self.contains(node);
+        self.0 |= 1 << node;
+        was_new
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over the node ids in the set, in increasing order
+    pub fn iter(self) -> impl Iterator<Item = NodeId> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let node = bits.trailing_zeros() as NodeId;
+            bits &= bits - 1; // clear lowest set bit
+            Some(node)
+        })
+    }
+}
+
+impl FromIterator<NodeId> for NodeSet {
+    fn from_iter<I: IntoIterator<Item = NodeId>>(iter: I) -> Self {
+        let mut set = NodeSet::EMPTY;
+        for node in iter {
+            set.insert(node);
+        }
+        set
+    }
+}
+
+/// Directed graph on interned node names, undirected edges are stored in both directions
+#[derive(Debug, Default, Clone)]
+pub struct Graph {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    adjacency: Vec<NodeSet>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse one undirected edge per line, with the two node names split by `separator`
+    /// (blank lines are ignored)
+    pub fn from_undirected_edges(input: &str, separator: char) -> Self {
+        let mut graph = Self::new();
+        for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (from, to) = line.split_once(separator).unwrap();
+            let (from, to) = (graph.intern(from), graph.intern(to));
+            graph.add_undirected_edge(from, to);
+        }
+        graph
+    }
+
+    /// Return the id for `name`, adding a new node if it is not known yet.
+    /// Panics if the graph would grow beyond `MAX_NODES`.
+    pub fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        assert!(id < MAX_NODES, "graph is limited to {} nodes", MAX_NODES);
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        self.adjacency.push(NodeSet::EMPTY);
+        id
+    }
+
+    /// Return the id of an already interned node name
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, node: NodeId) -> &str {
+        &self.names[node]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Iterate over all node ids
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.len()
+    }
+
+    /// Return the set of all nodes whose name fulfills the predicate
+    pub fn nodes_where(&self, predicate: impl Fn(&str) -> bool) -> NodeSet {
+        self.nodes().filter(|&n| predicate(self.name(n))).collect()
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        self.adjacency[from].insert(to);
+    }
+
+    pub fn add_undirected_edge(&mut self, a: NodeId, b: NodeId) {
+        self.add_edge(a, b);
+        self.add_edge(b, a);
+    }
+
+    pub fn neighbours(&self, node: NodeId) -> NodeSet {
+        self.adjacency[node]
+    }
+
+    /// Count all paths from `start` to `end` without materializing them.
+    /// `initial` is the path state after visiting `start`; `enter(node, state)` is called
+    /// before stepping to a neighbouring node and returns the new path state,
+    /// or `None` if the visit constraints forbid entering that node.
+    /// A path ends as soon as it reaches `end`.
+    /// Counts are memoized on (node, state), so the state should only contain
+    /// what the constraints actually depend on (e.g. a `NodeSet` of visited small nodes).
+    /// The callback needs to rule out infinite paths (e.g. cycles of freely revisitable nodes),
+    /// otherwise this will not terminate.
+    pub fn count_paths<S, F>(&self, start: NodeId, end: NodeId, initial: S, mut enter: F) -> u64
+    where
+        S: Clone + Eq + Hash,
+        F: FnMut(NodeId, &S) -> Option<S>,
+    {
+        let mut memo = HashMap::new();
+        self.count_paths_from(start, end, initial, &mut enter, &mut memo)
+    }
+
+    fn count_paths_from<S, F>(
+        &self,
+        node: NodeId,
+        end: NodeId,
+        state: S,
+        enter: &mut F,
+        memo: &mut HashMap<(NodeId, S), u64>,
+    ) -> u64
+    where
+        S: Clone + Eq + Hash,
+        F: FnMut(NodeId, &S) -> Option<S>,
+    {
+        if node == end {
+            return 1;
+        }
+        if let Some(&count) = memo.get(&(node, state.clone())) {
+            return count;
+        }
+        let mut count = 0;
+        for neigh in self.neighbours(node).iter() {
+            if let Some(neigh_state) = enter(neigh, &state) {
+                count += self.count_paths_from(neigh, end, neigh_state, enter, memo);
+            }
+        }
+        memo.insert((node, state), count);
+        count
+    }
+}
@@ -0,0 +1,190 @@
+//! Sets of values stored as sorted, disjoint half-open ranges, like the ticket field rules
+//! of 2020 day 16. Overlapping and touching ranges are merged when inserted,
+//! so a containment query is a single binary search, and set operations walk both
+//! range lists once.
+//!
+//! ```
+//! use advent_of_code_202x::interval_set::IntervalSet;
+//! let mut set: IntervalSet<u32> = [1..4, 10..12].into_iter().collect();
+//! set.insert(4..6);
+//! assert_eq!(set.ranges(), &[1..6, 10..12]);
+//! assert!(set.contains(&5) && !set.contains(&6));
+//! assert!(set.contains_range(&(2..5)) && !set.contains_range(&(5..11)));
+//! assert_eq!(set.total_len(), 7);
+//! let other: IntervalSet<u32> = [3..11].into_iter().collect();
+//! assert_eq!(set.union(&other).ranges(), &[1..12]);
+//! assert_eq!(set.intersection(&other).ranges(), &[3..6, 10..11]);
+//! assert_eq!(set.difference(&other).ranges(), &[1..3, 11..12]);
+//! ```
+//!
+//! Compare with plain bit sets, for random ranges:
+//! ```
+//! use advent_of_code_202x::interval_set::IntervalSet;
+//! use advent_of_code_202x::testing::XorShift;
+//! let mut rng = XorShift::default();
+//! let mut random_set = || {
+//!     let mut set = IntervalSet::new();
+//!     let mut bits = 0u64;
+//!     for _ in 0..rng.below(5) {
+//!         let (start, end) = (rng.below(64), rng.below(65));
+//!         set.insert(start..end);
+//!         bits |= (start..end).fold(0, |bits, i| bits | 1 << i);
+//!     }
+//!     (set, bits)
+//! };
+//! let to_bits = |set: &IntervalSet<u64>| (0..64).fold(0u64, |bits, i| bits | (set.contains(&i) as u64) << i);
+//! for _ in 0..500 {
+//!     let ((a, a_bits), (b, b_bits)) = (random_set(), random_set());
+//!     assert_eq!(to_bits(&a), a_bits);
+//!     assert_eq!(a.total_len(), a_bits.count_ones() as u64);
+//!     assert!(a.ranges().windows(2).all(|pair| pair[0].end < pair[1].start));
+//!     assert_eq!(to_bits(&a.union(&b)), a_bits | b_bits);
+//!     assert_eq!(to_bits(&a.intersection(&b)), a_bits & b_bits);
+//!     assert_eq!(to_bits(&a.difference(&b)), a_bits & !b_bits);
+//!     assert!(a.difference(&b).ranges().iter().all(|range| !range.is_empty()));
+//! }
+//! ```
+
+use std::iter::Sum;
+use std::ops::{Range, Sub};
+
+/// A set of values of an ordered type, as sorted ranges that neither overlap nor touch
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Copy + Ord> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut ranges: Vec<Range<T>> = iter.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort_unstable_by_key(|range| range.start);
+        Self::from_sorted(ranges)
+    }
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// Merge non-empty ranges sorted by start
+    fn from_sorted(sorted: impl IntoIterator<Item = Range<T>>) -> Self {
+        let mut ranges: Vec<Range<T>> = Vec::new();
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        Self { ranges }
+    }
+
+    /// The disjoint ranges of the set, sorted
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Add the range, merging it with the ranges it overlaps or touches
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = if first < last {
+            self.ranges[first].start.min(range.start)..self.ranges[last - 1].end.max(range.end)
+        } else {
+            range
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Return the range containing the value, by binary search
+    fn find(&self, value: &T) -> Option<&Range<T>> {
+        let index = self.ranges.partition_point(|r| r.end <= *value);
+        self.ranges.get(index).filter(|r| r.start <= *value)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Return true iff all values of the range are in the set
+    pub fn contains_range(&self, range: &Range<T>) -> bool {
+        range.is_empty() || self.find(&range.start).is_some_and(|r| range.end <= r.end)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let (mut mine, mut theirs) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        // merge both sorted lists by start:
+        let sorted = std::iter::from_fn(|| match (mine.peek(), theirs.peek()) {
+            (Some(a), Some(b)) if b.start < a.start => theirs.next(),
+            (Some(_), _) => mine.next(),
+            (None, _) => theirs.next(),
+        });
+        Self::from_sorted(sorted.cloned())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let (start, end) = (a.start.max(b.start), a.end.min(b.end));
+            if start < end {
+                ranges.push(start..end);
+            }
+            // the range ending first cannot overlap anything else:
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    /// The values of this set that are not in the other one
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            // skip the ranges of the other set ending before this one:
+            while other.ranges.get(j).is_some_and(|b| b.end <= start) {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(b) = other.ranges.get(k).filter(|b| b.start < range.end) {
+                if start < b.start {
+                    ranges.push(start..b.start);
+                }
+                start = start.max(b.end);
+                k += 1;
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl<T: Copy + Ord + Sub<Output = T> + Sum> IntervalSet<T> {
+    /// The number of values in the set, for integer types
+    pub fn total_len(&self) -> T {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+}
@@ -3,10 +3,65 @@
 /// all references to individual puzzles are auto-generated:
 pub mod generated;
 
+/// library modules shared by several puzzles:
+pub mod bitmap;
+pub mod convex_cost;
+pub mod expr;
+pub mod grammar;
+pub mod graph;
+pub mod interval_set;
+pub mod line;
+pub mod linear_recurrence;
+pub mod modint;
+pub mod number_theory;
+pub mod partial_order;
+pub mod pattern;
+pub mod peg;
+pub mod testing;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+
+/// Puzzle parameters given on the command line as key=value,
+/// passed to puzzles that define a `run_with_params` function
+pub type Params = HashMap<String, String>;
+
+/// Stream the file given with the `file=path` parameter through a buffered reader,
+/// return None without that parameter. Errors opening or reading the file are returned as text.
+/// ```
+/// use advent_of_code_202x::{process_file_param, Params};
+/// use std::io::BufRead;
+/// let count_lines = |reader: std::io::BufReader<std::fs::File>| {
+///     Ok(reader.lines().count().to_string())
+/// };
+/// assert_eq!(process_file_param(&Params::new(), count_lines), None);
+/// let path = std::env::temp_dir().join("process_file_param_example");
+/// let params = [("file".to_owned(), path.to_str().unwrap().to_owned())].into_iter().collect();
+/// std::fs::write(&path, "1\n2\n").unwrap();
+/// assert_eq!(process_file_param(&params, count_lines), Some("2".to_owned()));
+/// std::fs::remove_file(&path).unwrap();
+/// let error = process_file_param(&params, count_lines).unwrap();
+/// assert!(error.starts_with(&format!("{}: ", path.display())));
+/// ```
+pub fn process_file_param(
+    params: &Params,
+    process_reader: impl FnOnce(BufReader<File>) -> io::Result<String>,
+) -> Option<String> {
+    let path = params.get("file")?;
+    let result = File::open(path).and_then(|file| process_reader(BufReader::new(file)));
+    Some(result.unwrap_or_else(|e| format!("{}: {}", path, e)))
+}
+
 /// Two arguments possible: day year
 /// both optional, select the latest one (year or day) by default
 /// (on command line, the year can only be specified if day is present too)
-pub fn run_puzzles(year_arg: Option<&String>, day_arg: Option<&String>, use_example: bool) {
+pub fn run_puzzles(
+    year_arg: Option<&String>,
+    day_arg: Option<&String>,
+    use_example: bool,
+    params: &Params,
+) {
     let year: usize = match year_arg {
         Some(year) => year.parse().expect("integer year expected"),
         None => *generated::get_years().last().unwrap(), // latest year
@@ -19,12 +74,18 @@ pub fn run_puzzles(year_arg: Option<&String>, day_arg: Option<&String>, use_exam
             // find last element in array that is not None (i.e.: first Some)
             days.iter()
                 .enumerate()
-                .filter(|(_, d)| d.is_some())
-                .last()
+                .rfind(|(_, d)| d.is_some())
                 .unwrap()
                 .0
         }
     };
     let aoc = days[day].as_ref().unwrap();
-    println!("{}", (if use_example { aoc.example } else { aoc.run })());
+    if params.is_empty() {
+        println!("{}", (if use_example { aoc.example } else { aoc.run })());
+    } else {
+        let with_params = aoc
+            .with_params
+            .expect("this puzzle does not take parameters");
+        println!("{}", with_params(params, use_example));
+    }
 }
@@ -0,0 +1,228 @@
+//! Line segments between integer points, with any slope and signed coordinates.
+//! A segment covers the lattice points on it: with dx, dy the differences between the end points,
+//! these are gcd(dx, dy) + 1 points, a step of (dx, dy) / gcd apart.
+//!
+//! Counting the points covered by several segments sweeps over the x columns, instead of
+//! hashing every point: each sloped segment has at most one point per column, a vertical one an interval,
+//! so the overlaps in a column are found by sorting these y values and intervals.
+//! Horizontal segments are active rows while the sweep is between their end points,
+//! so columns with only horizontal segments are skipped.
+//! Horizontal and vertical segments cost time and memory by their number, not their length,
+//! but a sloped segment is still visited at each of its lattice points.
+//!
+//! ```
+//! use advent_of_code_202x::line::{count_overlaps, Segment};
+//! let segment = Segment::new((-2, 7), (4, -2));
+//! assert_eq!(segment.lattice_points().collect::<Vec<_>>(), [(-2, 7), (0, 4), (2, 1), (4, -2)]);
+//! assert_eq!(Segment::new((1, 1), (1, 1)).lattice_points().count(), 1);
+//! // overlaps at (0, 4), (1, 4), and along (1, 0) to (1, 1):
+//! let segments = [segment, Segment::new((-4, 4), (4, 4)), Segment::new((1, -3), (1, 1)), Segment::new((1, 0), (1, 5))];
+//! assert_eq!(count_overlaps(&segments), 4);
+//! // long horizontal and vertical segments are handled without visiting their points:
+//! let segments = [Segment::new((-1 << 40, 0), (1 << 40, 0)), Segment::new((0, -1 << 40), (0, 1 << 40))];
+//! assert_eq!(count_overlaps(&segments), 1);
+//! ```
+//!
+//! Compare with counting all points in a hashmap:
+//! ```
+//! use advent_of_code_202x::line::{count_overlaps, Segment};
+//! use advent_of_code_202x::testing::XorShift;
+//! use std::collections::HashMap;
+//! let mut rng = XorShift::default();
+//! let mut random = move || rng.range(-20, 20);
+//! for _ in 0..200 {
+//!     let mut segments: Vec<Segment> = (0..10)
+//!         .map(|_| Segment::new((random(), random()), (random(), random())))
+//!         .collect();
+//!     // and more straight lines:
+//!     for _ in 0..5 {
+//!         let (x, y, a, b) = (random(), random(), random(), random());
+//!         segments.extend([Segment::new((x, y), (a, y)), Segment::new((x, y), (x, b))]);
+//!     }
+//!     let mut counts = HashMap::new();
+//!     for point in segments.iter().flat_map(|segment| segment.lattice_points()) {
+//!         *counts.entry(point).or_insert(0) += 1;
+//!     }
+//!     let expected = counts.values().filter(|&&count| count > 1).count();
+//!     assert_eq!(count_overlaps(&segments), expected, "{:?}", segments);
+//! }
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::ops::RangeInclusive;
+
+use num::integer::gcd;
+
+pub type Point = (i64, i64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Segment {
+    pub fn new(start: Point, end: Point) -> Self {
+        Segment { start, end }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.start.1 == self.end.1
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.start.0 == self.end.0
+    }
+
+    /// Return the step between neighbouring lattice points and the number of steps
+    fn step(&self) -> (Point, i64) {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let steps = gcd(dx, dy);
+        if steps == 0 {
+            ((0, 0), 0)
+        } else {
+            ((dx / steps, dy / steps), steps)
+        }
+    }
+
+    /// Iterate over the integer points on the segment, from start to end
+    pub fn lattice_points(&self) -> impl Iterator<Item = Point> {
+        let ((step_x, step_y), steps) = self.step();
+        let start = self.start;
+        (0..=steps).map(move |i| (start.0 + i * step_x, start.1 + i * step_y))
+    }
+}
+
+/// Active horizontal segments during the sweep: how many cover each row,
+/// and how many rows are covered at least twice
+#[derive(Debug, Default)]
+struct ActiveRows {
+    counts: BTreeMap<i64, usize>,
+    double_rows: usize,
+}
+
+impl ActiveRows {
+    fn add(&mut self, y: i64) {
+        let count = self.counts.entry(y).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            self.double_rows += 1;
+        }
+    }
+
+    fn remove(&mut self, y: i64) {
+        let count = self.counts.get_mut(&y).unwrap();
+        *count -= 1;
+        if *count == 1 {
+            self.double_rows -= 1;
+        } else if *count == 0 {
+            self.counts.remove(&y);
+        }
+    }
+
+    /// count the rows in low..high with a count that is in the given range
+    fn count_rows(&self, low: i64, high: i64, counts: RangeInclusive<usize>) -> usize {
+        self.counts
+            .range(low..high)
+            .filter(|(_, count)| counts.contains(count))
+            .count()
+    }
+}
+
+/// Return how many integer points are covered by more than one segment
+pub fn count_overlaps(segments: &[Segment]) -> usize {
+    // vertical segments as y intervals per column:
+    let mut vertical: BTreeMap<i64, Vec<(i64, i64)>> = BTreeMap::new();
+    // horizontal segments start and end (one after the last point) in a column, as (x, y, start?):
+    let mut horizontal = Vec::new();
+    // other segments, oriented left to right: (x of the next point, segment index, y of that point)
+    let mut next_points = BinaryHeap::new();
+    let mut steps = Vec::new();
+    for segment in segments {
+        let (start, end) = if segment.start.0 < segment.end.0 {
+            (segment.start, segment.end)
+        } else {
+            (segment.end, segment.start)
+        };
+        if segment.is_vertical() {
+            let (y1, y2) = (segment.start.1, segment.end.1);
+            vertical
+                .entry(start.0)
+                .or_default()
+                .push((y1.min(y2), y1.max(y2)));
+        } else if segment.is_horizontal() {
+            horizontal.push((start.0, start.1, true));
+            horizontal.push((end.0 + 1, start.1, false));
+        } else {
+            let (step, _) = Segment::new(start, end).step();
+            next_points.push(Reverse((start.0, steps.len(), start.1)));
+            steps.push((step, end.0));
+        }
+    }
+    horizontal.sort_unstable_by_key(|&(x, _, _)| Reverse(x));
+    let mut rows = ActiveRows::default();
+    let mut overlaps = 0;
+    let mut last_x = None;
+    loop {
+        // next column with any change:
+        let x = [
+            next_points.peek().map(|Reverse((x, _, _))| *x),
+            vertical.keys().next().copied(),
+            horizontal.last().map(|&(x, _, _)| x),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let Some(x) = x else {
+            break;
+        };
+        // in the columns since the last one, only the horizontal segments overlap:
+        if let Some(last_x) = last_x {
+            overlaps += rows.double_rows * (x - last_x - 1) as usize;
+        }
+        last_x = Some(x);
+        while horizontal.last().is_some_and(|&(hx, _, _)| hx == x) {
+            match horizontal.pop().unwrap() {
+                (_, y, true) => rows.add(y),
+                (_, y, false) => rows.remove(y),
+            }
+        }
+        // the other segments in this column, as coverage changes (y, +1 or -1):
+        let mut events = Vec::new();
+        while let Some(&Reverse((next_x, index, y))) = next_points.peek() {
+            if next_x != x {
+                break;
+            }
+            next_points.pop();
+            events.extend([(y, 1), (y + 1, -1)]);
+            let ((step_x, step_y), end_x) = steps[index];
+            if x + step_x <= end_x {
+                next_points.push(Reverse((x + step_x, index, y + step_y)));
+            }
+        }
+        if vertical.keys().next() == Some(&x) {
+            let intervals = vertical.remove(&x).unwrap();
+            events.extend(
+                intervals
+                    .into_iter()
+                    .flat_map(|(low, high)| [(low, 1), (high + 1, -1)]),
+            );
+        }
+        events.sort_unstable();
+        // rows covered twice by horizontal segments, plus the points where the other segments add enough:
+        overlaps += rows.double_rows;
+        let mut coverage = 0;
+        let mut last_y = i64::MIN;
+        for (y, change) in events {
+            match coverage {
+                0 => {}
+                1 => overlaps += rows.count_rows(last_y, y, 1..=1),
+                _ => overlaps += (y - last_y) as usize - rows.count_rows(last_y, y, 2..=usize::MAX),
+            }
+            coverage += change;
+            last_y = y;
+        }
+    }
+    overlaps
+}
@@ -0,0 +1,302 @@
+//! Linear recurrences on counts of states: in every step, each unit of a state turns into
+//! a fixed number of units of other states (e.g. fish with a timer, or pairs of a polymer).
+//! The rules form a transition matrix, and the counts after N steps are found
+//! with exponentiation by squaring in O(states^3 * log N), either with checked u128
+//! arithmetic (reporting overflows) or modulo some number.
+//!
+//! ```
+//! use advent_of_code_202x::linear_recurrence::{Arithmetic, CountError, LinearRecurrence};
+//! // fibonacci: young rabbit pairs grow up, grown-up pairs stay and produce a young pair
+//! let mut rabbits = LinearRecurrence::new();
+//! rabbits.add_transition("young", "adult", 1);
+//! rabbits.add_transition("adult", "adult", 1);
+//! rabbits.add_transition("adult", "young", 1);
+//! let counts = rabbits.count_after([("young", 1)], 10, Arithmetic::Checked).unwrap();
+//! assert_eq!((counts["young"], counts["adult"]), (34, 55));
+//! let counts = rabbits.count_after([("young", 1)], 186, Arithmetic::Checked).unwrap();
+//! assert_eq!(counts["adult"], 332825110087067562321196029789634457848);
+//! assert_eq!(rabbits.count_after([("young", 1)], 187, Arithmetic::Checked), Err(CountError::Overflow));
+//! assert_eq!(rabbits.count_after([("old", 1)], 1, Arithmetic::Checked), Err(CountError::UnknownState("old")));
+//! // F(10^18) mod 10^9 + 7
+//! let counts = rabbits.count_after([("young", 1)], 10u64.pow(18), Arithmetic::Modulo(1_000_000_007)).unwrap();
+//! assert_eq!(counts["adult"], 209783453);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// How to add and multiply counts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Arithmetic {
+    /// u128, with an error on overflow
+    Checked,
+    /// modulo the given number, which must not be 0
+    Modulo(u64),
+}
+
+/// A count did not fit into u128
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "count does not fit into u128")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+/// Counting the states after some steps failed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CountError<K> {
+    Overflow,
+    /// an initial state that is not part of any transition
+    UnknownState(K),
+}
+
+impl<K> From<Overflow> for CountError<K> {
+    fn from(_: Overflow) -> Self {
+        CountError::Overflow
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for CountError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CountError::Overflow => write!(f, "{}", Overflow),
+            CountError::UnknownState(key) => write!(f, "unknown initial state {:?}", key),
+        }
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for CountError<K> {}
+
+impl Arithmetic {
+    pub fn reduce(self, a: u128) -> u128 {
+        match self {
+            Self::Checked => a,
+            Self::Modulo(modulus) => a % modulus as u128,
+        }
+    }
+
+    /// operands need to be reduced already, so they are below 2^64 for modular arithmetic
+    pub fn add(self, a: u128, b: u128) -> Result<u128, Overflow> {
+        match self {
+            Self::Checked => a.checked_add(b).ok_or(Overflow),
+            Self::Modulo(_) => Ok(self.reduce(a + b)),
+        }
+    }
+
+    pub fn mul(self, a: u128, b: u128) -> Result<u128, Overflow> {
+        match self {
+            Self::Checked => a.checked_mul(b).ok_or(Overflow),
+            Self::Modulo(_) => Ok(self.reduce(a * b)),
+        }
+    }
+}
+
+/// Square matrix of counts, entry (to, from) is how many units of `to`
+/// one unit of `from` turns into in a single step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    size: usize,
+    entries: Vec<u128>,
+}
+
+impl Matrix {
+    pub fn zero(size: usize) -> Self {
+        Self {
+            size,
+            entries: vec![0; size * size],
+        }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut identity = Self::zero(size);
+        for i in 0..size {
+            identity.entries[i * size + i] = 1;
+        }
+        identity
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, to: usize, from: usize) -> u128 {
+        self.entries[to * self.size + from]
+    }
+
+    pub fn set(&mut self, to: usize, from: usize, value: u128) {
+        self.entries[to * self.size + from] = value;
+    }
+
+    /// Matrix product: first apply `other`, then `self`
+    pub fn mul(&self, other: &Matrix, arithmetic: Arithmetic) -> Result<Matrix, Overflow> {
+        assert_eq!(self.size, other.size);
+        let size = self.size;
+        let mut product = Matrix::zero(size);
+        for i in 0..size {
+            for k in 0..size {
+                let a = self.entries[i * size + k];
+                // transition matrices are sparse, skip whole rows of work:
+                if a == 0 {
+                    continue;
+                }
+                for j in 0..size {
+                    let b = other.entries[k * size + j];
+                    if b != 0 {
+                        let entry = &mut product.entries[i * size + j];
+                        *entry = arithmetic.add(*entry, arithmetic.mul(a, b)?)?;
+                    }
+                }
+            }
+        }
+        Ok(product)
+    }
+
+    /// Apply the matrix to a vector of counts
+    pub fn apply(&self, counts: &[u128], arithmetic: Arithmetic) -> Result<Vec<u128>, Overflow> {
+        assert_eq!(self.size, counts.len());
+        let mut result = vec![0; self.size];
+        for (to, entry) in result.iter_mut().enumerate() {
+            for (from, &count) in counts.iter().enumerate() {
+                let factor = self.get(to, from);
+                if factor != 0 && count != 0 {
+                    *entry = arithmetic.add(*entry, arithmetic.mul(factor, count)?)?;
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Raise to the power `exp` by repeated squaring
+    pub fn pow(&self, exp: u64, arithmetic: Arithmetic) -> Result<Matrix, Overflow> {
+        let mut result = Matrix::identity(self.size);
+        self.for_each_power_of_two(exp, arithmetic, |square| {
+            result = square.mul(&result, arithmetic)?;
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// Apply the matrix `exp` times to a vector of counts. Only the squares of the matrix
+    /// are calculated, not its power, so this overflows only if the squares or the counts do.
+    pub fn apply_pow(
+        &self,
+        counts: &[u128],
+        exp: u64,
+        arithmetic: Arithmetic,
+    ) -> Result<Vec<u128>, Overflow> {
+        let mut result: Vec<u128> = counts.iter().map(|&c| arithmetic.reduce(c)).collect();
+        self.for_each_power_of_two(exp, arithmetic, |square| {
+            result = square.apply(&result, arithmetic)?;
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// call f with self ^ (2 ^ k) for every bit k that is set in exp
+    fn for_each_power_of_two(
+        &self,
+        mut exp: u64,
+        arithmetic: Arithmetic,
+        mut f: impl FnMut(&Matrix) -> Result<(), Overflow>,
+    ) -> Result<(), Overflow> {
+        let mut square = self.clone();
+        for entry in square.entries.iter_mut() {
+            *entry = arithmetic.reduce(*entry);
+        }
+        while exp > 0 {
+            if exp & 1 == 1 {
+                f(&square)?;
+            }
+            exp >>= 1;
+            // the last squaring is not needed, and could overflow needlessly:
+            if exp > 0 {
+                square = square.mul(&square, arithmetic)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rules for a linear recurrence on states named by keys of type K.
+/// States without outgoing transitions die out after one step.
+#[derive(Debug, Clone)]
+pub struct LinearRecurrence<K> {
+    keys: Vec<K>,
+    ids: HashMap<K, usize>,
+    matrix: Matrix,
+}
+
+impl<K: Clone + Eq + Hash> Default for LinearRecurrence<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone + Eq + Hash> LinearRecurrence<K> {
+    pub fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            ids: HashMap::new(),
+            matrix: Matrix::zero(0),
+        }
+    }
+
+    /// Return the index of a state in the matrix, adding the state if it is new
+    pub fn state(&mut self, key: K) -> usize {
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+        let id = self.keys.len();
+        self.keys.push(key.clone());
+        self.ids.insert(key, id);
+        // grow the matrix by one row and column:
+        let mut matrix = Matrix::zero(id + 1);
+        for to in 0..id {
+            for from in 0..id {
+                matrix.set(to, from, self.matrix.get(to, from));
+            }
+        }
+        self.matrix = matrix;
+        id
+    }
+
+    /// In every step, each unit of `from` adds `count` units to `to`
+    pub fn add_transition(&mut self, from: K, to: K, count: u128) {
+        let from = self.state(from);
+        let to = self.state(to);
+        let current = self.matrix.get(to, from);
+        self.matrix.set(to, from, current + count);
+    }
+
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    /// Return the counts of all states after `steps` steps, starting with the given counts.
+    /// Initial states need to be part of a transition.
+    pub fn count_after(
+        &self,
+        initial: impl IntoIterator<Item = (K, u128)>,
+        steps: u64,
+        arithmetic: Arithmetic,
+    ) -> Result<HashMap<K, u128>, CountError<K>> {
+        let mut counts = vec![0; self.keys.len()];
+        for (key, count) in initial {
+            let Some(&id) = self.ids.get(&key) else {
+                return Err(CountError::UnknownState(key));
+            };
+            counts[id] = arithmetic.add(counts[id], arithmetic.reduce(count))?;
+        }
+        let counts = self.matrix.apply_pow(&counts, steps, arithmetic)?;
+        Ok(self.keys.iter().cloned().zip(counts).collect())
+    }
+}
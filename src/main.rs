@@ -1,17 +1,26 @@
 #![warn(clippy::all)]
 
-use advent_of_code_202x::run_puzzles;
+use advent_of_code_202x::{run_puzzles, Params};
 
 /// Two arguments possible: day year
 /// both optional, select the latest one (year or day) by default
 /// (year can only be specified if day is present too)
+/// any number of key=value arguments are passed to the puzzle as parameters
 pub fn main() {
-    let mut args: Vec<String> = std::env::args().collect();
+    let (params, mut args): (Vec<String>, Vec<String>) =
+        std::env::args().partition(|arg| arg.contains('='));
+    let params: Params = params
+        .iter()
+        .map(|param| {
+            let (key, value) = param.split_once('=').unwrap();
+            (key.to_owned(), value.to_owned())
+        })
+        .collect();
     let use_example = if let Some(pos) = args.iter().position(|x| *x == "x") {
         args.remove(pos);
         true
     } else {
         false
     };
-    run_puzzles(args.get(2), args.get(1), use_example);
+    run_puzzles(args.get(2), args.get(1), use_example, &params);
 }
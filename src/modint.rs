@@ -0,0 +1,189 @@
+//! Integers modulo a compile-time constant `M`, stored in any unsigned type wide enough for `M - 1`.
+//! Arithmetic goes through u128, so there is no overflow even for moduli close to `u64::MAX`.
+//! Besides the usual operators there is counting of how often a value steps onto zero,
+//! for dial or ring puzzles that move in big steps.
+//!
+//! ```
+//! use advent_of_code_202x::modint::ModInt;
+//! type Dial = ModInt<u8, 100>;
+//! let dial = Dial::new(50);
+//! assert_eq!(dial + Dial::new(60), Dial::new(10));
+//! assert_eq!(dial - Dial::new(68), Dial::from_i64(-18));
+//! assert_eq!((-dial).value(), 50);
+//! assert_eq!(Dial::new(7) * Dial::new(30), Dial::new(10));
+//! assert_eq!("-1".parse::<Dial>().unwrap().to_string(), "99");
+//! // stepping 1000 clicks to the right from 50 passes zero ten times,
+//! // and moving left by 50 lands on zero once:
+//! assert_eq!(dial.add_counting_zeros(1000), (Dial::new(50), 10));
+//! assert_eq!(dial.sub_counting_zeros(50), (Dial::new(0), 1));
+//!
+//! type Big = ModInt<u64, 18446744069414584321>; // prime close to u64::MAX
+//! let x = Big::new(u64::MAX);
+//! assert_eq!(x * x.inverse().unwrap(), Big::new(1));
+//! assert_eq!(Big::new(7).pow(18446744069414584320), Big::new(1));
+//! assert_eq!(ModInt::<u8, 4>::new(2).inverse(), None);
+//! ```
+//!
+//! The modulus has to fit the storage type:
+//! ```compile_fail
+//! use advent_of_code_202x::modint::ModInt;
+//! let too_large = ModInt::<u8, 300>::new(1);
+//! ```
+
+use std::fmt;
+use std::hash::Hash;
+use std::num::ParseIntError;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::number_theory::{mod_inverse, pow_mod};
+
+/// Unsigned integer types that can store the value of a `ModInt`
+pub trait Unsigned: Copy + Eq + Ord + Hash + fmt::Debug + fmt::Display {
+    const MAX: u64;
+    fn to_u64(self) -> u64;
+    /// the value needs to fit, this truncates
+    fn from_u64(value: u64) -> Self;
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Unsigned for $t {
+                const MAX: u64 = <$t>::MAX as u64;
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+                fn from_u64(value: u64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64);
+
+/// An integer modulo M, always kept in the range 0..M
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModInt<T: Unsigned, const M: u64> {
+    value: T,
+}
+
+impl<T: Unsigned, const M: u64> ModInt<T, M> {
+    /// checked at compile time when a ModInt is created
+    const VALID_MODULUS: () = assert!(M > 0 && M - 1 <= T::MAX, "M - 1 needs to fit into T");
+
+    pub fn new(value: u64) -> Self {
+        let () = Self::VALID_MODULUS;
+        Self {
+            value: T::from_u64(value % M),
+        }
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        Self::new((value as i128).rem_euclid(M as i128) as u64)
+    }
+
+    pub fn value(self) -> T {
+        self.value
+    }
+
+    pub fn modulus() -> u64 {
+        M
+    }
+
+    pub fn pow(self, exp: u64) -> Self {
+        Self::new(pow_mod(self.value.to_u64(), exp, M))
+    }
+
+    /// Return the multiplicative inverse, if the value is coprime to M (always, for prime M and non-zero value)
+    pub fn inverse(self) -> Option<Self> {
+        mod_inverse(self.value.to_u64(), M).map(Self::new)
+    }
+
+    /// Add `steps` times one, return the result and how often the value was zero after a single step
+    pub fn add_counting_zeros(self, steps: u64) -> (Self, u64) {
+        let total = self.value.to_u64() as u128 + steps as u128;
+        (self + Self::new(steps), (total / M as u128) as u64)
+    }
+
+    /// Subtract `steps` times one, return the result and how often the value was zero after a single step
+    pub fn sub_counting_zeros(self, steps: u64) -> (Self, u64) {
+        let value = self.value.to_u64();
+        let zeros = match value {
+            0 => steps / M,
+            _ if steps >= value => 1 + (steps - value) / M,
+            _ => 0,
+        };
+        (self - Self::new(steps), zeros)
+    }
+}
+
+impl<T: Unsigned, const M: u64> Add for ModInt<T, M> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let sum = self.value.to_u64() as u128 + other.value.to_u64() as u128;
+        Self::new((sum % M as u128) as u64)
+    }
+}
+
+impl<T: Unsigned, const M: u64> Neg for ModInt<T, M> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(M - self.value.to_u64())
+    }
+}
+
+impl<T: Unsigned, const M: u64> Sub for ModInt<T, M> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + (-other)
+    }
+}
+
+impl<T: Unsigned, const M: u64> Mul for ModInt<T, M> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let product = self.value.to_u64() as u128 * other.value.to_u64() as u128;
+        Self::new((product % M as u128) as u64)
+    }
+}
+
+impl<T: Unsigned, const M: u64> AddAssign for ModInt<T, M> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Unsigned, const M: u64> SubAssign for ModInt<T, M> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Unsigned, const M: u64> MulAssign for ModInt<T, M> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<T: Unsigned, const M: u64> fmt::Display for ModInt<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+/// Parse any (possibly negative) integer and reduce it modulo M
+impl<T: Unsigned, const M: u64> FromStr for ModInt<T, M> {
+    type Err = ParseIntError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let value: i128 = input.trim().parse()?;
+        Ok(Self::new(value.rem_euclid(M as i128) as u64))
+    }
+}
@@ -0,0 +1,261 @@
+//! Modular arithmetic on `u64` moduli: multiplication without overflow (via u128),
+//! fast exponentiation, inverses with the extended euclidean algorithm,
+//! the chinese remainder theorem, factorization and discrete logarithms
+//! (baby-step giant-step, combined with Pohlig-Hellman for smooth group orders).
+//!
+//! ```
+//! use advent_of_code_202x::number_theory::{crt, mod_inverse, pow_mod};
+//! assert_eq!(pow_mod(7, 8, 20201227), 5764801);
+//! assert_eq!(mod_inverse(3, 7), Some(5));
+//! assert_eq!(mod_inverse(2, 4), None);
+//! // 2020 day 13 example, bus 7 at t, bus 13 at t + 1, bus 59 at t + 4, ...:
+//! let buses = [(7, 0), (13, 1), (59, 4), (31, 6), (19, 7)];
+//! let congruences: Vec<_> = buses.iter().map(|&(bus, offset)| ((bus - offset % bus) % bus, bus)).collect();
+//! assert_eq!(crt(&congruences), Some((1068781, 7 * 13 * 59 * 31 * 19)));
+//! // moduli do not need to be coprime, but then the congruences can contradict each other:
+//! assert_eq!(crt(&[(2, 6), (5, 9)]), Some((14, 18)));
+//! assert_eq!(crt(&[(1, 6), (2, 4)]), None);
+//! ```
+//!
+//! ```
+//! use advent_of_code_202x::number_theory::{discrete_log, factorize, pow_mod};
+//! assert_eq!(factorize(20201226), vec![(2, 1), (3, 1), (29, 1), (116099, 1)]);
+//! // the two largest 32 bit primes, so Pollard's rho works close to u64::MAX:
+//! assert_eq!(factorize(18446743979220271189), vec![(4294967279, 1), (4294967291, 1)]);
+//! assert_eq!(discrete_log(7, 5764801, 20201227), Some(8));
+//! // 64 bit prime 2^64 - 2^32 + 1, with a smooth group order:
+//! let prime = 18446744069414584321;
+//! let exponent = 1234567890123456789;
+//! assert_eq!(discrete_log(7, pow_mod(7, exponent, prime), prime), Some(exponent));
+//! // 2 only generates the quadratic residues modulo 7 (2, 4, 1):
+//! assert_eq!(discrete_log(2, 4, 7), Some(2));
+//! assert_eq!(discrete_log(2, 3, 7), None);
+//! ```
+
+use std::collections::HashMap;
+
+/// (a * b) % modulus, without overflow
+pub fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+/// base ^ exp % modulus, by repeated squaring
+pub fn pow_mod(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut square = base % modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, square, modulus);
+        }
+        square = mul_mod(square, square, modulus);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Return (gcd, x, y) with a * x + b * y = gcd
+pub fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    (old_r, old_x, old_y)
+}
+
+/// Return the inverse of a modulo modulus, if a and modulus are coprime
+pub fn mod_inverse(a: u64, modulus: u64) -> Option<u64> {
+    let (gcd, x, _) = ext_gcd((a % modulus) as i128, modulus as i128);
+    (gcd == 1).then(|| x.rem_euclid(modulus as i128) as u64)
+}
+
+/// Chinese remainder theorem: solve x = residue (mod modulus) for all given (residue, modulus),
+/// return the smallest such x with the lcm of the moduli,
+/// or None if the congruences contradict each other or the lcm does not fit into u64
+pub fn crt(congruences: &[(u64, u64)]) -> Option<(u64, u64)> {
+    congruences
+        .iter()
+        .try_fold((0, 1), |(x, lcm), &(residue, modulus)| {
+            crt_pair(x, lcm, residue % modulus, modulus)
+        })
+}
+
+/// combine x = a1 (mod m1) and x = a2 (mod m2)
+fn crt_pair(a1: u64, m1: u64, a2: u64, m2: u64) -> Option<(u64, u64)> {
+    let (gcd, _, _) = ext_gcd(m1 as i128, m2 as i128);
+    let gcd = gcd as u64;
+    let diff = (a2 as i128 - a1 as i128).rem_euclid(m2 as i128) as u64;
+    if !diff.is_multiple_of(gcd) {
+        return None;
+    }
+    let m2_reduced = m2 / gcd;
+    let lcm = m1.checked_mul(m2_reduced)?;
+    // a1 + m1 * t with m1 * t = diff (mod m2), i.e. t = diff/gcd * (m1/gcd)^-1 (mod m2/gcd)
+    // m1/gcd and m2/gcd are coprime, so the inverse always exists:
+    let inverse = mod_inverse((m1 / gcd) % m2_reduced, m2_reduced).unwrap();
+    let t = mul_mod((diff / gcd) % m2_reduced, inverse, m2_reduced);
+    let x = (a1 as u128 + m1 as u128 * t as u128) % lcm as u128;
+    Some((x as u64, lcm))
+}
+
+/// Deterministic Miller-Rabin test (these bases suffice for all u64)
+pub fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for &p in &BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d % 2 == 0 {
+        d /= 2;
+        s += 1;
+    }
+    BASES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Find a non-trivial divisor of the composite (odd) n with Pollard's rho (Floyd cycle detection)
+fn pollard_rho(n: u64) -> u64 {
+    for c in 1.. {
+        // in u128, as x * x % n + c can overflow for n close to u64::MAX:
+        let step = |x: u64| ((mul_mod(x, x, n) as u128 + c as u128) % n as u128) as u64;
+        let (mut slow, mut fast, mut divisor) = (2, 2, 1);
+        while divisor == 1 {
+            slow = step(slow);
+            fast = step(step(fast));
+            divisor = ext_gcd(slow.abs_diff(fast) as i128, n as i128).0 as u64;
+        }
+        if divisor != n {
+            return divisor;
+        }
+    }
+    unreachable!()
+}
+
+/// Return the prime factorization of n as sorted (prime, exponent) pairs
+pub fn factorize(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    let mut n = n;
+    // trial division for small factors, then split the rest with Pollard's rho:
+    for p in 2..1000 {
+        while n.is_multiple_of(p) {
+            primes.push(p);
+            n /= p;
+        }
+    }
+    let mut to_split = vec![n];
+    while let Some(m) = to_split.pop() {
+        if m == 1 {
+            continue;
+        }
+        if is_prime(m) {
+            primes.push(m);
+        } else {
+            let divisor = pollard_rho(m);
+            to_split.push(divisor);
+            to_split.push(m / divisor);
+        }
+    }
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((last, exponent)) if *last == p => *exponent += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
+
+/// Baby-step giant-step: return the smallest x < order with base ^ x = target (mod modulus),
+/// `order` only needs to be an upper bound for the order of base.
+/// Takes O(sqrt(order)) time and memory; base needs to be invertible modulo modulus.
+pub fn baby_step_giant_step(base: u64, target: u64, modulus: u64, order: u64) -> Option<u64> {
+    let steps = (order as f64).sqrt().ceil() as u64 + 1;
+    let mut baby_steps = HashMap::new();
+    let mut value = 1 % modulus;
+    for j in 0..steps {
+        baby_steps.entry(value).or_insert(j);
+        value = mul_mod(value, base, modulus);
+    }
+    let giant_step = pow_mod(mod_inverse(base, modulus)?, steps, modulus);
+    let mut gamma = target % modulus;
+    for i in 0..steps {
+        if let Some(j) = baby_steps.get(&gamma) {
+            // in u128, as steps * steps can be a little larger than u64::MAX:
+            let x = i as u128 * steps as u128 + *j as u128;
+            return (x < order as u128).then_some(x as u64);
+        }
+        gamma = mul_mod(gamma, giant_step, modulus);
+    }
+    None
+}
+
+/// Return the smallest x with base ^ x = target (mod modulus), if there is one.
+/// Base and modulus need to be coprime. Pohlig-Hellman reduces the search to
+/// baby-step giant-step in subgroups of prime order, so this is fast whenever the
+/// order of the multiplicative group (e.g. p - 1 for a prime modulus p) has no large prime factors.
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    if modulus == 1 {
+        return Some(0);
+    }
+    let base = base % modulus;
+    mod_inverse(base, modulus)?;
+    // the order of base divides the order of the group (euler's phi of the modulus):
+    let phi = factorize(modulus)
+        .iter()
+        .fold(1, |phi, &(p, e)| phi * (p - 1) * p.pow(e - 1));
+    let mut order = phi;
+    let mut order_factors = factorize(phi);
+    for (p, exponent) in order_factors.iter_mut() {
+        while *exponent > 0 && pow_mod(base, order / *p, modulus) == 1 {
+            order /= *p;
+            *exponent -= 1;
+        }
+    }
+    let mut congruences = Vec::new();
+    for &(p, exponent) in order_factors.iter().filter(|(_, e)| *e > 0) {
+        let prime_power = p.pow(exponent);
+        let sub_base = pow_mod(base, order / prime_power, modulus);
+        let sub_target = pow_mod(target, order / prime_power, modulus);
+        congruences.push((
+            log_prime_power(sub_base, sub_target, modulus, p, exponent)?,
+            prime_power,
+        ));
+    }
+    let (x, _) = crt(&congruences)?;
+    (pow_mod(base, x, modulus) == target % modulus).then_some(x)
+}
+
+/// discrete log for a base of order p ^ exponent, one base-p digit of the result at a time
+fn log_prime_power(base: u64, target: u64, modulus: u64, p: u64, exponent: u32) -> Option<u64> {
+    let generator = pow_mod(base, p.pow(exponent - 1), modulus); // has order p
+    let base_inverse = mod_inverse(base, modulus)?;
+    let mut x = 0;
+    for k in 0..exponent {
+        let reduced = mul_mod(pow_mod(base_inverse, x, modulus), target, modulus);
+        let digit_target = pow_mod(reduced, p.pow(exponent - 1 - k), modulus);
+        let digit = baby_step_giant_step(generator, digit_target, modulus, p)?;
+        x += digit * p.pow(k);
+    }
+    Some(x)
+}
@@ -0,0 +1,187 @@
+//! Partial orders given by rules "a before b", e.g. the page ordering rules of 2024 day 5.
+//! The rules need not be a total order, nor be acyclic as a whole: only the rules between
+//! the items of one list apply to it. Sorting uses Kahn's algorithm on that restricted subgraph,
+//! taking the earliest item of the list that is ready next, so the sort is stable.
+//! A cycle among the items is reported with the items on it.
+//!
+//! ```
+//! use advent_of_code_202x::partial_order::{Cycle, PartialOrder};
+//! let mut order = PartialOrder::new();
+//! order.add(1, 3);
+//! order.add(3, 4);
+//! order.add(4, 1);
+//! // 2 is not ordered with the others, and stays first:
+//! assert!(!order.is_sorted(&[3, 2, 1]));
+//! assert_eq!(order.sort(&[3, 2, 1]), Ok(vec![2, 1, 3]));
+//! assert!(order.is_sorted(&[2, 1, 3]));
+//! // the cycle only matters if all of its items are there:
+//! assert_eq!(order.sort(&[4, 3, 2, 1]), Err(Cycle(vec![4, 1, 3])));
+//! ```
+//!
+//! Compare with checking all permutations, for random rules:
+//! ```
+//! use advent_of_code_202x::partial_order::PartialOrder;
+//! use itertools::Itertools;
+//! use advent_of_code_202x::testing::XorShift;
+//! let mut rng = XorShift::default();
+//! for _ in 0..200 {
+//!     let mut order = PartialOrder::new();
+//!     let mut rules = Vec::new();
+//!     for _ in 0..rng.below(12) {
+//!         let (a, b) = (rng.below(8), rng.below(8));
+//!         order.add(a, b);
+//!         rules.push((a, b));
+//!     }
+//!     let items: Vec<u64> = (0..1 + rng.below(6)).map(|_| rng.below(8)).unique().collect();
+//!     let valid = |list: &[u64]| rules.iter().all(|&(a, b)| {
+//!         let (pos_a, pos_b) = (list.iter().position(|&x| x == a), list.iter().position(|&x| x == b));
+//!         pos_a.zip(pos_b).is_none_or(|(pos_a, pos_b)| pos_a < pos_b)
+//!     });
+//!     assert_eq!(order.is_sorted(&items), valid(&items));
+//!     let any_valid = items.iter().copied().permutations(items.len()).any(|list| valid(&list));
+//!     match order.sort(&items) {
+//!         Ok(sorted) => {
+//!             assert!(valid(&sorted));
+//!             assert_eq!(sorted.iter().sorted().collect_vec(), items.iter().sorted().collect_vec());
+//!         }
+//!         Err(cycle) => {
+//!             assert!(!any_valid);
+//!             for (a, b) in cycle.0.iter().circular_tuple_windows() {
+//!                 assert!(rules.contains(&(*a, *b)) && items.contains(a));
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+/// Items that need to be before each other in a circle, each one before the next,
+/// and the last one before the first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<T>(pub Vec<T>);
+
+impl<T: fmt::Display> fmt::Display for Cycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cycle")?;
+        for item in self.0.iter().chain(self.0.first()) {
+            write!(f, " {}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> std::error::Error for Cycle<T> {}
+
+/// Rules for which items come before which others
+#[derive(Debug, Clone)]
+pub struct PartialOrder<T> {
+    /// for each item, the items that need to come after it
+    after: HashMap<T, HashSet<T>>,
+}
+
+impl<T: Copy + Eq + Hash> Default for PartialOrder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Eq + Hash> PartialOrder<T> {
+    pub fn new() -> Self {
+        Self {
+            after: HashMap::new(),
+        }
+    }
+
+    /// Add the rule that `before` comes before `after`
+    pub fn add(&mut self, before: T, after: T) {
+        self.after.entry(before).or_default().insert(after);
+    }
+
+    /// Iterate over the items that need to come after `item`
+    pub fn successors(&self, item: T) -> impl Iterator<Item = T> + '_ {
+        self.after.get(&item).into_iter().flatten().copied()
+    }
+
+    /// Return true iff no item of the list has a rule to be before an earlier item
+    pub fn is_sorted(&self, items: &[T]) -> bool {
+        let mut seen = HashSet::new();
+        items.iter().all(|&item| {
+            seen.insert(item);
+            self.successors(item).all(|other| !seen.contains(&other))
+        })
+    }
+
+    /// Return the items sorted by the rules among them, keeping the given order where
+    /// there is no rule, or a cycle if the rules among them contradict each other
+    pub fn sort(&self, items: &[T]) -> Result<Vec<T>, Cycle<T>> {
+        let mut indices: HashMap<T, Vec<usize>> = HashMap::new();
+        for (index, &item) in items.iter().enumerate() {
+            indices.entry(item).or_default().push(index);
+        }
+        // edges of the rules restricted to the items, by index:
+        let mut edges = vec![Vec::new(); items.len()];
+        let mut in_degrees = vec![0; items.len()];
+        for (index, &item) in items.iter().enumerate() {
+            for other in self.successors(item) {
+                for &other_index in indices.get(&other).into_iter().flatten() {
+                    edges[index].push(other_index);
+                    in_degrees[other_index] += 1;
+                }
+            }
+        }
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..items.len())
+            .filter(|&index| in_degrees[index] == 0)
+            .map(Reverse)
+            .collect();
+        let mut sorted = Vec::with_capacity(items.len());
+        while let Some(Reverse(index)) = ready.pop() {
+            sorted.push(items[index]);
+            for &next in &edges[index] {
+                in_degrees[next] -= 1;
+                if in_degrees[next] == 0 {
+                    ready.push(Reverse(next));
+                }
+            }
+        }
+        if sorted.len() == items.len() {
+            Ok(sorted)
+        } else {
+            Err(Cycle(find_cycle(items, &edges, &in_degrees)))
+        }
+    }
+}
+
+/// After Kahn's algorithm got stuck, every item left has an edge from another item left.
+/// Walking these edges backwards has to run into a cycle.
+fn find_cycle<T: Copy>(items: &[T], edges: &[Vec<usize>], in_degrees: &[usize]) -> Vec<T> {
+    let mut predecessor = vec![None; items.len()];
+    for (index, targets) in edges.iter().enumerate() {
+        if in_degrees[index] > 0 {
+            for &target in targets {
+                predecessor[target] = Some(index);
+            }
+        }
+    }
+    let mut visited = vec![false; items.len()];
+    let mut index = (0..items.len()).find(|&i| in_degrees[i] > 0).unwrap();
+    while !visited[index] {
+        visited[index] = true;
+        index = predecessor[index].unwrap();
+    }
+    // index is on the cycle now, collect it backwards:
+    let mut cycle = vec![index];
+    let mut current = predecessor[index].unwrap();
+    while current != index {
+        cycle.push(current);
+        current = predecessor[current].unwrap();
+    }
+    cycle.reverse();
+    // start with the earliest item in the list, for a stable report:
+    let start = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+    cycle.rotate_left(start);
+    cycle.into_iter().map(|index| items[index]).collect()
+}
@@ -0,0 +1,383 @@
+//! Pattern matching in 2D grids, for word searches and for pictures in images.
+//!
+//! A `Template` is a set of cells with the values required there, all other cells are wildcards.
+//! It is matched in any of the 8 orientations of the square (4 rotations, each possibly mirrored);
+//! orientations that give the same template (for symmetric ones) are only searched once.
+//!
+//! Words are searched in all 8 compass directions instead, since diagonals are no symmetry of a row.
+//! A `WordSearch` builds an Aho-Corasick automaton of all words, and runs it once along each line
+//! of the grid in each direction, so the time does not grow with the number or length of the words.
+//!
+//! ```
+//! use advent_of_code_202x::pattern::{find_matches, Orientation, Template, WordSearch};
+//! use ndarray::Array2;
+//! let grid = Array2::from_shape_vec((3, 4), "CATSXTAXSACT".chars().collect()).unwrap();
+//! // C A T S
+//! // X T A X
+//! // S A C T
+//! let words = WordSearch::new(&["CAT", "TAX", "SAT"]);
+//! let found: Vec<_> = words.find(grid.view()).iter().map(|m| (m.word, m.start, m.direction)).collect();
+//! assert_eq!(found, [(0, (0, 0), (0, 1)), (1, (1, 1), (0, 1)), (0, (2, 2), (-1, 0))]);
+//! // a T with an A next to it, in any orientation (the mirrored ones are the same):
+//! let template = Template::from_rows("TA", '.');
+//! let matches = find_matches(grid.view(), &template, &Orientation::ALL);
+//! assert_eq!(matches.len(), 5);
+//! // turned clockwise, the A is under the T:
+//! assert!(matches.contains(&((0, 2), Orientation::new(1, false))));
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use ndarray::ArrayView2;
+
+/// Position in a grid as (row, column)
+pub type Position = (usize, usize);
+/// Step between positions as (rows, columns)
+pub type Offset = (isize, isize);
+
+/// the 8 compass directions, as row and column steps
+pub const DIRECTIONS: [Offset; 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// One of the 8 symmetries of a square: first mirrored left to right (or not),
+/// then turned clockwise by a number of quarter turns
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Orientation {
+    pub turns: u8,
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    pub const IDENTITY: Orientation = Orientation {
+        turns: 0,
+        mirrored: false,
+    };
+
+    pub const ALL: [Orientation; 8] = [
+        Orientation::new(0, false),
+        Orientation::new(1, false),
+        Orientation::new(2, false),
+        Orientation::new(3, false),
+        Orientation::new(0, true),
+        Orientation::new(1, true),
+        Orientation::new(2, true),
+        Orientation::new(3, true),
+    ];
+
+    pub const fn new(turns: u8, mirrored: bool) -> Self {
+        Orientation {
+            turns: turns % 4,
+            mirrored,
+        }
+    }
+
+    /// Move an offset with the symmetry (around the origin)
+    pub fn apply(self, (row, col): Offset) -> Offset {
+        let mut offset = if self.mirrored {
+            (row, -col)
+        } else {
+            (row, col)
+        };
+        for _ in 0..self.turns {
+            offset = (offset.1, -offset.0);
+        }
+        offset
+    }
+}
+
+/// Required values at some cells, relative to the top left corner of the template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template<T> {
+    cells: Vec<(Offset, T)>,
+}
+
+impl Template<char> {
+    /// Parse a template from lines of characters, where the wildcard character matches anything
+    /// (so do the cells to the right of short lines)
+    pub fn from_rows(rows: &str, wildcard: char) -> Self {
+        let cells = rows
+            .lines()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(move |&(_, c)| c != wildcard)
+                    .map(move |(col, c)| ((row as isize, col as isize), c))
+            })
+            .collect();
+        Template::new(cells)
+    }
+}
+
+impl<T: Clone + Ord> Template<T> {
+    /// Create a template from cells at any offsets, the top left corner is moved to (0, 0)
+    pub fn new(mut cells: Vec<(Offset, T)>) -> Self {
+        let min_row = cells.iter().map(|((row, _), _)| *row).min().unwrap_or(0);
+        let min_col = cells.iter().map(|((_, col), _)| *col).min().unwrap_or(0);
+        for ((row, col), _) in cells.iter_mut() {
+            *row -= min_row;
+            *col -= min_col;
+        }
+        cells.sort_unstable();
+        Template { cells }
+    }
+
+    pub fn cells(&self) -> &[(Offset, T)] {
+        &self.cells
+    }
+
+    /// Convert the values of the cells
+    pub fn map<U: Clone + Ord>(&self, f: impl Fn(&T) -> U) -> Template<U> {
+        Template::new(
+            self.cells
+                .iter()
+                .map(|(offset, value)| (*offset, f(value)))
+                .collect(),
+        )
+    }
+
+    /// Return the template in another orientation
+    pub fn oriented(&self, orientation: Orientation) -> Self {
+        Template::new(
+            self.cells
+                .iter()
+                .map(|(offset, value)| (orientation.apply(*offset), value.clone()))
+                .collect(),
+        )
+    }
+
+    /// Return true iff the template fits onto the grid with its top left corner at the position
+    pub fn matches_at(&self, grid: ArrayView2<T>, (row, col): Position) -> bool {
+        self.cells.iter().all(|((d_row, d_col), value)| {
+            grid.get((row + *d_row as usize, col + *d_col as usize)) == Some(value)
+        })
+    }
+
+    /// Return the positions of the grid that the template covers when placed at the position
+    pub fn positions_at(&self, (row, col): Position) -> impl Iterator<Item = Position> + '_ {
+        self.cells
+            .iter()
+            .map(move |((d_row, d_col), _)| (row + *d_row as usize, col + *d_col as usize))
+    }
+}
+
+/// Find the template in the grid in the given orientations. Returns the top left corner
+/// of each match (of the oriented template) with the orientation, ordered by orientation first.
+/// A symmetric template is only searched in the first of the orientations that give the same template.
+pub fn find_matches<T: Clone + Ord>(
+    grid: ArrayView2<T>,
+    template: &Template<T>,
+    orientations: &[Orientation],
+) -> Vec<(Position, Orientation)> {
+    let mut searched = Vec::new();
+    let mut matches = Vec::new();
+    for &orientation in orientations {
+        let oriented = template.oriented(orientation);
+        if searched.contains(&oriented) {
+            continue;
+        }
+        let height = oriented.cells.iter().map(|((row, _), _)| row + 1).max();
+        let width = oriented.cells.iter().map(|((_, col), _)| col + 1).max();
+        let (rows, cols) = grid.dim();
+        if let (Some(height), Some(width)) = (height, width) {
+            for row in 0..(rows + 1).saturating_sub(height as usize) {
+                for col in 0..(cols + 1).saturating_sub(width as usize) {
+                    if oriented.matches_at(grid, (row, col)) {
+                        matches.push(((row, col), orientation));
+                    }
+                }
+            }
+        }
+        searched.push(oriented);
+    }
+    matches
+}
+
+/// Return all positions covered by the matches of the template
+pub fn covered_positions<T: Clone + Ord>(
+    template: &Template<T>,
+    matches: &[(Position, Orientation)],
+) -> HashSet<Position> {
+    matches
+        .iter()
+        .flat_map(|&(position, orientation)| {
+            template
+                .oriented(orientation)
+                .positions_at(position)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// A word found in a grid: its index in the word list, where it starts and its direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordMatch {
+    pub word: usize,
+    pub start: Position,
+    pub direction: Offset,
+}
+
+/// Aho-Corasick automaton for a list of words, searched in the 8 directions of a grid.
+/// Palindromes are found twice, once in each direction.
+/// ```
+/// use advent_of_code_202x::pattern::WordSearch;
+/// use ndarray::Array2;
+/// use advent_of_code_202x::testing::XorShift;
+/// // compare with checking every start and direction, in random grids:
+/// let mut rng = XorShift::default();
+/// for _ in 0..100 {
+///     let letter = |r: u64| (b'A' + r as u8) as char;
+///     let (rows, cols) = (1 + rng.below(8) as usize, 1 + rng.below(8) as usize);
+///     let grid = Array2::from_shape_fn((rows, cols), |_| letter(rng.below(3)));
+///     let words: Vec<String> = (0..1 + rng.below(4)).map(|_| (0..1 + rng.below(4)).map(|_| letter(rng.below(3))).collect()).collect();
+///     let words: Vec<&str> = words.iter().map(String::as_str).collect();
+///     let found = WordSearch::new(&words).find(grid.view());
+///     let mut expected = Vec::new();
+///     for (word, text) in words.iter().enumerate() {
+///         for (row, col) in (0..rows).flat_map(|row| (0..cols).map(move |col| (row, col))) {
+///             for (d_row, d_col) in advent_of_code_202x::pattern::DIRECTIONS {
+///                 let fits = text.chars().enumerate().all(|(i, c)| {
+///                     let (r, c2) = (row as isize + i as isize * d_row, col as isize + i as isize * d_col);
+///                     r >= 0 && c2 >= 0 && grid.get((r as usize, c2 as usize)) == Some(&c)
+///                 });
+///                 // single letters are in every direction:
+///                 if fits && (text.len() > 1 || (d_row, d_col) == (0, 1)) {
+///                     expected.push((word, (row, col), (d_row, d_col)));
+///                 }
+///             }
+///         }
+///     }
+///     let mut found: Vec<_> = found.iter().map(|m| (m.word, m.start, m.direction)).collect();
+///     found.sort();
+///     expected.sort();
+///     assert_eq!(found, expected, "{:?} in {:?}", words, grid);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WordSearch<T> {
+    /// transitions of the trie
+    goto: Vec<HashMap<T, usize>>,
+    /// longest proper suffix of a state that is a state too
+    fail: Vec<usize>,
+    /// the words ending in a state, including those of its suffixes
+    outputs: Vec<Vec<usize>>,
+    lengths: Vec<usize>,
+}
+
+impl WordSearch<char> {
+    pub fn new(words: &[&str]) -> Self {
+        let words: Vec<Vec<char>> = words.iter().map(|word| word.chars().collect()).collect();
+        Self::from_words(&words)
+    }
+}
+
+impl<T: Clone + Eq + Hash> WordSearch<T> {
+    pub fn from_words(words: &[Vec<T>]) -> Self {
+        let mut search = WordSearch {
+            goto: vec![HashMap::new()],
+            fail: vec![0],
+            outputs: vec![Vec::new()],
+            lengths: words.iter().map(Vec::len).collect(),
+        };
+        for (index, word) in words.iter().enumerate() {
+            let mut state = 0;
+            for symbol in word {
+                state = match search.goto[state].get(symbol) {
+                    Some(&next) => next,
+                    None => {
+                        let next = search.goto.len();
+                        search.goto.push(HashMap::new());
+                        search.fail.push(0);
+                        search.outputs.push(Vec::new());
+                        search.goto[state].insert(symbol.clone(), next);
+                        next
+                    }
+                };
+            }
+            // an empty word would match everywhere, leave it out:
+            if !word.is_empty() {
+                search.outputs[state].push(index);
+            }
+        }
+        // failure links breadth first, so the ones of shorter prefixes are known:
+        let mut queue: VecDeque<usize> = search.goto[0].values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let transitions: Vec<(T, usize)> = search.goto[state]
+                .iter()
+                .map(|(symbol, &next)| (symbol.clone(), next))
+                .collect();
+            for (symbol, next) in transitions {
+                let fail = if state == 0 {
+                    0
+                } else {
+                    search.step(search.fail[state], &symbol)
+                };
+                search.fail[next] = fail;
+                let inherited = search.outputs[fail].clone();
+                search.outputs[next].extend(inherited);
+                queue.push_back(next);
+            }
+        }
+        search
+    }
+
+    fn step(&self, mut state: usize, symbol: &T) -> usize {
+        loop {
+            if let Some(&next) = self.goto[state].get(symbol) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    /// Find all words along the lines of the grid in all 8 directions.
+    /// Words of a single symbol are only reported in the direction (0, 1).
+    pub fn find(&self, grid: ArrayView2<T>) -> Vec<WordMatch> {
+        let (rows, cols) = grid.dim();
+        let inside = |(row, col): Offset| {
+            (0..rows as isize).contains(&row) && (0..cols as isize).contains(&col)
+        };
+        let mut matches = Vec::new();
+        for direction in DIRECTIONS {
+            // every line starts at a cell without a cell before it:
+            let starts = (0..rows as isize)
+                .flat_map(|row| (0..cols as isize).map(move |col| (row, col)))
+                .filter(|&(row, col)| !inside((row - direction.0, col - direction.1)));
+            for mut position in starts {
+                let mut state = 0;
+                while inside(position) {
+                    state = self.step(state, &grid[(position.0 as usize, position.1 as usize)]);
+                    for &word in &self.outputs[state] {
+                        let back = self.lengths[word] as isize - 1;
+                        if back == 0 && direction != (0, 1) {
+                            continue;
+                        }
+                        let start = (
+                            (position.0 - back * direction.0) as usize,
+                            (position.1 - back * direction.1) as usize,
+                        );
+                        matches.push(WordMatch {
+                            word,
+                            start,
+                            direction,
+                        });
+                    }
+                    position = (position.0 + direction.0, position.1 + direction.1);
+                }
+            }
+        }
+        matches
+    }
+}
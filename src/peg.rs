@@ -0,0 +1,785 @@
+//! Packrat parser for parsing expression grammars (PEG), with a small grammar language.
+//! Each rule result is memoized per input position, and captures are shared between results
+//! instead of copied, so parsing takes linear time.
+//! Left-recursive rules are supported by growing a seed like Warth et al.:
+//! the left-recursive call first fails, then the rule is evaluated again with the previous
+//! result memoized, as long as that matches more of the input (once per step of growing).
+//! Rules and captures nest up to [`MAX_NESTING`] and [`MAX_CAPTURE_NESTING`] levels,
+//! deeper input is a parse error rather than a stack overflow.
+//!
+//! Rules are written `name <- expression`, one per line (lines starting with whitespace continue
+//! the rule before, `#` outside of literals and classes comments out the rest of the line).
+//! Expressions are:
+//! - `"literal"` or `'literal'`, `[a-z_]` and `[^0-9]` character classes, `.` for any character
+//! - `rule` references, `( ... )` for grouping
+//! - sequences `a b`, ordered choices `a / b`
+//! - repetitions `a*`, `a+` and optional `a?`, all greedy
+//! - lookahead `&a` and negative lookahead `!a`, which do not consume any input
+//! - named captures `name:a`, which record the text matched by `a`, with the captures inside it
+//!
+//! A rule itself does not capture anything, the captures inside it belong to the capture around
+//! the rule reference (label it, like `policy:policy` below, to group them).
+//!
+//! ```
+//! use advent_of_code_202x::peg::Peg;
+//! let peg = Peg::new(r#"
+//! policies <- (policy:policy "\n"?)*
+//! policy <- min:num "-" max:num " " letter:[a-z] ": " password:[a-z]*
+//! num <- [0-9]+
+//! "#).unwrap();
+//! let input = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
+//! let parsed = peg.parse("policies", input).unwrap();
+//! let valid = parsed.all("policy").filter(|policy| {
+//!     let (min, max): (usize, usize) = (policy.value("min").unwrap(), policy.value("max").unwrap());
+//!     let letter: char = policy.value("letter").unwrap();
+//!     let count = policy.text_of("password").unwrap().chars().filter(|&c| c == letter).count();
+//!     (min..=max).contains(&count)
+//! });
+//! assert_eq!(valid.count(), 2);
+//! let error = peg.parse("policies", "1-3 a: abc\n1-x b: cd").unwrap_err();
+//! assert_eq!(error.to_string(), "line 2, column 3: expected [0-9]");
+//! ```
+//!
+//! Left recursion, for left-associative operators:
+//! ```
+//! use advent_of_code_202x::peg::{Capture, Peg};
+//! let peg = Peg::new(r#"
+//! sum <- left:sum op:[+-] right:num / num
+//! num <- value:[0-9]+
+//! "#).unwrap();
+//! fn eval(capture: &Capture) -> i64 {
+//!     match (capture.get("left"), capture.get("value")) {
+//!         (Some(left), _) => {
+//!             let right = eval(capture.get("right").unwrap());
+//!             if capture.text_of("op") == Some("+") { eval(left) + right } else { eval(left) - right }
+//!         }
+//!         (None, Some(value)) => value.parse().unwrap(),
+//!         // a left or right operand with only a number inside:
+//!         (None, None) => eval(&capture.children[0]),
+//!     }
+//! }
+//! let parsed = peg.parse("sum", "10-3-2+1").unwrap();
+//! assert_eq!(eval(&parsed), 6);
+//! // indirect left recursion, and lookahead:
+//! let peg = Peg::new("list <- item / 'x'\nitem <- list ',' !',' 'x'").unwrap();
+//! assert!(peg.matches("list", "x,x,x"));
+//! assert!(!peg.matches("list", "x,,x"));
+//! // each item nests one more rule application:
+//! let peg = Peg::new("list <- c:'x' ',' list / c:'x'").unwrap();
+//! assert_eq!(peg.parse("list", &["x"; 999].join(",")).unwrap().children.len(), 999);
+//! let error = peg.parse("list", &["x"; 100_000].join(",")).unwrap_err();
+//! assert_eq!(error.to_string(), "line 1, column 2001: expected at most 1000 nested rules");
+//! let error = Peg::new("a <- !'x' .").unwrap().parse("a", "x").unwrap_err();
+//! assert_eq!(error.to_string(), "line 1, column 1: expected not \"x\"");
+//! ```
+//!
+//! Comments, and `#` in classes and literals:
+//! ```
+//! use advent_of_code_202x::peg::Peg;
+//! let peg = Peg::new("# a row of tiles\nrow <- [.#]+ '!#' # ends with !#\n  # an optional newline:\n  '\\n'?").unwrap();
+//! assert!(peg.matches("row", ".#.!#\n"));
+//! assert!(!peg.matches("row", ".#.!"));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Index of a rule in the grammar
+pub type RuleId = usize;
+
+/// Rules applied inside each other are limited to this depth, so deeply nested input
+/// is an error instead of overflowing the stack (this fits the 8 MB stack of the main thread
+/// even in debug builds, threads with smaller stacks may need shallower input)
+pub const MAX_NESTING: usize = 1000;
+
+/// Captures inside each other are limited to this depth, they take much less stack
+/// than rules, and are nested deeper by left-recursive rules, which do not nest rule applications
+pub const MAX_CAPTURE_NESTING: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Literal(String),
+    /// character ranges, and whether the class is negated
+    Class(Vec<(char, char)>, bool),
+    Any,
+    Rule(RuleId),
+    Sequence(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Repeat(Box<Expr>, usize, Option<usize>),
+    And(Box<Expr>),
+    Not(Box<Expr>),
+    Capture(String, Box<Expr>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Literal(text) => write!(f, "{:?}", text),
+            Expr::Class(ranges, negated) => {
+                write!(f, "[{}", if *negated { "^" } else { "" })?;
+                for &(low, high) in ranges {
+                    if low == high {
+                        write!(f, "{}", low.escape_default())?;
+                    } else {
+                        write!(f, "{}-{}", low.escape_default(), high.escape_default())?;
+                    }
+                }
+                write!(f, "]")
+            }
+            Expr::Any => write!(f, "any character"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PegError {
+    /// a syntax error in the grammar, at the line (1-based) with a description
+    Syntax(usize, String),
+    UndefinedRule(String),
+    DuplicateRule(String),
+}
+
+impl fmt::Display for PegError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PegError::Syntax(line, message) => write!(f, "line {}: {}", line, message),
+            PegError::UndefinedRule(name) => write!(f, "undefined rule {}", name),
+            PegError::DuplicateRule(name) => write!(f, "rule {} is defined twice", name),
+        }
+    }
+}
+
+impl std::error::Error for PegError {}
+
+/// The input did not match, with the position where parsing got the farthest
+/// and what was expected there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: Vec<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}",
+            self.line,
+            self.column,
+            self.expected.join(" or ")
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A named capture could not be extracted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureError {
+    Missing(String),
+    /// the text of the capture did not parse into the requested type
+    Invalid(String, String),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureError::Missing(name) => write!(f, "no capture {}", name),
+            CaptureError::Invalid(name, text) => write!(f, "invalid {}: {:?}", name, text),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+/// The text matched by a named capture (or the whole input for the start rule),
+/// with the captures directly inside it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture<'i> {
+    pub name: String,
+    pub text: &'i str,
+    /// byte offset of the text in the input
+    pub start: usize,
+    pub children: Vec<Capture<'i>>,
+}
+
+impl<'i> Capture<'i> {
+    /// Return the first capture with the name directly inside this one
+    pub fn get(&self, name: &str) -> Option<&Capture<'i>> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Iterate over all captures with the name directly inside this one
+    pub fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Capture<'i>> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn text_of(&self, name: &str) -> Option<&'i str> {
+        self.get(name).map(|capture| capture.text)
+    }
+
+    /// Parse the text of this capture
+    pub fn parse<T: FromStr>(&self) -> Result<T, CaptureError> {
+        self.text
+            .parse()
+            .map_err(|_| CaptureError::Invalid(self.name.clone(), self.text.to_owned()))
+    }
+
+    /// Parse the text of the first capture with the name
+    pub fn value<T: FromStr>(&self, name: &str) -> Result<T, CaptureError> {
+        self.get(name)
+            .ok_or_else(|| CaptureError::Missing(name.to_owned()))?
+            .parse()
+    }
+
+    /// Parse the texts of all captures with the name
+    pub fn values<T: FromStr>(&self, name: &str) -> Result<Vec<T>, CaptureError> {
+        self.all(name).map(Capture::parse).collect()
+    }
+}
+
+/// A compiled grammar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Peg {
+    names: Vec<String>,
+    ids: HashMap<String, RuleId>,
+    rules: Vec<Option<Expr>>,
+}
+
+/// Parser for the grammar language, for one rule body
+struct DslParser<'a> {
+    peg: &'a mut Peg,
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl DslParser<'_> {
+    fn error<T>(&self, message: &str) -> Result<T, PegError> {
+        Err(PegError::Syntax(self.line, message.to_owned()))
+    }
+
+    /// Skip whitespace and comments, which are never inside literals or classes here
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.get(self.pos) {
+            if c == '#' {
+                while self.chars.get(self.pos).is_some_and(|&c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c.is_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|&c| c.is_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| self.chars[start..self.pos].iter().collect())
+    }
+
+    /// a character in a literal or class, with escapes
+    fn escaped_char(&mut self) -> Result<char, PegError> {
+        let Some(&c) = self.chars.get(self.pos) else {
+            return self.error("unexpected end of rule");
+        };
+        self.pos += 1;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let Some(&escaped) = self.chars.get(self.pos) else {
+            return self.error("unexpected end of rule");
+        };
+        self.pos += 1;
+        Ok(match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            other => other,
+        })
+    }
+
+    fn choice(&mut self) -> Result<Expr, PegError> {
+        let mut choices = vec![self.sequence()?];
+        while self.peek() == Some('/') {
+            self.pos += 1;
+            choices.push(self.sequence()?);
+        }
+        Ok(if choices.len() == 1 {
+            choices.pop().unwrap()
+        } else {
+            Expr::Choice(choices)
+        })
+    }
+
+    fn sequence(&mut self) -> Result<Expr, PegError> {
+        let mut items = Vec::new();
+        while self.peek().is_some_and(|c| c != '/' && c != ')') {
+            items.push(self.prefixed()?);
+        }
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Expr::Sequence(items)
+        })
+    }
+
+    fn prefixed(&mut self) -> Result<Expr, PegError> {
+        match self.peek() {
+            Some('&') => {
+                self.pos += 1;
+                Ok(Expr::And(Box::new(self.labeled()?)))
+            }
+            Some('!') => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.labeled()?)))
+            }
+            _ => self.labeled(),
+        }
+    }
+
+    fn labeled(&mut self) -> Result<Expr, PegError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if let Some(label) = self.identifier() {
+            if self.chars.get(self.pos) == Some(&':') {
+                self.pos += 1;
+                return Ok(Expr::Capture(label, Box::new(self.suffixed()?)));
+            }
+        }
+        self.pos = start;
+        self.suffixed()
+    }
+
+    fn suffixed(&mut self) -> Result<Expr, PegError> {
+        let mut expr = self.primary()?;
+        loop {
+            let (min, max) = match self.chars.get(self.pos) {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Repeat(Box::new(expr), min, max);
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, PegError> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                let mut text = String::new();
+                while self.chars.get(self.pos) != Some(&quote) {
+                    text.push(self.escaped_char()?);
+                }
+                self.pos += 1;
+                Ok(Expr::Literal(text))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let negated = self.chars.get(self.pos) == Some(&'^');
+                if negated {
+                    self.pos += 1;
+                }
+                let mut ranges = Vec::new();
+                while self.chars.get(self.pos) != Some(&']') {
+                    let low = self.escaped_char()?;
+                    let high = if self.chars.get(self.pos) == Some(&'-')
+                        && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']')
+                    {
+                        self.pos += 1;
+                        self.escaped_char()?
+                    } else {
+                        low
+                    };
+                    ranges.push((low, high));
+                }
+                self.pos += 1;
+                Ok(Expr::Class(ranges, negated))
+            }
+            Some('.') => {
+                self.pos += 1;
+                Ok(Expr::Any)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.choice()?;
+                if self.peek() != Some(')') {
+                    return self.error("missing )");
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(_) => match self.identifier() {
+                Some(name) => Ok(Expr::Rule(self.peg.rule_id(&name))),
+                None => self.error(&format!("unexpected {:?}", self.chars[self.pos])),
+            },
+            None => self.error("unexpected end of rule"),
+        }
+    }
+}
+
+impl Peg {
+    /// Compile the rules, and check that all referenced rules are defined
+    pub fn new(grammar: &str) -> Result<Self, PegError> {
+        let mut peg = Peg::default();
+        // rules with their line numbers, joined with their continuation lines:
+        let mut definitions: Vec<(usize, String)> = Vec::new();
+        for (index, line) in grammar.lines().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            match definitions.last_mut() {
+                Some((_, definition)) if line.starts_with(char::is_whitespace) => {
+                    definition.push('\n');
+                    definition.push_str(line);
+                }
+                _ => definitions.push((index + 1, line.to_owned())),
+            }
+        }
+        for (line, definition) in definitions {
+            let Some((name, body)) = definition.split_once("<-") else {
+                return Err(PegError::Syntax(line, "missing <-".to_owned()));
+            };
+            let name = name.trim();
+            let id = peg.rule_id(name);
+            if peg.rules[id].is_some() {
+                return Err(PegError::DuplicateRule(name.to_owned()));
+            }
+            let mut parser = DslParser {
+                peg: &mut peg,
+                chars: body.chars().collect(),
+                pos: 0,
+                line,
+            };
+            let expr = parser.choice()?;
+            if parser.peek().is_some() {
+                return parser.error("unmatched )");
+            }
+            peg.rules[id] = Some(expr);
+        }
+        if let Some(id) = peg.rules.iter().position(Option::is_none) {
+            return Err(PegError::UndefinedRule(peg.names[id].clone()));
+        }
+        Ok(peg)
+    }
+
+    /// Return the id of the rule name, adding the name if it is new
+    fn rule_id(&mut self, name: &str) -> RuleId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        self.rules.push(None);
+        id
+    }
+
+    /// Parse the whole input with the rule, return a capture with the rule's name for all of it
+    pub fn parse<'i>(&self, rule: &str, input: &'i str) -> Result<Capture<'i>, ParseError> {
+        let Some(&id) = self.ids.get(rule) else {
+            return Err(ParseError {
+                line: 1,
+                column: 1,
+                expected: vec![format!("a rule named {}", rule)],
+            });
+        };
+        let mut run = ParseRun {
+            peg: self,
+            input,
+            memo: vec![HashMap::new(); input.len() + 1],
+            nodes: Vec::new(),
+            nesting: 0,
+            too_deep: None,
+            farthest: 0,
+            expected: Vec::new(),
+        };
+        let result = run.apply(id, 0);
+        if let Some(pos) = run.too_deep {
+            run.farthest = pos;
+            run.expected = vec![format!("at most {} nested rules", MAX_NESTING)];
+            return Err(run.error());
+        }
+        match result {
+            Some((end, captures)) if end == input.len() => match run.build(captures, 1) {
+                Some(children) => Ok(Capture {
+                    name: rule.to_owned(),
+                    text: input,
+                    start: 0,
+                    children,
+                }),
+                None => Err(ParseError {
+                    line: 1,
+                    column: 1,
+                    expected: vec![format!("at most {} nested captures", MAX_CAPTURE_NESTING)],
+                }),
+            },
+            result => {
+                if let Some((end, _)) = result {
+                    run.fail(end, "end of input".to_owned());
+                }
+                Err(run.error())
+            }
+        }
+    }
+
+    /// Return true iff the rule matches the whole input
+    pub fn matches(&self, rule: &str, input: &str) -> bool {
+        self.parse(rule, input).is_ok()
+    }
+}
+
+/// The captures of a match, as a node of the run's capture arena (None if there are none)
+type Captures = Option<usize>;
+
+/// The end of a match and its captures
+type Match = Option<(usize, Captures)>;
+
+/// Captures are shared between matches instead of copied, so memoized results
+/// and concatenations of captures take constant time
+#[derive(Debug, Clone, Copy)]
+enum CaptureNode<'p> {
+    Capture {
+        name: &'p str,
+        start: usize,
+        end: usize,
+        children: Captures,
+    },
+    /// the captures of one match followed by those of another
+    Concat(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MemoEntry {
+    /// the rule is being evaluated at the position, and whether it called itself there
+    InProgress(bool),
+    Done(Match),
+}
+
+/// Memoized evaluation of the rules on one input
+struct ParseRun<'p, 'i> {
+    peg: &'p Peg,
+    input: &'i str,
+    /// rule results for each position
+    memo: Vec<HashMap<RuleId, MemoEntry>>,
+    nodes: Vec<CaptureNode<'p>>,
+    /// the number of rules being applied, limited by MAX_NESTING
+    nesting: usize,
+    /// the position where the nesting limit was reached, all rules fail after that
+    too_deep: Option<usize>,
+    farthest: usize,
+    expected: Vec<String>,
+}
+
+impl<'p, 'i> ParseRun<'p, 'i> {
+    fn fail(&mut self, pos: usize, expected: String) {
+        if pos > self.farthest {
+            self.farthest = pos;
+            self.expected.clear();
+        }
+        if pos == self.farthest && !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
+    }
+
+    fn concat(&mut self, first: Captures, second: Captures) -> Captures {
+        match (first, second) {
+            (Some(first), Some(second)) => {
+                self.nodes.push(CaptureNode::Concat(first, second));
+                Some(self.nodes.len() - 1)
+            }
+            (first, None) => first,
+            (None, second) => second,
+        }
+    }
+
+    /// Build the captures in order, with the depth of the capture they are in,
+    /// return None if they are nested more than MAX_CAPTURE_NESTING deep
+    fn build(&self, captures: Captures, depth: usize) -> Option<Vec<Capture<'i>>> {
+        let mut built = Vec::new();
+        // concatenations can be as long as the input, so they are not walked recursively:
+        let mut stack: Vec<usize> = captures.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            match self.nodes[node] {
+                CaptureNode::Concat(first, second) => stack.extend([second, first]),
+                CaptureNode::Capture {
+                    name,
+                    start,
+                    end,
+                    children,
+                } => {
+                    if depth == MAX_CAPTURE_NESTING {
+                        return None;
+                    }
+                    built.push(Capture {
+                        name: name.to_owned(),
+                        text: &self.input[start..end],
+                        start,
+                        children: self.build(children, depth + 1)?,
+                    });
+                }
+            }
+        }
+        Some(built)
+    }
+
+    fn error(&self) -> ParseError {
+        let before = &self.input[..self.farthest];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+        ParseError {
+            line,
+            column,
+            expected: self.expected.clone(),
+        }
+    }
+
+    /// Apply a rule with memoization, growing the result of left-recursive rules
+    fn apply(&mut self, rule: RuleId, pos: usize) -> Match {
+        match self.memo[pos].get_mut(&rule) {
+            Some(MemoEntry::Done(result)) => return *result,
+            Some(MemoEntry::InProgress(left_recursive)) => {
+                *left_recursive = true;
+                return None;
+            }
+            None => {}
+        }
+        if self.nesting == MAX_NESTING {
+            self.too_deep.get_or_insert(pos);
+        }
+        if self.too_deep.is_some() {
+            return None;
+        }
+        let peg = self.peg;
+        let body = peg.rules[rule].as_ref().unwrap();
+        self.memo[pos].insert(rule, MemoEntry::InProgress(false));
+        self.nesting += 1;
+        let mut result = self.eval(body, pos);
+        if let Some(MemoEntry::InProgress(true)) = self.memo[pos].get(&rule) {
+            loop {
+                self.memo[pos].insert(rule, MemoEntry::Done(result));
+                // results of other rules here may depend on the seed:
+                self.memo[pos].retain(|&other, entry| {
+                    other == rule || matches!(entry, MemoEntry::InProgress(_))
+                });
+                let next = self.eval(body, pos);
+                let longer = match (&next, &result) {
+                    (Some((next_end, _)), Some((end, _))) => next_end > end,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                };
+                if !longer {
+                    break;
+                }
+                result = next;
+            }
+        }
+        self.nesting -= 1;
+        self.memo[pos].insert(rule, MemoEntry::Done(result));
+        result
+    }
+
+    fn eval(&mut self, expr: &'p Expr, pos: usize) -> Match {
+        let rest = &self.input[pos..];
+        match expr {
+            Expr::Literal(text) => {
+                if rest.starts_with(text.as_str()) {
+                    Some((pos + text.len(), None))
+                } else {
+                    self.fail(pos, expr.to_string());
+                    None
+                }
+            }
+            Expr::Class(ranges, negated) => {
+                let matched = rest.chars().next().filter(|&c| {
+                    ranges.iter().any(|&(low, high)| (low..=high).contains(&c)) != *negated
+                });
+                match matched {
+                    Some(c) => Some((pos + c.len_utf8(), None)),
+                    None => {
+                        self.fail(pos, expr.to_string());
+                        None
+                    }
+                }
+            }
+            Expr::Any => match rest.chars().next() {
+                Some(c) => Some((pos + c.len_utf8(), None)),
+                None => {
+                    self.fail(pos, expr.to_string());
+                    None
+                }
+            },
+            Expr::Rule(rule) => self.apply(*rule, pos),
+            Expr::Sequence(items) => {
+                let mut end = pos;
+                let mut captures = None;
+                for item in items {
+                    let (next, inner) = self.eval(item, end)?;
+                    end = next;
+                    captures = self.concat(captures, inner);
+                }
+                Some((end, captures))
+            }
+            Expr::Choice(choices) => choices.iter().find_map(|choice| self.eval(choice, pos)),
+            Expr::Repeat(item, min, max) => {
+                let mut end = pos;
+                let mut captures = None;
+                let mut count = 0;
+                while max.is_none_or(|max| count < max) {
+                    let Some((next, inner)) = self.eval(item, end) else {
+                        break;
+                    };
+                    count += 1;
+                    captures = self.concat(captures, inner);
+                    // an empty match would repeat forever:
+                    if next == end {
+                        break;
+                    }
+                    end = next;
+                }
+                (count >= *min).then_some((end, captures))
+            }
+            Expr::And(item) => self.eval(item, pos).map(|_| (pos, None)),
+            Expr::Not(item) => match self.eval(item, pos) {
+                Some(_) => {
+                    let description = match **item {
+                        Expr::Rule(rule) => self.peg.names[rule].clone(),
+                        _ => item.to_string(),
+                    };
+                    self.fail(pos, format!("not {}", description));
+                    None
+                }
+                None => Some((pos, None)),
+            },
+            Expr::Capture(name, item) => {
+                let (end, children) = self.eval(item, pos)?;
+                self.nodes.push(CaptureNode::Capture {
+                    name,
+                    start: pos,
+                    end,
+                    children,
+                });
+                Some((end, Some(self.nodes.len() - 1)))
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Helpers for the randomized property tests in the doctests:
+//! a seeded xorshift generator, so each run checks the same cases without a `rand` dependency.
+//!
+//! ```
+//! use advent_of_code_202x::testing::XorShift;
+//! let mut rng = XorShift::default();
+//! let values: Vec<u64> = (0..1000).map(|_| rng.below(6)).collect();
+//! assert!((0..6).all(|value| values.contains(&value)));
+//! assert!((0..100).all(|_| (-3..=3).contains(&rng.range(-3, 3))));
+//! // the same seed gives the same sequence:
+//! assert_eq!(XorShift::new(7).next_u64(), XorShift::new(7).next_u64());
+//! ```
+
+/// Marsaglia's 64 bit xorshift generator, with shifts 13, 7 and 17
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl Default for XorShift {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+impl XorShift {
+    pub const DEFAULT_SEED: u64 = 0x2545f4914f6cdd1d;
+
+    /// The seed must not be 0, which would only ever give 0, the default seed is used instead
+    pub fn new(seed: u64) -> Self {
+        let state = if seed == 0 { Self::DEFAULT_SEED } else { seed };
+        Self { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..limit`
+    pub fn below(&mut self, limit: u64) -> u64 {
+        self.next_u64() % limit
+    }
+
+    /// An index in `0..len`
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    /// A number in `low..=high`
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + self.below((high - low + 1) as u64) as i64
+    }
+}